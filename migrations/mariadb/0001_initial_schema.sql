@@ -0,0 +1,21 @@
+-- The tables from the original schema.sql. Libraries created by hand with that file
+-- already have these, so this migration only records them as version 1.
+
+CREATE TABLE IF NOT EXISTS Artists(
+  name VARCHAR(60) not null PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS Albums(
+  id int unsigned not null auto_increment primary key,
+  title VARCHAR(60) not null,
+  artist VARCHAR(200) not null,
+  release_year INT not null,
+  media_type VARCHAR(60) not null);
+
+CREATE TABLE IF NOT EXISTS Songs(
+  id int unsigned not null auto_increment primary key,
+  title VARCHAR(60) not null,
+  artist VARCHAR(200) not null,
+  album VARCHAR(1000) not null,
+  release_year INT not null,
+  media_type VARCHAR(60) not null);
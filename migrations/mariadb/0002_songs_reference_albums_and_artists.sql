@@ -0,0 +1,70 @@
+-- Songs used to store the artist and album as free text, so every typo made a new "artist".
+-- This rebuilds the tables so Artists and Albums are the authoritative lists and Songs point at them by id.
+-- Names that only differ by case or surrounding whitespace are merged into one row,
+-- preferring the spelling already in Artists/Albums over the one typed into a song.
+
+RENAME TABLE Artists TO Artists_old, Albums TO Albums_old, Songs TO Songs_old;
+
+CREATE TABLE Artists(
+  id int unsigned not null auto_increment primary key,
+  name VARCHAR(200) not null,
+  UNIQUE KEY artist_name (name));
+
+CREATE TABLE Albums(
+  id int unsigned not null auto_increment primary key,
+  title VARCHAR(200) not null,
+  artist_id int unsigned not null,
+  release_year INT not null,
+  media_type VARCHAR(60) not null,
+  UNIQUE KEY album_title (artist_id, title),
+  FOREIGN KEY (artist_id) REFERENCES Artists(id));
+
+CREATE TABLE Songs(
+  id int unsigned not null auto_increment primary key,
+  title VARCHAR(60) not null,
+  artist_id int unsigned not null,
+  album_id int unsigned not null,
+  release_year INT not null,
+  media_type VARCHAR(60) not null,
+  FOREIGN KEY (artist_id) REFERENCES Artists(id),
+  FOREIGN KEY (album_id) REFERENCES Albums(id));
+
+-- artists: the Artists table first, then any new names from albums, then from songs
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(name)) FROM Artists_old
+  GROUP BY LOWER(TRIM(name));
+
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(artist)) FROM Albums_old
+  WHERE LOWER(TRIM(artist)) NOT IN (SELECT LOWER(name) FROM Artists)
+  GROUP BY LOWER(TRIM(artist));
+
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(artist)) FROM Songs_old
+  WHERE LOWER(TRIM(artist)) NOT IN (SELECT LOWER(name) FROM Artists)
+  GROUP BY LOWER(TRIM(artist));
+
+-- albums: the Albums table first, then any album only mentioned by a song
+INSERT INTO Albums (title, artist_id, release_year, media_type)
+  SELECT MIN(TRIM(al.title)), ar.id, MIN(al.release_year), MIN(al.media_type)
+  FROM Albums_old al
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(al.artist))
+  GROUP BY ar.id, LOWER(TRIM(al.title));
+
+INSERT INTO Albums (title, artist_id, release_year, media_type)
+  SELECT MIN(TRIM(s.album)), ar.id, MIN(s.release_year), MIN(s.media_type)
+  FROM Songs_old s
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(s.artist))
+  WHERE NOT EXISTS (
+    SELECT 1 FROM Albums al
+    WHERE al.artist_id = ar.id AND LOWER(al.title) = LOWER(TRIM(s.album)))
+  GROUP BY ar.id, LOWER(TRIM(s.album));
+
+-- songs keep their ids
+INSERT INTO Songs (id, title, artist_id, album_id, release_year, media_type)
+  SELECT s.id, s.title, ar.id, al.id, s.release_year, s.media_type
+  FROM Songs_old s
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(s.artist))
+  JOIN Albums al ON al.artist_id = ar.id AND LOWER(al.title) = LOWER(TRIM(s.album));
+
+DROP TABLE Songs_old, Albums_old, Artists_old;
@@ -0,0 +1,11 @@
+-- Audio files imported by a folder scan, so a rescan only reads the files that changed since.
+-- song_id is cleared if the song is deleted, so the scan doesn't bring it back while the file is unchanged.
+
+CREATE TABLE ScannedFiles(
+  id int unsigned not null auto_increment primary key,
+  path VARCHAR(700) not null,
+  song_id int unsigned,
+  modified BIGINT not null,
+  size BIGINT not null,
+  UNIQUE KEY scanned_path (path),
+  FOREIGN KEY (song_id) REFERENCES Songs(id) ON DELETE SET NULL);
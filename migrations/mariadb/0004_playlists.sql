@@ -0,0 +1,17 @@
+-- Playlists are named, ordered lists of songs. A song can be on a playlist more than once,
+-- so each entry has its own id. Deleting a song or a playlist deletes its entries with it.
+
+CREATE TABLE Playlists(
+  id int unsigned not null auto_increment primary key,
+  name VARCHAR(200) not null,
+  description VARCHAR(500) not null default '',
+  UNIQUE KEY playlist_name (name));
+
+CREATE TABLE PlaylistSongs(
+  id int unsigned not null auto_increment primary key,
+  playlist_id int unsigned not null,
+  song_id int unsigned not null,
+  position int not null,
+  KEY playlist_position (playlist_id, position),
+  FOREIGN KEY (playlist_id) REFERENCES Playlists(id) ON DELETE CASCADE,
+  FOREIGN KEY (song_id) REFERENCES Songs(id) ON DELETE CASCADE);
@@ -0,0 +1,29 @@
+-- Plain search words used to be five '%word%' LIKEs, which can't use an index.
+-- search_text holds every field a plain word can match, so one FULLTEXT index covers them all.
+-- Triggers keep it up to date, also when an artist or album is renamed.
+
+ALTER TABLE Songs ADD COLUMN search_text VARCHAR(600) not null default '';
+
+CREATE TRIGGER songs_search_text_insert BEFORE INSERT ON Songs FOR EACH ROW
+  SET NEW.search_text = CONCAT_WS(' ', NEW.title,
+    (SELECT name FROM Artists WHERE id = NEW.artist_id),
+    (SELECT title FROM Albums WHERE id = NEW.album_id),
+    NEW.release_year, NEW.media_type);
+
+CREATE TRIGGER songs_search_text_update BEFORE UPDATE ON Songs FOR EACH ROW
+  SET NEW.search_text = CONCAT_WS(' ', NEW.title,
+    (SELECT name FROM Artists WHERE id = NEW.artist_id),
+    (SELECT title FROM Albums WHERE id = NEW.album_id),
+    NEW.release_year, NEW.media_type);
+
+-- touching the songs makes the update trigger above read the new name
+CREATE TRIGGER artists_search_text AFTER UPDATE ON Artists FOR EACH ROW
+  UPDATE Songs SET search_text = '' WHERE artist_id = NEW.id AND NEW.name <> OLD.name;
+
+CREATE TRIGGER albums_search_text AFTER UPDATE ON Albums FOR EACH ROW
+  UPDATE Songs SET search_text = '' WHERE album_id = NEW.id AND NEW.title <> OLD.title;
+
+-- fill in the songs already in the library
+UPDATE Songs SET search_text = '';
+
+ALTER TABLE Songs ADD FULLTEXT INDEX song_search (search_text);
@@ -0,0 +1,18 @@
+-- Every song added, edited or deleted, so the change can be undone, even after a restart.
+-- old_ columns are NULL for an added song and new_ columns for a deleted one.
+-- undone marks the changes that were undone, which can be redone until the next change clears them.
+
+CREATE TABLE Journal(
+  id int unsigned not null auto_increment primary key,
+  song_id int unsigned not null,
+  old_title VARCHAR(60),
+  old_artist VARCHAR(200),
+  old_album VARCHAR(200),
+  old_release_year int,
+  old_media_type VARCHAR(60),
+  new_title VARCHAR(60),
+  new_artist VARCHAR(200),
+  new_album VARCHAR(200),
+  new_release_year int,
+  new_media_type VARCHAR(60),
+  undone BOOLEAN not null default false);
@@ -0,0 +1,21 @@
+-- Every song added, edited or deleted, with when and by whom, kept for good.
+-- changed_at is "YYYY-MM-DD HH:MM:SS" in UTC, written by the app so both backends store the same text.
+-- old_ columns are NULL for an added song and new_ columns for a deleted one, like the Journal's.
+
+CREATE TABLE AuditLog(
+  id int unsigned not null auto_increment primary key,
+  song_id int unsigned not null,
+  changed_at CHAR(19) not null,
+  changed_by VARCHAR(100) not null,
+  old_title VARCHAR(60),
+  old_artist VARCHAR(200),
+  old_album VARCHAR(200),
+  old_release_year int,
+  old_media_type VARCHAR(60),
+  new_title VARCHAR(60),
+  new_artist VARCHAR(200),
+  new_album VARCHAR(200),
+  new_release_year int,
+  new_media_type VARCHAR(60),
+  INDEX audit_song (song_id),
+  INDEX audit_time (changed_at));
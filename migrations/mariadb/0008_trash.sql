@@ -0,0 +1,7 @@
+-- Deleting a song moves it to the trash instead of removing the row, so it can be restored.
+-- deleted_at is when, as "YYYY-MM-DD HH:MM:SS" in UTC like AuditLog.changed_at; NULL for songs that aren't in the trash.
+-- Songs in the trash are purged for good once they've been there longer than the configured retention period.
+
+ALTER TABLE Songs
+  ADD COLUMN deleted_at CHAR(19) NULL,
+  ADD INDEX songs_deleted (deleted_at);
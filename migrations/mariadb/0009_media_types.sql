@@ -0,0 +1,69 @@
+-- Media types used to be free text, so the same thing was filed as "Vinyl", "vinyl", "LP" and "12in".
+-- MediaTypes is now the managed list songs and albums pick their media type from, and they keep storing its name.
+-- format groups the types (vinyl, cd, cassette, digital or other) and says which details apply:
+-- speed and size for vinyl, bitrate and codec for digital. Details are optional.
+-- MediaTypeAliases are other spellings of a type, which are filed under it when a song is saved with them.
+
+CREATE TABLE MediaTypes(
+  id int unsigned not null auto_increment primary key,
+  name VARCHAR(60) not null,
+  format VARCHAR(20) not null,
+  speed VARCHAR(60) null,
+  size VARCHAR(60) null,
+  bitrate VARCHAR(60) null,
+  codec VARCHAR(60) null,
+  UNIQUE KEY media_type_name (name));
+
+CREATE TABLE MediaTypeAliases(
+  alias VARCHAR(60) not null primary key,
+  media_type_id int unsigned not null,
+  FOREIGN KEY (media_type_id) REFERENCES MediaTypes(id) ON DELETE CASCADE);
+
+-- the usual types, including the ones folder scans file tracks under
+INSERT INTO MediaTypes (name, format, speed, size, bitrate, codec) VALUES
+  ('Vinyl', 'vinyl', '33 1/3 rpm', '12"', NULL, NULL),
+  ('7" Single', 'vinyl', '45 rpm', '7"', NULL, NULL),
+  ('CD', 'cd', NULL, NULL, NULL, NULL),
+  ('Cassette', 'cassette', NULL, NULL, NULL, NULL),
+  ('FLAC', 'digital', NULL, NULL, NULL, 'FLAC'),
+  ('Digital Download', 'digital', NULL, NULL, NULL, NULL);
+
+INSERT INTO MediaTypeAliases (alias, media_type_id) VALUES
+  ('LP', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12in', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12"', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12 inch', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('Record', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('7in', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('7"', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('7 inch', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('45', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('Compact Disc', (SELECT id FROM MediaTypes WHERE name = 'CD')),
+  ('Tape', (SELECT id FROM MediaTypes WHERE name = 'Cassette')),
+  ('MC', (SELECT id FROM MediaTypes WHERE name = 'Cassette')),
+  ('Digital', (SELECT id FROM MediaTypes WHERE name = 'Digital Download')),
+  ('Download', (SELECT id FROM MediaTypes WHERE name = 'Digital Download')),
+  ('MP3', (SELECT id FROM MediaTypes WHERE name = 'Digital Download'));
+
+-- every other value in use becomes a type of its own, values that only differ by case or surrounding
+-- whitespace becoming one type. `media-types map` files them under another type afterwards.
+INSERT INTO MediaTypes (name, format)
+  SELECT MIN(TRIM(used.media_type)), 'other'
+  FROM (SELECT media_type FROM Songs UNION ALL SELECT media_type FROM Albums) used
+  WHERE TRIM(used.media_type) <> ''
+    AND LOWER(TRIM(used.media_type)) NOT IN (SELECT LOWER(name) FROM MediaTypes)
+    AND LOWER(TRIM(used.media_type)) NOT IN (SELECT LOWER(alias) FROM MediaTypeAliases)
+  GROUP BY LOWER(TRIM(used.media_type));
+
+-- songs and albums take the name of their type, by its name first and then by an alias
+UPDATE Songs SET media_type = COALESCE(
+  (SELECT mt.name FROM MediaTypes mt WHERE LOWER(mt.name) = LOWER(TRIM(Songs.media_type))),
+  (SELECT mt.name FROM MediaTypeAliases a JOIN MediaTypes mt ON mt.id = a.media_type_id
+   WHERE LOWER(a.alias) = LOWER(TRIM(Songs.media_type))),
+  media_type);
+
+UPDATE Albums SET media_type = COALESCE(
+  (SELECT mt.name FROM MediaTypes mt WHERE LOWER(mt.name) = LOWER(TRIM(Albums.media_type))),
+  (SELECT mt.name FROM MediaTypeAliases a JOIN MediaTypes mt ON mt.id = a.media_type_id
+   WHERE LOWER(a.alias) = LOWER(TRIM(Albums.media_type))),
+  media_type);
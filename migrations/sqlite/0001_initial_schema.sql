@@ -0,0 +1,20 @@
+-- Same tables as migrations/mariadb/0001_initial_schema.sql, in SQLite's dialect
+
+CREATE TABLE IF NOT EXISTS Artists(
+  name VARCHAR(60) NOT NULL PRIMARY KEY
+);
+
+CREATE TABLE IF NOT EXISTS Albums(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR(60) NOT NULL,
+  artist VARCHAR(200) NOT NULL,
+  release_year INT NOT NULL,
+  media_type VARCHAR(60) NOT NULL);
+
+CREATE TABLE IF NOT EXISTS Songs(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR(60) NOT NULL,
+  artist VARCHAR(200) NOT NULL,
+  album VARCHAR(1000) NOT NULL,
+  release_year INT NOT NULL,
+  media_type VARCHAR(60) NOT NULL);
@@ -0,0 +1,67 @@
+-- Same as migrations/mariadb/0002_songs_reference_albums_and_artists.sql, in SQLite's dialect
+
+ALTER TABLE Artists RENAME TO Artists_old;
+ALTER TABLE Albums RENAME TO Albums_old;
+ALTER TABLE Songs RENAME TO Songs_old;
+
+CREATE TABLE Artists(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR(200) NOT NULL UNIQUE COLLATE NOCASE);
+
+CREATE TABLE Albums(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR(200) NOT NULL COLLATE NOCASE,
+  artist_id INTEGER NOT NULL REFERENCES Artists(id),
+  release_year INT NOT NULL,
+  media_type VARCHAR(60) NOT NULL,
+  UNIQUE (artist_id, title));
+
+CREATE TABLE Songs(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  title VARCHAR(60) NOT NULL,
+  artist_id INTEGER NOT NULL REFERENCES Artists(id),
+  album_id INTEGER NOT NULL REFERENCES Albums(id),
+  release_year INT NOT NULL,
+  media_type VARCHAR(60) NOT NULL);
+
+-- artists: the Artists table first, then any new names from albums, then from songs
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(name)) FROM Artists_old
+  GROUP BY LOWER(TRIM(name));
+
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(artist)) FROM Albums_old
+  WHERE LOWER(TRIM(artist)) NOT IN (SELECT LOWER(name) FROM Artists)
+  GROUP BY LOWER(TRIM(artist));
+
+INSERT INTO Artists (name)
+  SELECT MIN(TRIM(artist)) FROM Songs_old
+  WHERE LOWER(TRIM(artist)) NOT IN (SELECT LOWER(name) FROM Artists)
+  GROUP BY LOWER(TRIM(artist));
+
+-- albums: the Albums table first, then any album only mentioned by a song
+INSERT INTO Albums (title, artist_id, release_year, media_type)
+  SELECT MIN(TRIM(al.title)), ar.id, MIN(al.release_year), MIN(al.media_type)
+  FROM Albums_old al
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(al.artist))
+  GROUP BY ar.id, LOWER(TRIM(al.title));
+
+INSERT INTO Albums (title, artist_id, release_year, media_type)
+  SELECT MIN(TRIM(s.album)), ar.id, MIN(s.release_year), MIN(s.media_type)
+  FROM Songs_old s
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(s.artist))
+  WHERE NOT EXISTS (
+    SELECT 1 FROM Albums al
+    WHERE al.artist_id = ar.id AND LOWER(al.title) = LOWER(TRIM(s.album)))
+  GROUP BY ar.id, LOWER(TRIM(s.album));
+
+-- songs keep their ids
+INSERT INTO Songs (id, title, artist_id, album_id, release_year, media_type)
+  SELECT s.id, s.title, ar.id, al.id, s.release_year, s.media_type
+  FROM Songs_old s
+  JOIN Artists ar ON LOWER(ar.name) = LOWER(TRIM(s.artist))
+  JOIN Albums al ON al.artist_id = ar.id AND LOWER(al.title) = LOWER(TRIM(s.album));
+
+DROP TABLE Songs_old;
+DROP TABLE Albums_old;
+DROP TABLE Artists_old;
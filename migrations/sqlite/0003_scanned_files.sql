@@ -0,0 +1,8 @@
+-- Same as migrations/mariadb/0003_scanned_files.sql, in SQLite's dialect
+
+CREATE TABLE ScannedFiles(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  path VARCHAR(700) NOT NULL UNIQUE,
+  song_id INTEGER REFERENCES Songs(id) ON DELETE SET NULL,
+  modified BIGINT NOT NULL,
+  size BIGINT NOT NULL);
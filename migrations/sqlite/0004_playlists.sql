@@ -0,0 +1,14 @@
+-- Same as migrations/mariadb/0004_playlists.sql, in SQLite's dialect
+
+CREATE TABLE Playlists(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR(200) NOT NULL UNIQUE COLLATE NOCASE,
+  description VARCHAR(500) NOT NULL DEFAULT '');
+
+CREATE TABLE PlaylistSongs(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  playlist_id INTEGER NOT NULL REFERENCES Playlists(id) ON DELETE CASCADE,
+  song_id INTEGER NOT NULL REFERENCES Songs(id) ON DELETE CASCADE,
+  position INT NOT NULL);
+
+CREATE INDEX playlist_position ON PlaylistSongs (playlist_id, position);
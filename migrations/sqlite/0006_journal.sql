@@ -0,0 +1,16 @@
+-- Same as migrations/mariadb/0006_journal.sql, in SQLite's dialect
+
+CREATE TABLE Journal(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  song_id INTEGER NOT NULL,
+  old_title VARCHAR(60),
+  old_artist VARCHAR(200),
+  old_album VARCHAR(200),
+  old_release_year INT,
+  old_media_type VARCHAR(60),
+  new_title VARCHAR(60),
+  new_artist VARCHAR(200),
+  new_album VARCHAR(200),
+  new_release_year INT,
+  new_media_type VARCHAR(60),
+  undone BOOLEAN NOT NULL DEFAULT 0);
@@ -0,0 +1,20 @@
+-- Same as migrations/mariadb/0007_audit_log.sql, in SQLite's dialect
+
+CREATE TABLE AuditLog(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  song_id INTEGER NOT NULL,
+  changed_at CHAR(19) NOT NULL,
+  changed_by VARCHAR(100) NOT NULL,
+  old_title VARCHAR(60),
+  old_artist VARCHAR(200),
+  old_album VARCHAR(200),
+  old_release_year INT,
+  old_media_type VARCHAR(60),
+  new_title VARCHAR(60),
+  new_artist VARCHAR(200),
+  new_album VARCHAR(200),
+  new_release_year INT,
+  new_media_type VARCHAR(60));
+
+CREATE INDEX audit_song ON AuditLog (song_id);
+CREATE INDEX audit_time ON AuditLog (changed_at);
@@ -0,0 +1,5 @@
+-- Same as migrations/mariadb/0008_trash.sql, in SQLite's dialect
+
+ALTER TABLE Songs ADD COLUMN deleted_at CHAR(19);
+
+CREATE INDEX songs_deleted ON Songs (deleted_at);
@@ -0,0 +1,63 @@
+-- Same as migrations/mariadb/0009_media_types.sql, in SQLite's dialect
+
+CREATE TABLE MediaTypes(
+  id INTEGER PRIMARY KEY AUTOINCREMENT,
+  name VARCHAR(60) NOT NULL UNIQUE COLLATE NOCASE,
+  format VARCHAR(20) NOT NULL,
+  speed VARCHAR(60),
+  size VARCHAR(60),
+  bitrate VARCHAR(60),
+  codec VARCHAR(60));
+
+CREATE TABLE MediaTypeAliases(
+  alias VARCHAR(60) NOT NULL PRIMARY KEY COLLATE NOCASE,
+  media_type_id INTEGER NOT NULL REFERENCES MediaTypes(id) ON DELETE CASCADE);
+
+-- the usual types, including the ones folder scans file tracks under
+INSERT INTO MediaTypes (name, format, speed, size, bitrate, codec) VALUES
+  ('Vinyl', 'vinyl', '33 1/3 rpm', '12"', NULL, NULL),
+  ('7" Single', 'vinyl', '45 rpm', '7"', NULL, NULL),
+  ('CD', 'cd', NULL, NULL, NULL, NULL),
+  ('Cassette', 'cassette', NULL, NULL, NULL, NULL),
+  ('FLAC', 'digital', NULL, NULL, NULL, 'FLAC'),
+  ('Digital Download', 'digital', NULL, NULL, NULL, NULL);
+
+INSERT INTO MediaTypeAliases (alias, media_type_id) VALUES
+  ('LP', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12in', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12"', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('12 inch', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('Record', (SELECT id FROM MediaTypes WHERE name = 'Vinyl')),
+  ('7in', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('7"', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('7 inch', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('45', (SELECT id FROM MediaTypes WHERE name = '7" Single')),
+  ('Compact Disc', (SELECT id FROM MediaTypes WHERE name = 'CD')),
+  ('Tape', (SELECT id FROM MediaTypes WHERE name = 'Cassette')),
+  ('MC', (SELECT id FROM MediaTypes WHERE name = 'Cassette')),
+  ('Digital', (SELECT id FROM MediaTypes WHERE name = 'Digital Download')),
+  ('Download', (SELECT id FROM MediaTypes WHERE name = 'Digital Download')),
+  ('MP3', (SELECT id FROM MediaTypes WHERE name = 'Digital Download'));
+
+-- every other value in use becomes a type of its own, values that only differ by case or surrounding
+-- whitespace becoming one type. `media-types map` files them under another type afterwards.
+INSERT INTO MediaTypes (name, format)
+  SELECT MIN(TRIM(used.media_type)), 'other'
+  FROM (SELECT media_type FROM Songs UNION ALL SELECT media_type FROM Albums) used
+  WHERE TRIM(used.media_type) <> ''
+    AND LOWER(TRIM(used.media_type)) NOT IN (SELECT LOWER(name) FROM MediaTypes)
+    AND LOWER(TRIM(used.media_type)) NOT IN (SELECT LOWER(alias) FROM MediaTypeAliases)
+  GROUP BY LOWER(TRIM(used.media_type));
+
+-- songs and albums take the name of their type, by its name first and then by an alias
+UPDATE Songs SET media_type = COALESCE(
+  (SELECT mt.name FROM MediaTypes mt WHERE LOWER(mt.name) = LOWER(TRIM(Songs.media_type))),
+  (SELECT mt.name FROM MediaTypeAliases a JOIN MediaTypes mt ON mt.id = a.media_type_id
+   WHERE LOWER(a.alias) = LOWER(TRIM(Songs.media_type))),
+  media_type);
+
+UPDATE Albums SET media_type = COALESCE(
+  (SELECT mt.name FROM MediaTypes mt WHERE LOWER(mt.name) = LOWER(TRIM(Albums.media_type))),
+  (SELECT mt.name FROM MediaTypeAliases a JOIN MediaTypes mt ON mt.id = a.media_type_id
+   WHERE LOWER(a.alias) = LOWER(TRIM(Albums.media_type))),
+  media_type);
@@ -0,0 +1,34 @@
+-- Some example rows to try the app with. The tables themselves are created by the migrations
+-- in migrations/, which run automatically when the app connects. Run this on an empty library.
+
+USE music;
+
+INSERT INTO Artists (id, name) VALUES
+    (1, 'Radiohead'),
+    (2, 'Future'),
+    (3, 'Bob Dylan'),
+    (4, '2 Chainz'),
+    (5, 'Black Sabbath'),
+    (6, 'The Beatles'),
+    (7, 'B.B. King'),
+    (8, 'Chief Keef');
+
+INSERT INTO Albums (id, title, artist_id, release_year, media_type) VALUES
+    (1, 'The Bends', 1, 1995, 'Digital Download'),
+    (2, 'HNDRXX', 2, 2017, 'Digital Download'),
+    (3, 'The Freewheelin Bob Dylan', 3, 1963, 'Vinyl'),
+    (4, 'Pretty Girls Like Trap Music', 4, 2017, 'Digital Download'),
+    (5, 'Paranoid', 5, 1970, 'Vinyl'),
+    (6, 'Magical Mystery Tour', 6, 1967, 'Vinyl'),
+    (7, 'B.B. King in London', 7, 1971, 'Vinyl'),
+    (8, 'Finally Rich', 8, 2012, 'Digital Download');
+
+INSERT INTO Songs (title, artist_id, album_id, release_year, media_type) VALUES
+    ('My Iron Lung', 1, 1, 1995, 'Digital Download'),
+    ('Solo', 2, 2, 2017, 'Digital Download'),
+    ('Masters of War', 3, 3, 1963, 'Vinyl'),
+    ('Riverdale Rd', 4, 4, 2017, 'Digital Download'),
+    ('War Pigs', 5, 5, 1970, 'Vinyl'),
+    ('I Am the Walrus', 6, 6, 1967, 'Vinyl'),
+    ('Caldonia', 7, 7, 1971, 'Vinyl'),
+    ('Love Sosa', 8, 8, 2012, 'Digital Download');
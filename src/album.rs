@@ -0,0 +1,24 @@
+// This is the program's model of an Album, mirroring Song.
+// song_count is only filled in when albums are read from the database.
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Album {
+    pub id: u32,
+    pub title: String,
+    pub artist: String,
+    pub release_year: i32,
+    pub media_type: String,
+    pub song_count: i64,
+}
+impl Album {
+    pub fn new(id: u32, title: &str, artist: &str, release_year: i32, media_type: &str) -> Self {
+        Self {
+            id,
+            title: title.to_owned(),
+            artist: artist.to_owned(),
+            release_year,
+            media_type: media_type.to_owned(),
+            song_count: 0,
+        }
+    }
+}
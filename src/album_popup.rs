@@ -0,0 +1,218 @@
+// this file contains the album popup widget logic, the album version of popup.rs
+
+use crate::{
+    album::Album,
+    database::LibraryStore,
+    error::{AppError, Context},
+    import::Field,
+    picker::{Choice, Picker},
+    popup::PopupMode,
+    text_box::{InputMode, TextBox},
+    validate,
+};
+use crossterm::event::{KeyCode, KeyEvent};
+
+// the fields of the popup in Tab order
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AlbumField {
+    Title,
+    Artist,
+    ReleaseYear,
+    MediaType,
+    UpdateSongs,
+}
+
+// AlbumPopup stores all state info
+#[derive(Debug, Clone)]
+pub struct AlbumPopup {
+    mode: PopupMode, // mode
+    album_id: u32,   // id for album to edit or create
+    focus: AlbumField,
+    // text boxes for each input field
+    pub title_box: TextBox,
+    pub artist_box: TextBox,
+    pub release_year_box: TextBox,
+    pub media_type_picker: Picker,
+    // Edit only: also give every song on the album the new year and media type
+    update_songs: bool,
+}
+
+impl AlbumPopup {
+    pub fn new(mode: PopupMode, album_id: u32) -> Self {
+        Self {
+            mode,
+            album_id,
+            focus: AlbumField::Title,
+            title_box: TextBox::new("Title".to_owned()),
+            artist_box: TextBox::new("Artist".to_owned()),
+            release_year_box: TextBox::new("Year".to_owned()).numeric(),
+            media_type_picker: Picker::new("Media Type".to_owned()),
+            update_songs: false,
+        }
+    }
+
+    // adds or updates the album, returns the number of album rows affected
+    pub async fn submit(&mut self, store: &dyn LibraryStore) -> Result<u64, AppError> {
+        // submit all boxes and store an album from the input
+        self.submit_all_boxes();
+        let new_album = self.get_album_from_input()?;
+        match self.mode {
+            PopupMode::New => store.add_album(new_album).await.context("add the album"),
+            // Edit also updates the album's songs if the checkbox is ticked
+            PopupMode::Edit => store
+                .update_album(self.album_id, new_album, self.update_songs)
+                .await
+                .context("update the album"),
+        }
+    }
+
+    // submits all text boxes
+    fn submit_all_boxes(&mut self) {
+        self.title_box.submit_message();
+        self.artist_box.submit_message();
+        self.release_year_box.submit_message();
+    }
+
+    // clears all input fields in text boxes
+    pub fn clear_all_boxes(&mut self) {
+        self.title_box.clear_input();
+        self.artist_box.clear_input();
+        self.release_year_box.clear_input();
+        self.media_type_picker.clear_input();
+        self.update_songs = false;
+    }
+
+    // returns a checked album from the text boxes' last submitted input and the picked media type,
+    // or everything wrong with them
+    fn get_album_from_input(&self) -> Result<Album, AppError> {
+        let message = |text_box: &TextBox| text_box.get_mesages().pop().unwrap_or_default();
+        let title = validate::text(Field::Album, &message(&self.title_box));
+        let artist = validate::text(Field::Artist, &message(&self.artist_box));
+        let release_year = validate::parse_year(&message(&self.release_year_box));
+        let media_type = validate::text(Field::MediaType, self.media_type_picker.get_input());
+        match (title, artist, release_year, media_type) {
+            (Ok(title), Ok(artist), Ok(release_year), Ok(media_type)) => {
+                Ok(Album::new(0, &title, &artist, release_year, &media_type))
+            }
+            (title, artist, release_year, media_type) => Err(AppError::Invalid(
+                [
+                    title.err(),
+                    artist.err(),
+                    release_year.err(),
+                    media_type.err(),
+                ]
+                .into_iter()
+                .flatten()
+                .collect(),
+            )),
+        }
+    }
+
+    // returns true if all boxes have some text in them
+    pub fn do_all_boxes_have_text(&self) -> bool {
+        !self.title_box.get_input().is_empty()
+            && !self.artist_box.get_input().is_empty()
+            && !self.release_year_box.get_input().is_empty()
+            && !self.media_type_picker.get_input().is_empty()
+    }
+
+    // sets all textbox input modes to the passed mode
+    pub fn set_all_input_modes(&mut self, new_mode: InputMode) {
+        self.title_box.set_input_mode(new_mode.clone());
+        self.artist_box.set_input_mode(new_mode.clone());
+        self.release_year_box.set_input_mode(new_mode.clone());
+        self.media_type_picker.set_input_mode(new_mode);
+    }
+
+    // puts the focus on the title box, like opening the popup fresh
+    pub fn focus_first_field(&mut self) {
+        self.set_all_input_modes(InputMode::Normal);
+        self.focus = AlbumField::Title;
+        self.title_box.set_input_mode(InputMode::Editing);
+    }
+
+    // "Tab" moves to the next field. The update-songs checkbox only exists when editing.
+    pub fn focus_next_field(&mut self) {
+        self.focus = match (self.focus, &self.mode) {
+            (AlbumField::Title, _) => AlbumField::Artist,
+            (AlbumField::Artist, _) => AlbumField::ReleaseYear,
+            (AlbumField::ReleaseYear, _) => AlbumField::MediaType,
+            (AlbumField::MediaType, PopupMode::Edit) => AlbumField::UpdateSongs,
+            (AlbumField::MediaType, PopupMode::New) | (AlbumField::UpdateSongs, _) => {
+                AlbumField::Title
+            }
+        };
+        self.set_all_input_modes(InputMode::Normal);
+        if self.focus == AlbumField::MediaType {
+            self.media_type_picker.set_input_mode(InputMode::Editing);
+        } else if let Some(text_box) = self.focused_box() {
+            text_box.set_input_mode(InputMode::Editing);
+        }
+    }
+
+    // the text box with focus, or None if the media type picker or the checkbox has it
+    pub fn focused_box(&mut self) -> Option<&mut TextBox> {
+        match self.focus {
+            AlbumField::Title => Some(&mut self.title_box),
+            AlbumField::Artist => Some(&mut self.artist_box),
+            AlbumField::ReleaseYear => Some(&mut self.release_year_box),
+            AlbumField::MediaType | AlbumField::UpdateSongs => None,
+        }
+    }
+
+    // edits the focused box with a key, picks a media type, or flips the checkbox with space
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        match self.focus {
+            AlbumField::MediaType => {
+                self.media_type_picker.handle_key(key_event);
+            }
+            AlbumField::UpdateSongs if key_event.code == KeyCode::Char(' ') => {
+                self.update_songs = !self.update_songs
+            }
+            _ => {
+                if let Some(text_box) = self.focused_box() {
+                    text_box.handle_key(key_event);
+                }
+            }
+        }
+    }
+
+    // pasted text goes into the focused box, or narrows the media types down
+    pub fn paste(&mut self, text: &str) {
+        if self.focus == AlbumField::MediaType {
+            self.media_type_picker.insert_str(text);
+        } else if let Some(text_box) = self.focused_box() {
+            text_box.insert_str(text);
+        }
+    }
+
+    // the media types to pick from
+    pub fn set_media_types(&mut self, choices: Vec<Choice>) {
+        self.media_type_picker.set_choices(choices);
+    }
+
+    // pushes the data from the album's fields to each text box
+    pub fn populate_textboxes_with_album(&mut self, album: &Album) {
+        self.title_box.set_input(album.title.clone());
+        self.artist_box.set_input(album.artist.clone());
+        self.release_year_box
+            .set_input(album.release_year.to_string());
+        self.media_type_picker.set_input(album.media_type.clone());
+    }
+
+    pub fn get_popup_mode(&self) -> PopupMode {
+        self.mode.clone()
+    }
+
+    pub fn set_album_id(&mut self, new_id: u32) {
+        self.album_id = new_id;
+    }
+
+    pub fn is_update_songs_focused(&self) -> bool {
+        self.focus == AlbumField::UpdateSongs
+    }
+
+    pub fn get_update_songs(&self) -> bool {
+        self.update_songs
+    }
+}
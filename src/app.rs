@@ -1,13 +1,33 @@
 //  This file holds the code that displays the UI and handles input
 
 use crate::{
-    database::{delete_song, get_all_songs, get_songs_matching},
+    album::Album,
+    album_popup::AlbumPopup,
+    artist::Artist,
+    audit::{AuditEntry, FieldChange},
+    cli::{self, OutputFormat},
+    complete::Vocabulary,
+    config::{StartView, UiConfig},
+    database::LibraryStore,
+    error::{AppError, Context, QueryError},
+    fuzzy,
+    import::Field,
+    media_type::MediaType,
+    media_type_popup::MediaTypePopup,
+    pager::SongPager,
+    picker::Choice,
+    playlist::{Playlist, PlaylistEntry},
+    playlist_popup::PlaylistPopup,
     popup::{Popup, PopupMode},
-    song::Song,
+    query::Query,
+    song::{Song, TrashedSong},
+    sort::Sort,
+    status::{Severity, Status},
     text_box::{InputMode, TextBox},
     tui,
 };
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use crossterm::event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use futures_util::StreamExt;
 use ratatui::{
     prelude::*,
     symbols::border,
@@ -16,8 +36,39 @@ use ratatui::{
         Block, Borders, Cell, Clear, Paragraph, Row, Table, TableState,
     },
 };
-use sqlx::MySqlPool;
-use std::{io, rc::Rc};
+use std::{
+    collections::BTreeSet,
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    task::JoinHandle,
+};
+
+// how long typing has to pause before the search runs,
+// so a fast typist doesn't start a query per key
+const SEARCH_DELAY: Duration = Duration::from_millis(150);
+
+// songs loaded in the background, sent back to the run loop.
+// generation tells a result apart from ones that came too late.
+#[derive(Debug)]
+enum Loaded {
+    // the songs a search found
+    Search {
+        generation: u64,
+        songs: Result<SongPager, sqlx::Error>,
+    },
+    // the songs table with the pages around the screen fetched
+    Pages {
+        generation: u64,
+        songs: Result<SongPager, sqlx::Error>,
+    },
+}
 
 // AppMode stores the app's current input mode
 #[derive(Debug, PartialEq)]
@@ -27,86 +78,349 @@ enum AppMode {
     Search,
     Edit,
     Delete,
+    Merge,
+    Export,
+    History,
     Exit,
 }
 
-// App stores the context information for what action is taking place as well as the database pool
+// View stores which table is on screen
+#[derive(Debug, PartialEq)]
+enum View {
+    Songs,
+    Albums,
+    Artists,
+    Playlists,
+    Playlist,   // the songs on the open playlist
+    Trash,      // the deleted songs, until they're restored or purged
+    MediaTypes, // the managed list songs and albums take their media type from
+}
+
+// App stores the context information for what action is taking place as well as the database store
 #[derive(Debug)]
 pub struct App {
-    songs: Vec<Song>, // list of songs to display
-    pool: MySqlPool,  // database connection pool
+    songs: SongPager,             // the songs to display, loaded a page at a time
+    albums: Vec<Album>,           // list of albums to display
+    artists: Vec<Artist>,         // list of artists to display
+    playlists: Vec<Playlist>,     // list of playlists to display
+    entries: Vec<PlaylistEntry>,  // songs on the open playlist
+    trash: Vec<TrashedSong>,      // songs in the trash
+    media_types: Vec<MediaType>,  // the media types on the list
+    album: Option<Album>,         // album whose songs are displayed, None means every song
+    playlist: Option<Playlist>,   // playlist last opened, where "+" adds songs
+    store: Arc<dyn LibraryStore>, // database the library lives in
 
-    selected_row: usize, // selected row of the table
-    mode: AppMode,       // current mode
-    debug: bool,         // set to True to display debug info
-    esc_mode: bool,      // in esc_mode, hitting "Esc" returns to Normal mode
+    selected_row: usize,            // selected row of the table
+    table_offset: usize,            // the song at the top of the table
+    table_height: usize,            // how many rows fit in the table, as of the last frame
+    marked: BTreeSet<u32>,          // ids of the songs marked to be edited or deleted together
+    mark_anchor: Option<usize>,     // the row "v" started a range at, it runs to the selected row
+    selected_album_row: usize,      // selected row of the albums table
+    selected_artist_row: usize,     // selected row of the artists table
+    selected_playlist_row: usize,   // selected row of the playlists table
+    selected_entry_row: usize,      // selected row of the open playlist
+    selected_trash_row: usize,      // selected row of the trash
+    selected_media_type_row: usize, // selected row of the media types table
+    merge_from: Option<Artist>,     // artist picked to be merged into the next one picked
+    history: Vec<FieldChange>, // the selected song's changes, newest first, while "H" shows them
+    history_row: usize,        // selected row of the history
+    view: View,                // table on screen
+    mode: AppMode,             // current mode
+    debug: bool,               // set to True to display debug info
+    esc_mode: bool,            // in esc_mode, hitting "Esc" returns to Normal mode
+    allow_adding_media_types: bool, // see [media_types] in the config file
 
     // Widgets to display
     searchbar: TextBox,
+    search_error: Option<QueryError>, // why the query in the searchbar can't be run
+    status: Option<Status>, // what the last action did or why it failed, until it times out
+    fuzzy: bool,            // rank songs by a typo-tolerant match instead of the query language
+    last_query: String,     // the search the table shows, run again when fuzzy is toggled
+    sort: Sort,             // the order the songs table is sorted in
+    search_sender: UnboundedSender<Loaded>, // where searches and page loads send their songs
+    search_receiver: Option<UnboundedReceiver<Loaded>>, // taken by run to wait on
+    search_task: Option<JoinHandle<()>>, // the search running now, aborted if a newer one starts
+    search_generation: u64, // counts searches, only the latest one's result is shown
+    page_task: Option<JoinHandle<()>>, // the pages being fetched for the songs table
+    page_generation: u64,   // counts songs lists, pages fetched for a replaced one are ignored
+    requested_rows: Option<(usize, usize)>, // the rows the last page load was for, not retried
+    pending_search: Option<Instant>, // when to search the searchbar's text, once typing pauses
     new_popup: Popup,
     edit_popup: Popup,
+    new_album_popup: AlbumPopup,
+    edit_album_popup: AlbumPopup,
+    new_playlist_popup: PlaylistPopup,
+    edit_playlist_popup: PlaylistPopup,
+    media_type_popup: MediaTypePopup,
+    rename_box: TextBox,
+    export_box: TextBox,
 }
 impl App {
-    pub fn new(pool: MySqlPool) -> Self {
-        // initial state is everything false.
+    pub fn new(
+        store: Arc<dyn LibraryStore>,
+        ui_config: UiConfig,
+        allow_adding_media_types: bool,
+    ) -> Self {
+        // initial state is everything false, apart from what the config file asks for.
+        let (search_sender, search_receiver) = mpsc::unbounded_channel();
         Self {
-            songs: Vec::new(),
-            pool,
+            songs: SongPager::default(),
+            albums: Vec::new(),
+            artists: Vec::new(),
+            playlists: Vec::new(),
+            entries: Vec::new(),
+            trash: Vec::new(),
+            media_types: Vec::new(),
+            album: None,
+            playlist: None,
+            store,
             selected_row: 1,
+            table_offset: 0,
+            table_height: 0,
+            marked: BTreeSet::new(),
+            mark_anchor: None,
+            selected_album_row: 0,
+            selected_artist_row: 0,
+            selected_playlist_row: 0,
+            selected_entry_row: 0,
+            selected_trash_row: 0,
+            selected_media_type_row: 0,
+            merge_from: None,
+            history: Vec::new(),
+            history_row: 0,
+            view: match ui_config.start_view {
+                StartView::Songs => View::Songs,
+                StartView::Albums => View::Albums,
+                StartView::Artists => View::Artists,
+            },
             mode: AppMode::Normal,
-            debug: false,
+            debug: ui_config.debug,
             esc_mode: false,
+            allow_adding_media_types,
             searchbar: TextBox::new("Search".to_owned()),
+            search_error: None,
+            status: None,
+            fuzzy: false,
+            last_query: String::new(),
+            sort: Sort::default(),
+            search_sender,
+            search_receiver: Some(search_receiver),
+            search_task: None,
+            search_generation: 0,
+            page_task: None,
+            page_generation: 0,
+            requested_rows: None,
+            pending_search: None,
             new_popup: Popup::new(PopupMode::New, 0),
             edit_popup: Popup::new(PopupMode::Edit, 0),
+            new_album_popup: AlbumPopup::new(PopupMode::New, 0),
+            edit_album_popup: AlbumPopup::new(PopupMode::Edit, 0),
+            new_playlist_popup: PlaylistPopup::new(PopupMode::New, 0),
+            edit_playlist_popup: PlaylistPopup::new(PopupMode::Edit, 0),
+            media_type_popup: MediaTypePopup::default(),
+            rename_box: TextBox::new("Name".to_owned()),
+            export_box: TextBox::new("File".to_owned()),
         }
     }
 
     pub async fn run(&mut self, terminal: &mut tui::Tui) -> io::Result<()> {
-        // try to get songs from database, if failure return a template song
-        self.songs = match get_all_songs(&self.pool).await {
+        // try to get songs from database,
+        // if that fails the table starts empty and the status bar says why
+        let songs = match SongPager::open(self.store.as_ref(), Query::default(), self.sort.clone())
+            .await
+            .context("load the songs")
+        {
             Ok(songs) => songs,
             Err(error) => {
-                eprintln!("Error getting songs from database: {}", error);
-                vec![Song::new(
-                    404,
-                    " Error",
-                    "Displaying",
-                    "Songs. ",
-                    404,
-                    "Error ",
-                )]
+                self.report_error(error);
+                SongPager::default()
             }
         };
+        self.show_songs(songs);
+        // the config file can start the app on another table
+        match self.view {
+            View::Albums => self.refresh_albums().await,
+            View::Artists => self.refresh_artists().await,
+            View::Songs | View::Playlists | View::Playlist | View::Trash | View::MediaTypes => {}
+        }
+        // input comes in as a stream, so the loop below can wait on the keyboard, searches and
+        // page loads at once. It stops reading when the stream is dropped as run returns.
+        let mut events = EventStream::new();
+        let Some(mut loaded) = self.search_receiver.take() else {
+            return Err(io::Error::other("the app is already running"));
+        };
+
         // handle events and render the app until the user exits
         while self.mode != AppMode::Exit {
+            self.load_visible_songs();
             terminal.draw(|frame| self.render_frame(frame))?;
-            self.handle_events().await?;
+            let search_due = self.pending_search;
+            let status_due = self.status.as_ref().map(|status| status.expires_at);
+            tokio::select! {
+                Some(event) = events.next() => self.handle_events(event?).await,
+                Some(songs) = loaded.recv() => self.show_loaded(songs),
+                _ = sleep_until(search_due) => {
+                    self.pending_search = None;
+                    self.submit_search_query(self.searchbar.get_input().to_string());
+                }
+                _ = sleep_until(status_due) => {
+                    self.status = None;
+                }
+            }
         }
+        self.cancel_search();
+        self.cancel_page_load();
         Ok(())
     }
 
     fn render_frame(&mut self, frame: &mut Frame) {
-        // initialize table rows
-        let mut rows: Vec<Row> = Vec::new();
-        for song in &self.songs {
-            let row = song_to_row(song);
-            rows.push(row);
-        }
-        // table title and instructions
-        let title = Title::from(" Music Library ".bold());
-        let instructions = Title::from(Line::from(vec![
-            " Search ".into(),
-            "</>".yellow().bold(),
-            " New Song ".into(),
-            "<N>".yellow().bold(),
-            " Edit Song ".into(),
-            "<E>".yellow().bold(),
-            " Delete Song ".into(),
-            "<D> ".yellow().bold(),
-        ]));
+        // table title and instructions depend on what the table is showing
+        let title = match (&self.view, &self.album, &self.playlist) {
+            (View::Playlist, _, Some(playlist)) => {
+                Title::from(format!(" Playlist: {} ", playlist.name).bold())
+            }
+            (View::Trash, _, _) => Title::from(" Trash ".bold()),
+            (View::MediaTypes, _, _) => Title::from(" Media Types ".bold()),
+            (_, Some(album), _) => Title::from(format!(" Music Library: {} ", album.title).bold()),
+            _ => Title::from(" Music Library ".bold()),
+        };
+        // "+" adds to the playlist opened last, so it's only offered once there is one
+        let add_to_playlist = match &self.playlist {
+            Some(playlist) => vec![
+                format!(" Add to {} ", playlist.name).into(),
+                "<+>".yellow().bold(),
+            ],
+            None => Vec::new(),
+        };
+        // with songs marked, Edit and Delete act on all of them
+        let (edit_label, delete_label) = match self.marked.len() {
+            0 => (" Edit Song ".to_owned(), " Delete Song ".to_owned()),
+            count => (
+                format!(" Edit {} ", songs(count)),
+                format!(" Delete {} ", songs(count)),
+            ),
+        };
+        let mark_label = match (self.mark_anchor, self.marked.is_empty()) {
+            (Some(_), _) => vec![" End Range ".into(), "<V>".yellow().bold()],
+            (None, true) => vec![" Mark ".into(), "<Space V *>".yellow().bold()],
+            (None, false) => vec![
+                " Mark ".into(),
+                "<Space V *>".yellow().bold(),
+                " Clear Marks ".into(),
+                "<ESC>".yellow().bold(),
+            ],
+        };
+        let instructions = match self.view {
+            View::Songs => Title::from(Line::from(
+                [
+                    vec![
+                        " Search ".into(),
+                        "</>".yellow().bold(),
+                        " New Song ".into(),
+                        "<N>".yellow().bold(),
+                        edit_label.into(),
+                        "<E>".yellow().bold(),
+                        delete_label.into(),
+                        "<D>".yellow().bold(),
+                    ],
+                    mark_label,
+                    vec![
+                        " Albums ".into(),
+                        "<A>".yellow().bold(),
+                        " Artists ".into(),
+                        "<R>".yellow().bold(),
+                        " Export ".into(),
+                        "<X>".yellow().bold(),
+                        if self.fuzzy {
+                            " Exact Search ".into()
+                        } else {
+                            " Fuzzy Search ".into()
+                        },
+                        "<F>".yellow().bold(),
+                        " Playlists ".into(),
+                        "<P>".yellow().bold(),
+                        " Sort ".into(),
+                        "<1-5>".yellow().bold(),
+                        " History ".into(),
+                        "<H>".yellow().bold(),
+                        " Trash ".into(),
+                        "<T>".yellow().bold(),
+                        " Media Types ".into(),
+                        "<M> ".yellow().bold(),
+                    ],
+                    add_to_playlist,
+                ]
+                .concat(),
+            )),
+            View::Albums => Title::from(Line::from(vec![
+                " Show Songs ".into(),
+                "<Enter>".yellow().bold(),
+                " New Album ".into(),
+                "<N>".yellow().bold(),
+                " Edit Album ".into(),
+                "<E>".yellow().bold(),
+                " Delete Album ".into(),
+                "<D>".yellow().bold(),
+                " All Songs ".into(),
+                "<A> ".yellow().bold(),
+            ])),
+            View::Artists => match &self.merge_from {
+                Some(artist) => Title::from(Line::from(vec![
+                    format!(" Merge {} into ", artist.name).into(),
+                    "<M>".yellow().bold(),
+                    " Cancel ".into(),
+                    "<ESC> ".yellow().bold(),
+                ])),
+                None => Title::from(Line::from(vec![
+                    " Rename Artist ".into(),
+                    "<E>".yellow().bold(),
+                    " Merge Artist ".into(),
+                    "<M>".yellow().bold(),
+                    " All Songs ".into(),
+                    "<R> ".yellow().bold(),
+                ])),
+            },
+            View::Playlists => Title::from(Line::from(vec![
+                " Open ".into(),
+                "<Enter>".yellow().bold(),
+                " New Playlist ".into(),
+                "<N>".yellow().bold(),
+                " Edit Playlist ".into(),
+                "<E>".yellow().bold(),
+                " Delete Playlist ".into(),
+                "<D>".yellow().bold(),
+                " All Songs ".into(),
+                "<P> ".yellow().bold(),
+            ])),
+            View::Playlist => Title::from(Line::from(vec![
+                " Move Up ".into(),
+                "<K>".yellow().bold(),
+                " Move Down ".into(),
+                "<J>".yellow().bold(),
+                " Remove ".into(),
+                "<D>".yellow().bold(),
+                " All Songs ".into(),
+                "<A>".yellow().bold(),
+                " Playlists ".into(),
+                "<P> ".yellow().bold(),
+            ])),
+            View::Trash => Title::from(Line::from(vec![
+                " Restore ".into(),
+                "<Enter>".yellow().bold(),
+                " Delete Forever ".into(),
+                "<D>".yellow().bold(),
+                " All Songs ".into(),
+                "<T> ".yellow().bold(),
+            ])),
+            View::MediaTypes => Title::from(Line::from(vec![
+                " New Media Type ".into(),
+                "<N>".yellow().bold(),
+                " All Songs ".into(),
+                "<M> ".yellow().bold(),
+            ])),
+        };
         // display blocks for the table and searchbar
-        let table_block = Block::default()
+        let mut table_block = Block::default()
             .title(
                 instructions
                     .alignment(Alignment::Center)
@@ -115,54 +429,232 @@ impl App {
             .borders(Borders::ALL)
             .border_set(border::THICK);
 
-        let search_block = Block::default()
+        let mut search_block = Block::default()
             .title(title.alignment(Alignment::Center))
             .borders(Borders::ALL)
             .border_set(border::THICK);
-        // store the selected row in the table's state
-        let mut table_state: TableState =
-            TableState::default().with_selected(Some(self.selected_row.clamp(0, self.songs.len())));
-        // Table row headers
-        let header = Row::new(vec![
-            Cell::from(" Title".bold()),
-            Cell::from("Artist".bold()),
-            Cell::from("Album".bold()),
-            Cell::from("Year".bold()),
-            Cell::from("Media Type".bold()),
-        ]);
-        // Create table
-        let table = Table::new(
-            rows,
-            [
-                Constraint::Percentage(20),
-                Constraint::Percentage(20),
-                Constraint::Percentage(30),
-                Constraint::Percentage(10),
-                Constraint::Percentage(20),
-            ],
-        )
-        .column_spacing(1)
-        .header(header)
-        .highlight_style(Style::new().reversed())
-        .highlight_symbol(">>")
-        .block(table_block);
+        if self.search_task.is_some() || self.pending_search.is_some() {
+            search_block = search_block.title(
+                Title::from(" Searching… ".italic())
+                    .alignment(Alignment::Right)
+                    .position(Position::Bottom),
+            );
+        }
+        if self.fuzzy {
+            search_block = search_block.title(
+                Title::from(" Fuzzy ".yellow().bold())
+                    .alignment(Alignment::Right)
+                    .position(Position::Bottom),
+            );
+        }
+        // a query that doesn't parse leaves the last results in the table and says why here
+        if let Some(error) = &self.search_error {
+            search_block = search_block.title(
+                Title::from(format!(" {} ", error).red().bold())
+                    .alignment(Alignment::Left)
+                    .position(Position::Bottom),
+            );
+        }
+        // the songs table only builds the rows on screen, so it needs to know how many fit
+        // (the area less its borders and header)
+        self.table_height = self.get_layout(frame)[1].height.saturating_sub(3) as usize;
+        if self.view == View::Songs {
+            let count = match (self.songs.len(), self.marked.len()) {
+                (0, _) => " No songs ".to_owned(),
+                (total, 0) => format!(" {} of {} songs ", self.selected_row + 1, total),
+                (total, marked) => format!(
+                    " {} of {} songs, {} marked ",
+                    self.selected_row + 1,
+                    total,
+                    marked
+                ),
+            };
+            table_block = table_block.title(
+                Title::from(count)
+                    .alignment(Alignment::Right)
+                    .position(Position::Top),
+            );
+        }
+        // Create the table for the current view and store its selected row in the table's state
+        let (table, selected_row) = match self.view {
+            View::Songs => (
+                song_table(
+                    &self.songs,
+                    self.table_offset..self.table_offset + self.table_height,
+                    &self.marked,
+                    self.mark_anchor.map(|anchor| {
+                        anchor.min(self.selected_row)..=anchor.max(self.selected_row)
+                    }),
+                ),
+                self.selected_row.saturating_sub(self.table_offset),
+            ),
+            View::Albums => (
+                album_table(&self.albums),
+                self.selected_album_row.clamp(0, self.albums.len()),
+            ),
+            View::Artists => (
+                artist_table(&self.artists),
+                self.selected_artist_row.clamp(0, self.artists.len()),
+            ),
+            View::Playlists => (
+                playlist_table(&self.playlists),
+                self.selected_playlist_row.clamp(0, self.playlists.len()),
+            ),
+            View::Playlist => (
+                entry_table(&self.entries),
+                self.selected_entry_row.clamp(0, self.entries.len()),
+            ),
+            View::Trash => (
+                trash_table(&self.trash),
+                self.selected_trash_row.clamp(0, self.trash.len()),
+            ),
+            View::MediaTypes => (
+                media_type_table(&self.media_types),
+                self.selected_media_type_row
+                    .clamp(0, self.media_types.len()),
+            ),
+        };
+        let mut table_state: TableState = TableState::default().with_selected(Some(selected_row));
+        let table = table
+            .column_spacing(1)
+            .highlight_style(Style::new().reversed())
+            .highlight_symbol(">>")
+            .block(table_block);
         // render searchbar and table
-        frame.render_widget(Clear, self.get_layout(&frame)[0]);
-        frame.render_widget(
-            self.searchbar.get_widget().block(search_block),
-            self.get_layout(&frame)[0],
-        );
+        frame.render_widget(Clear, self.get_layout(frame)[0]);
+        self.searchbar
+            .render(frame, search_block, self.get_layout(frame)[0]);
         frame.render_stateful_widget(table, self.get_layout(frame)[1], &mut table_state);
 
+        // the status bar along the bottom, colored by how the last action went
+        if let Some(status) = &self.status {
+            let (symbol, color) = match status.severity {
+                Severity::Success => ("✓", Color::Green),
+                Severity::Info => ("•", Color::Cyan),
+                Severity::Warning => ("!", Color::Yellow),
+                Severity::Error => ("✗", Color::Red),
+            };
+            let status_area = *self.get_layout(frame).last().unwrap_or(&Rect::default());
+            frame.render_widget(
+                Paragraph::new(format!(" {} {}", symbol, status.message))
+                    .style(Style::new().fg(color).bold()),
+                status_area,
+            );
+        }
+
         // if app mode is new, edit, or delete, render an area for the popup
-        if self.mode == AppMode::New || self.mode == AppMode::Edit || self.mode == AppMode::Delete {
+        if self.mode == AppMode::New
+            || self.mode == AppMode::Edit
+            || self.mode == AppMode::Delete
+            || self.mode == AppMode::Merge
+            || self.mode == AppMode::Export
+            || self.mode == AppMode::History
+        {
             let popup_area = centered_rect(frame.size(), 70, 50);
             frame.render_widget(Clear, popup_area);
             // display appropriate popup for the app's mode
-            match self.mode {
-                AppMode::New => render_popup(frame, self.new_popup.clone(), popup_area),
-                AppMode::Edit => render_popup(frame, self.edit_popup.clone(), popup_area),
-                AppMode::Delete => render_delete_popup(frame, popup_area),
+            match (&self.mode, &self.view) {
+                (AppMode::New, View::Songs) => {
+                    render_popup(frame, self.new_popup.clone(), popup_area)
+                }
+                (AppMode::Edit, View::Songs) => {
+                    render_popup(frame, self.edit_popup.clone(), popup_area)
+                }
+                (AppMode::New, View::Albums) => {
+                    render_album_popup(frame, &self.new_album_popup, popup_area)
+                }
+                (AppMode::Edit, View::Albums) => {
+                    render_album_popup(frame, &self.edit_album_popup, popup_area)
+                }
+                (AppMode::New, View::Playlists) => {
+                    render_playlist_popup(frame, &self.new_playlist_popup, popup_area)
+                }
+                (AppMode::Edit, View::Playlists) => {
+                    render_playlist_popup(frame, &self.edit_playlist_popup, popup_area)
+                }
+                (AppMode::New, View::MediaTypes) => {
+                    render_media_type_popup(frame, &self.media_type_popup, popup_area)
+                }
+                (AppMode::Delete, View::Playlists) => {
+                    let name = self
+                        .get_selected_playlist()
+                        .map_or(String::new(), |playlist| playlist.name);
+                    render_confirm_popup(
+                        frame,
+                        popup_area,
+                        " Delete Playlist ",
+                        format!(
+                            " Are you sure you want to delete {}? Its songs stay in the library. ",
+                            name
+                        ),
+                    )
+                }
+                (AppMode::Edit, View::Artists) => {
+                    render_text_popup(frame, " Rename Artist ", &self.rename_box, popup_area)
+                }
+                (AppMode::Export, _) => render_text_popup(
+                    frame,
+                    " Export Songs to CSV ",
+                    &self.export_box,
+                    popup_area,
+                ),
+                (AppMode::Merge, View::Artists) => {
+                    if let (Some(from), Some(into)) = (&self.merge_from, self.get_selected_artist())
+                    {
+                        render_confirm_popup(
+                            frame,
+                            popup_area,
+                            " Merge Artists ",
+                            format!(
+                                " Move all {} songs and {} albums of {} to {}? ",
+                                from.song_count, from.album_count, from.name, into.name
+                            ),
+                        )
+                    }
+                }
+                (AppMode::History, View::Songs) => {
+                    let title = self
+                        .songs
+                        .get(self.selected_row)
+                        .map_or(String::new(), |song| format!(" History of {} ", song.title));
+                    render_history_popup(frame, &title, &self.history, self.history_row, popup_area)
+                }
+                (AppMode::Delete, View::Songs) if !self.marked.is_empty() => render_confirm_popup(
+                    frame,
+                    popup_area,
+                    " Delete Songs ",
+                    format!(
+                        " Move {} to the trash? U undoes one song at a time, or restore them from the trash. ",
+                        songs(self.marked.len())
+                    ),
+                ),
+                (AppMode::Delete, View::Songs) => render_confirm_popup(
+                    frame,
+                    popup_area,
+                    " Delete Song ",
+                    " Move this song to the trash? You can undo it with U. ".to_owned(),
+                ),
+                (AppMode::Delete, View::Trash) => render_confirm_popup(
+                    frame,
+                    popup_area,
+                    " Delete Forever ",
+                    " Are you sure you want to delete this song for good? This can't be undone. "
+                        .to_owned(),
+                ),
+                (AppMode::Delete, View::Albums) => {
+                    let song_count = self
+                        .get_selected_album()
+                        .map_or(0, |album| album.song_count);
+                    let message = match song_count {
+                        0 => " Are you sure you want to delete this album? ".to_owned(),
+                        _ => format!(
+                            " Move the {} on this album to the trash? \
+                            The album goes once nothing in the trash is on it. ",
+                            songs(song_count as usize)
+                        ),
+                    };
+                    render_confirm_popup(frame, popup_area, " Delete Album ", message)
+                }
                 _ => {}
             }
         }
@@ -171,28 +663,74 @@ impl App {
             frame.render_widget(
             Text::raw(format!(
                 "app mode: {:?}, searchbar mode: {:#?}, esc mode: {:#?}, title box: {:?}, title box input mode: {:?}\n
-                selected row: {:?}",
+                selected row: {:?}, artist id: {:?}, album id: {:?}",
                 &self.mode,
                 self.searchbar.get_input_mode(),
                 self.esc_mode,
                 self.new_popup.title_box.get_input(),
                 self.new_popup.title_box.get_input_mode(),
                 self.selected_row,
+                self.songs.get(self.selected_row).map(|song| song.artist_id),
+                self.songs.get(self.selected_row).map(|song| song.album_id),
             )),
-            self.get_layout(&frame)[2],
+            self.get_layout(frame)[2],
         );
         }
     }
 
     // Handle keyboard input events
-    async fn handle_events(&mut self) -> io::Result<()> {
-        match event::read()? {
+    async fn handle_events(&mut self, event: Event) {
+        match event {
             Event::Key(key_event) if key_event.kind == KeyEventKind::Press => {
                 self.handle_keypress_event(key_event).await
             }
+            Event::Paste(text) => self.paste(&text),
             _ => {}
         };
-        Ok(())
+    }
+
+    // pasted text goes into the box being typed into all at once, rather than as keys that
+    // could submit the form or switch views halfway through
+    fn paste(&mut self, text: &str) {
+        if !self.esc_mode {
+            return;
+        }
+        let is_new = self.mode == AppMode::New;
+        let text_box = match self.mode {
+            AppMode::Search => Some(&mut self.searchbar),
+            AppMode::Export => Some(&mut self.export_box),
+            AppMode::Edit if self.view == View::Artists => Some(&mut self.rename_box),
+            AppMode::New | AppMode::Edit if self.view == View::Albums => {
+                match is_new {
+                    true => self.new_album_popup.paste(text),
+                    false => self.edit_album_popup.paste(text),
+                }
+                None
+            }
+            AppMode::New if self.view == View::MediaTypes => {
+                self.media_type_popup.paste(text);
+                None
+            }
+            AppMode::New | AppMode::Edit if self.view == View::Playlists => match is_new {
+                true => Some(self.new_playlist_popup.focused_box()),
+                false => Some(self.edit_playlist_popup.focused_box()),
+            },
+            AppMode::New => {
+                self.new_popup.paste(text);
+                None
+            }
+            AppMode::Edit => {
+                self.edit_popup.paste(text);
+                None
+            }
+            _ => None,
+        };
+        if let Some(text_box) = text_box {
+            text_box.insert_str(text);
+        }
+        if self.mode == AppMode::Search {
+            self.schedule_search();
+        }
     }
 
     // logic for input depending on app's state
@@ -201,311 +739,511 @@ impl App {
             match key_event.code {
                 // if not in esc mode, toggle app mode depending on key pressed
                 KeyCode::Char('q') => self.exit(),
-                KeyCode::Char('/') => self.toggle_search(),
-                KeyCode::Char('n') => self.toggle_new_song(),
-                KeyCode::Char('e') => self.toggle_edit_song(),
-                KeyCode::Char('d') => self.toggle_delete_song(),
+                KeyCode::Char('/') => {
+                    // search always looks through every song, so leave the albums behind
+                    self.view = View::Songs;
+                    self.album = None;
+                    self.toggle_search();
+                }
+                KeyCode::Char('a') => self.toggle_albums().await,
+                KeyCode::Char('u') => self.replay_change(true).await,
+                KeyCode::Char('r') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                    self.replay_change(false).await
+                }
+                KeyCode::Char('r') => self.toggle_artists().await,
+                KeyCode::Char('p') => self.toggle_playlists().await,
+                KeyCode::Char('t') => self.toggle_trash().await,
+                KeyCode::Enter if self.view == View::Albums => self.open_selected_album().await,
+                KeyCode::Enter if self.view == View::Playlists => {
+                    self.open_selected_playlist().await
+                }
+                KeyCode::Enter if self.view == View::Trash => self.restore_selected_song().await,
+                KeyCode::Char('n') => match self.view {
+                    View::Songs => {
+                        self.load_vocabulary().await;
+                        self.toggle_new_song()
+                    }
+                    View::Albums => {
+                        self.load_vocabulary().await;
+                        self.toggle_new_album()
+                    }
+                    View::Playlists => self.toggle_new_playlist(),
+                    View::MediaTypes if self.allow_adding_media_types => {
+                        self.toggle_new_media_type()
+                    }
+                    View::MediaTypes => self.notify(
+                        Severity::Warning,
+                        "Adding media types is turned off, see [media_types] in the config file",
+                    ),
+                    View::Artists | View::Playlist | View::Trash => {}
+                },
+                // a range being marked ends when the songs in it are edited or deleted
+                KeyCode::Char('e') if self.view == View::Songs => {
+                    self.mark_range().await;
+                    match self.marked.is_empty() && self.songs.len() == 0 {
+                        true => self.notify(Severity::Warning, "There are no songs to edit"),
+                        false => {
+                            self.load_vocabulary().await;
+                            self.toggle_edit_song()
+                        }
+                    }
+                }
+                KeyCode::Char('d') if self.view == View::Songs => {
+                    self.mark_range().await;
+                    match self.marked.is_empty() && self.songs.len() == 0 {
+                        true => self.notify(Severity::Warning, "There are no songs to delete"),
+                        false => self.toggle_delete_song(),
+                    }
+                }
+                // the songs view is handled above, along with the marked songs
+                KeyCode::Char('e') => match self.view {
+                    View::Albums => {
+                        self.load_vocabulary().await;
+                        self.toggle_edit_album()
+                    }
+                    View::Artists => self.toggle_rename_artist(),
+                    View::Playlists => self.toggle_edit_playlist(),
+                    View::Songs | View::Playlist | View::Trash | View::MediaTypes => {}
+                },
+                KeyCode::Char('d') => match self.view {
+                    View::Albums => self.toggle_delete_song(),
+                    View::Trash if !self.trash.is_empty() => self.toggle_delete_song(),
+                    View::Playlists if self.get_selected_playlist().is_some() => {
+                        self.toggle_delete_song()
+                    }
+                    // taking a song off a playlist doesn't delete anything,
+                    // so it needs no confirmation
+                    View::Playlist => self.remove_selected_entry().await,
+                    View::Songs
+                    | View::Artists
+                    | View::Playlists
+                    | View::Trash
+                    | View::MediaTypes => {}
+                },
+                KeyCode::Char('+') if self.view == View::Songs => {
+                    self.add_selected_song_to_playlist().await
+                }
+                KeyCode::Char('K') if self.view == View::Playlist => {
+                    self.move_selected_entry(true).await
+                }
+                KeyCode::Char('J') if self.view == View::Playlist => {
+                    self.move_selected_entry(false).await
+                }
+                KeyCode::Up
+                    if self.view == View::Playlist
+                        && key_event.modifiers.contains(KeyModifiers::SHIFT) =>
+                {
+                    self.move_selected_entry(true).await
+                }
+                KeyCode::Down
+                    if self.view == View::Playlist
+                        && key_event.modifiers.contains(KeyModifiers::SHIFT) =>
+                {
+                    self.move_selected_entry(false).await
+                }
+                KeyCode::Char('m') if self.view == View::Artists => self.pick_artist_to_merge(),
+                KeyCode::Char('m') => self.toggle_media_types().await,
+                KeyCode::Char('x') if self.view == View::Songs => self.toggle_export(),
+                KeyCode::Char('f') if self.view == View::Songs => self.toggle_fuzzy(),
+                KeyCode::Char('h') if self.view == View::Songs => self.toggle_history().await,
+                KeyCode::Char(' ') if self.view == View::Songs => self.toggle_mark(),
+                KeyCode::Char('v') if self.view == View::Songs => self.toggle_mark_range().await,
+                KeyCode::Char('*') if self.view == View::Songs => self.mark_all().await,
+                // the number keys sort by the columns from left to right
+                KeyCode::Char(digit @ '1'..='5') if self.view == View::Songs => {
+                    let column = digit as usize - '1' as usize;
+                    self.sort_songs(Field::ALL[column]).await
+                }
+                KeyCode::Esc => {
+                    self.merge_from = None;
+                    self.mark_anchor = None;
+                    self.marked.clear();
+                }
                 KeyCode::Up | KeyCode::Char('k') => {
-                    // scroll up in the table
-                    self.selected_row = if self.selected_row == 0 { // wrap around if up is pressed enough times
-                        self.songs.len() - 1
-                    } else {    // go up otherwise
-                        self.selected_row - 1
+                    // scroll up in the table of the current view
+                    let row_count = self.row_count();
+                    let selected_row = self.selected_row_mut();
+                    *selected_row = if *selected_row == 0 {
+                        // wrap around if up is pressed enough times
+                        row_count.saturating_sub(1)
+                    } else {
+                        // go up otherwise
+                        *selected_row - 1
                     };
                 }
                 KeyCode::Down | KeyCode::Char('j') => {
-                    // scroll down in the table
-                    self.selected_row = if self.selected_row + 1 >= self.songs.len() {
+                    // scroll down in the table of the current view
+                    let row_count = self.row_count();
+                    let selected_row = self.selected_row_mut();
+                    *selected_row = if *selected_row + 1 >= row_count {
                         0
                     } else {
-                        self.selected_row + 1
+                        *selected_row + 1
                     };
                 }
+                // a page at a time, stopping at the ends instead of wrapping around
+                KeyCode::PageUp => {
+                    let page = self.table_height.max(1);
+                    let selected_row = self.selected_row_mut();
+                    *selected_row = selected_row.saturating_sub(page);
+                }
+                KeyCode::PageDown => {
+                    let page = self.table_height.max(1);
+                    let last_row = self.row_count().saturating_sub(1);
+                    let selected_row = self.selected_row_mut();
+                    *selected_row = (*selected_row + page).min(last_row);
+                }
+                KeyCode::Home | KeyCode::Char('g') => *self.selected_row_mut() = 0,
+                KeyCode::End | KeyCode::Char('G') => {
+                    *self.selected_row_mut() = self.row_count().saturating_sub(1)
+                }
                 _ => {}
             }
         } else {
-            match key_event.code {
-                // return to Normal state if in esc mode
-                KeyCode::Esc => {
-                    self.mode = AppMode::Normal;
-                    self.esc_mode = false;
-                    self.searchbar.set_input_mode(InputMode::Normal);
-                    self.new_popup.set_all_input_modes(InputMode::Normal);
-                    self.edit_popup.set_all_input_modes(InputMode::Normal);
-                }
-                _ => {}
+            // return to Normal state if in esc mode
+            if key_event.code == KeyCode::Esc {
+                self.mode = AppMode::Normal;
+                self.esc_mode = false;
+                self.searchbar.set_input_mode(InputMode::Normal);
+                self.new_popup.set_all_input_modes(InputMode::Normal);
+                self.edit_popup.set_all_input_modes(InputMode::Normal);
+                self.new_album_popup.set_all_input_modes(InputMode::Normal);
+                self.edit_album_popup.set_all_input_modes(InputMode::Normal);
+                self.new_playlist_popup
+                    .set_all_input_modes(InputMode::Normal);
+                self.edit_playlist_popup
+                    .set_all_input_modes(InputMode::Normal);
+                self.media_type_popup.set_all_input_modes(InputMode::Normal);
+                self.rename_box.set_input_mode(InputMode::Normal);
+                self.export_box.set_input_mode(InputMode::Normal);
+                self.merge_from = None;
             }
 
             // Perform functions depending on app mode
             match self.mode {
                 AppMode::Search => match key_event.code {
-                    KeyCode::Char(input_char) => {
-                        // enter query as the user types
-                        self.searchbar.enter_char(input_char);
-                        self.submit_search_query(self.searchbar.get_input().to_string())
-                            .await;
+                    KeyCode::Char('f') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                        self.toggle_fuzzy()
                     }
-                    KeyCode::Backspace => self.searchbar.delete_char(),
-                    KeyCode::Left => self.searchbar.move_cursor_left(),
-                    KeyCode::Right => self.searchbar.move_cursor_right(),
+                    // keep the query to fix if it has a syntax error
+                    KeyCode::Enter if self.search_error.is_some() => {}
                     KeyCode::Enter => {
-                        // exit Search mode and clear searchbar if Enter is pressed
-                        self.submit_search_query(self.searchbar.get_input().to_string())
-                            .await;
+                        // exit Search mode and clear searchbar if Enter is pressed,
+                        // without waiting for the pause
+                        self.pending_search = None;
+                        self.submit_search_query(self.searchbar.get_input().to_string());
                         self.searchbar.submit_message();
                         self.toggle_search();
                     }
+                    _ => {
+                        // search as the user types, once they pause
+                        let query = self.searchbar.get_input().clone();
+                        if self.searchbar.handle_key(key_event)
+                            && *self.searchbar.get_input() != query
+                        {
+                            self.schedule_search();
+                        }
+                    }
+                },
+                AppMode::New | AppMode::Edit if self.view == View::Albums => {
+                    self.handle_album_popup_keypress(key_event).await
+                }
+                AppMode::New | AppMode::Edit if self.view == View::Playlists => {
+                    self.handle_playlist_popup_keypress(key_event).await
+                }
+                AppMode::New if self.view == View::MediaTypes => {
+                    self.handle_media_type_popup_keypress(key_event).await
+                }
+                AppMode::Edit if self.view == View::Artists => match key_event.code {
+                    KeyCode::Enter if !self.rename_box.get_input().is_empty() => {
+                        // rename the artist and return to Normal mode
+                        if let Some(selected_artist) = self.get_selected_artist() {
+                            let new_name = self.rename_box.get_input().to_string();
+                            let result = self
+                                .store
+                                .rename_artist(selected_artist.id, new_name.clone())
+                                .await
+                                .context("rename the artist");
+                            self.report(
+                                result,
+                                format!("Renamed {} to {}", selected_artist.name, new_name),
+                            );
+                        }
+                        self.toggle_rename_artist();
+                        self.refresh_artists().await;
+                    }
+                    _ => {
+                        self.rename_box.handle_key(key_event);
+                    }
+                },
+                AppMode::Export => match key_event.code {
+                    KeyCode::Enter if !self.export_box.get_input().is_empty() => {
+                        // write the songs on screen to the file and return to Normal mode
+                        // only the songs near the screen are loaded, so fetch the whole list again
+                        let path = PathBuf::from(self.export_box.get_input());
+                        match self.export_songs(&path).await {
+                            Ok(count) => self.notify(
+                                Severity::Success,
+                                format!("Exported {} to {}", songs(count), path.display()),
+                            ),
+                            Err(error) => self.report_error(error),
+                        }
+                        self.toggle_export();
+                    }
+                    _ => {
+                        self.export_box.handle_key(key_event);
+                    }
+                },
+                AppMode::History => match key_event.code {
+                    KeyCode::Up | KeyCode::Char('k') => {
+                        self.history_row = self.history_row.saturating_sub(1)
+                    }
+                    KeyCode::Down | KeyCode::Char('j') => {
+                        self.history_row =
+                            (self.history_row + 1).min(self.history.len().saturating_sub(1))
+                    }
+                    KeyCode::Char('h') => self.toggle_history().await,
                     _ => {}
                 },
+                // merge the artists and return to Normal mode if capital Y is pressed
+                AppMode::Merge if key_event.code == KeyCode::Char('Y') => {
+                    if let (Some(from), Some(into)) =
+                        (self.merge_from.take(), self.get_selected_artist())
+                    {
+                        let result = self
+                            .store
+                            .merge_artists(from.id, into.id)
+                            .await
+                            .context("merge the artists");
+                        self.report(result, format!("Merged {} into {}", from.name, into.name));
+                    }
+                    self.mode = AppMode::Normal;
+                    self.esc_mode = false;
+                    self.refresh_artists().await;
+                }
                 AppMode::New => {
-                    if self.new_popup.do_all_boxes_have_text() {
-                        // check if each box has some input
-                        match key_event.code {
-                            KeyCode::Enter => {
-                                // submit new song and return to Normal mode if enter is pressed
-                                self.new_popup.submit(&self.pool).await;
-                                self.toggle_new_song();
-                                self.new_popup.set_all_input_modes(InputMode::Normal);
-                                self.submit_search_query("".to_owned()).await;
-                            }
-                            _ => {}
-                        }
+                    // check the input, and flag what's wrong with it if it can't be added
+                    if key_event.code == KeyCode::Enter && !self.new_popup.can_submit() {
+                        self.new_popup.show_errors();
+                    } else if key_event.code == KeyCode::Enter {
+                        // submit new song and return to Normal mode if enter is pressed
+                        let result = self.new_popup.submit(self.store.as_ref()).await;
+                        self.report(result, "Added the song");
+                        self.toggle_new_song();
+                        self.new_popup.set_all_input_modes(InputMode::Normal);
+                        self.refresh_songs().await;
                     }
                     // This code lets the user input characters and edit each box.
                     // It also lets them use "Tab" to cycle through boxes
-                    if self.new_popup.title_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.new_popup.title_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.new_popup.title_box.delete_char(),
-                            KeyCode::Left => self.new_popup.title_box.move_cursor_left(),
-                            KeyCode::Right => self.new_popup.title_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.new_popup.artist_box.set_input_mode(InputMode::Editing);
-                                self.new_popup.title_box.set_input_mode(InputMode::Normal);
-                            }
-                            _ => {}
-                        }
-                    } else if self.new_popup.artist_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.new_popup.artist_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.new_popup.artist_box.delete_char(),
-                            KeyCode::Left => self.new_popup.artist_box.move_cursor_left(),
-                            KeyCode::Right => self.new_popup.artist_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.new_popup.album_box.set_input_mode(InputMode::Editing);
-                                self.new_popup.artist_box.set_input_mode(InputMode::Normal);
-                            }
-                            _ => {}
-                        }
-                    } else if self.new_popup.album_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.new_popup.album_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.new_popup.album_box.delete_char(),
-                            KeyCode::Left => self.new_popup.album_box.move_cursor_left(),
-                            KeyCode::Right => self.new_popup.album_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.new_popup
-                                    .release_year_box
-                                    .set_input_mode(InputMode::Editing);
-                                self.new_popup.album_box.set_input_mode(InputMode::Normal);
-                            }
-                            _ => {}
-                        }
-                    } else if self.new_popup.release_year_box.get_input_mode() == InputMode::Editing
-                    {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                if input_char.is_numeric() {
-                                    // only lets the user input numbers
-                                    self.new_popup.release_year_box.enter_char(input_char);
-                                }
-                            }
-                            KeyCode::Backspace => self.new_popup.release_year_box.delete_char(),
-                            KeyCode::Left => self.new_popup.release_year_box.move_cursor_left(),
-                            KeyCode::Right => self.new_popup.release_year_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.new_popup
-                                    .media_type_box
-                                    .set_input_mode(InputMode::Editing);
-                                self.new_popup
-                                    .release_year_box
-                                    .set_input_mode(InputMode::Normal);
-                            }
-                            KeyCode::Enter => {
-                                self.new_popup.release_year_box.submit_message();
-                            }
-                            _ => {}
-                        }
-                    } else if self.new_popup.media_type_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.new_popup.media_type_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.new_popup.media_type_box.delete_char(),
-                            KeyCode::Left => self.new_popup.media_type_box.move_cursor_left(),
-                            KeyCode::Right => self.new_popup.media_type_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.new_popup.title_box.set_input_mode(InputMode::Editing);
-                                self.new_popup
-                                    .media_type_box
-                                    .set_input_mode(InputMode::Normal);
-                            }
-                            _ => {}
-                        }
+                    match key_event.code {
+                        KeyCode::Tab => self.new_popup.complete_or_focus_next(),
+                        _ => self.new_popup.handle_key(key_event),
                     }
                 }
                 AppMode::Edit => {
-                    if self.edit_popup.do_all_boxes_have_text() {
-                        // check if each box has some input
-                        match key_event.code {
-                            KeyCode::Enter => {
-                                // submit edited song and return to Normal mode if enter is pressed
-                                let selected_id = self.get_selected_song().id;
-                                self.edit_popup.set_song_id(selected_id);
-                                self.edit_popup.submit(&self.pool).await;
-                                self.toggle_edit_song();
-                                self.edit_popup.set_all_input_modes(InputMode::Normal);
-                                self.submit_search_query("".to_owned()).await;
-                            }
-                            _ => {}
-                        }
-                    }
-                    // Same as the code with the New popup
-                    if self.edit_popup.title_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.edit_popup.title_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.edit_popup.title_box.delete_char(),
-                            KeyCode::Left => self.edit_popup.title_box.move_cursor_left(),
-                            KeyCode::Right => self.edit_popup.title_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.edit_popup
-                                    .artist_box
-                                    .set_input_mode(InputMode::Editing);
-                                self.edit_popup.title_box.set_input_mode(InputMode::Normal);
-                            }
-                            KeyCode::Enter => {
-                                self.edit_popup.title_box.submit_message();
-                            }
-                            _ => {}
-                        }
-                    } else if self.edit_popup.artist_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.edit_popup.artist_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.edit_popup.artist_box.delete_char(),
-                            KeyCode::Left => self.edit_popup.artist_box.move_cursor_left(),
-                            KeyCode::Right => self.edit_popup.artist_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.edit_popup.album_box.set_input_mode(InputMode::Editing);
-                                self.edit_popup.artist_box.set_input_mode(InputMode::Normal);
-                            }
-                            KeyCode::Enter => {
-                                self.edit_popup.artist_box.submit_message();
-                            }
-                            _ => {}
-                        }
-                    } else if self.edit_popup.album_box.get_input_mode() == InputMode::Editing {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.edit_popup.album_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.edit_popup.album_box.delete_char(),
-                            KeyCode::Left => self.edit_popup.album_box.move_cursor_left(),
-                            KeyCode::Right => self.edit_popup.album_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.edit_popup
-                                    .release_year_box
-                                    .set_input_mode(InputMode::Editing);
-                                self.edit_popup.album_box.set_input_mode(InputMode::Normal);
+                    // check the input, and flag what's wrong with it if it can't be saved
+                    if key_event.code == KeyCode::Enter && !self.edit_popup.can_submit() {
+                        self.edit_popup.show_errors();
+                    } else if key_event.code == KeyCode::Enter {
+                        // submit edited song and return to Normal mode if enter is pressed
+                        if !self.edit_popup.is_bulk() {
+                            if let Some(selected_song) = self.get_selected_song() {
+                                self.edit_popup.set_song_id(selected_song.id);
                             }
-                            KeyCode::Enter => {
-                                self.edit_popup.album_box.submit_message();
-                            }
-                            _ => {}
                         }
-                    } else if self.edit_popup.release_year_box.get_input_mode()
-                        == InputMode::Editing
-                    {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                if input_char.is_numeric() {
-                                    // only lets the user input numbers
-                                    self.edit_popup.release_year_box.enter_char(input_char);
-                                }
+                        // the marks stay if the edit failed, so it can be tried again
+                        match self.edit_popup.submit(self.store.as_ref()).await {
+                            Ok(count) if self.edit_popup.is_bulk() => {
+                                self.marked.clear();
+                                self.notify(
+                                    Severity::Success,
+                                    format!("Updated {}", songs(count as usize)),
+                                )
                             }
-                            KeyCode::Backspace => self.edit_popup.release_year_box.delete_char(),
-                            KeyCode::Left => self.edit_popup.release_year_box.move_cursor_left(),
-                            KeyCode::Right => self.edit_popup.release_year_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.edit_popup
-                                    .media_type_box
-                                    .set_input_mode(InputMode::Editing);
-                                self.edit_popup
-                                    .release_year_box
-                                    .set_input_mode(InputMode::Normal);
-                            }
-                            KeyCode::Enter => {
-                                self.edit_popup.release_year_box.submit_message();
-                            }
-                            _ => {}
+                            Ok(_) => self.notify(Severity::Success, "Updated the song"),
+                            Err(error) => self.report_error(error),
                         }
-                    } else if self.edit_popup.media_type_box.get_input_mode() == InputMode::Editing
-                    {
-                        match key_event.code {
-                            KeyCode::Char(input_char) => {
-                                self.edit_popup.media_type_box.enter_char(input_char);
-                            }
-                            KeyCode::Backspace => self.edit_popup.media_type_box.delete_char(),
-                            KeyCode::Left => self.edit_popup.media_type_box.move_cursor_left(),
-                            KeyCode::Right => self.edit_popup.media_type_box.move_cursor_right(),
-                            KeyCode::Tab => {
-                                self.edit_popup.title_box.set_input_mode(InputMode::Editing);
-                                self.edit_popup
-                                    .media_type_box
-                                    .set_input_mode(InputMode::Normal);
-                            }
-                            KeyCode::Enter => {
-                                self.edit_popup.media_type_box.submit_message();
-                            }
-                            _ => {}
+                        self.toggle_edit_song();
+                        self.edit_popup.set_all_input_modes(InputMode::Normal);
+                        self.refresh_songs().await;
+                    }
+                    // Same as the code with the New popup
+                    match key_event.code {
+                        KeyCode::Tab => self.edit_popup.complete_or_focus_next(),
+                        _ => self.edit_popup.handle_key(key_event),
+                    }
+                }
+                // delete the playlist if capital Y is pressed in the playlists view
+                AppMode::Delete
+                    if self.view == View::Playlists && key_event.code == KeyCode::Char('Y') =>
+                {
+                    if let Some(selected_playlist) = self.get_selected_playlist() {
+                        let result = self
+                            .store
+                            .delete_playlist(selected_playlist.id)
+                            .await
+                            .context("delete the playlist");
+                        self.report(result, format!("Deleted {}", selected_playlist.name));
+                        // "+" can't add to a playlist that's gone
+                        if self
+                            .playlist
+                            .as_ref()
+                            .is_some_and(|playlist| playlist.id == selected_playlist.id)
+                        {
+                            self.playlist = None;
                         }
                     }
+                    self.toggle_delete_song();
+                    self.refresh_playlists().await;
+                }
+                // trash the album's songs and delete it if capital Y is pressed in the albums view
+                AppMode::Delete
+                    if self.view == View::Albums && key_event.code == KeyCode::Char('Y') =>
+                {
+                    if let Some(selected_album) = self.get_selected_album() {
+                        self.delete_album(selected_album).await;
+                    }
+                    self.toggle_delete_song();
+                    self.refresh_albums().await;
                 }
-                AppMode::Delete => match key_event.code {
-                    KeyCode::Char('Y') => {
-                        // delete song and return to Normal mode if capital Y is pressed
-                        let selected_song = self.get_selected_song();
+                AppMode::Delete
+                    if self.view == View::Trash && key_event.code == KeyCode::Char('Y') =>
+                {
+                    if let Some(trashed) = self.trash.get(self.selected_trash_row).cloned() {
+                        let result = self
+                            .store
+                            .purge_song(trashed.song.id)
+                            .await
+                            .context("delete the song");
+                        self.report(result, format!("Deleted {} for good", trashed.song.title));
+                    }
+                    self.toggle_delete_song();
+                    self.refresh_trash().await;
+                }
+                // delete song and return to Normal mode if capital Y is pressed
+                AppMode::Delete if key_event.code == KeyCode::Char('Y') => {
+                    if !self.marked.is_empty() {
+                        self.purge_marked_songs().await;
+                    } else if let Some(selected_song) = self.get_selected_song() {
                         self.purge_song(selected_song).await;
-                        self.toggle_delete_song();
-                        self.submit_search_query("".to_owned()).await;
                     }
-                    _ => {}
-                },
+                    self.toggle_delete_song();
+                    self.refresh_songs().await;
+                }
                 _ => {}
             }
         }
     }
 
-    // returns the layout for the app
+    // typing, Tab and Enter for whichever album popup is open
+    async fn handle_album_popup_keypress(&mut self, key_event: KeyEvent) {
+        let is_new = self.mode == AppMode::New;
+        let selected_id = self.get_selected_album().map_or(0, |album| album.id);
+        let popup = if is_new {
+            &mut self.new_album_popup
+        } else {
+            &mut self.edit_album_popup
+        };
+        match key_event.code {
+            // submit album and return to Normal mode if every box has some input
+            KeyCode::Enter if popup.do_all_boxes_have_text() => {
+                popup.set_album_id(selected_id);
+                let result = popup.submit(self.store.as_ref()).await;
+                popup.set_all_input_modes(InputMode::Normal);
+                self.report(
+                    result,
+                    if is_new {
+                        "Added the album"
+                    } else {
+                        "Updated the album"
+                    },
+                );
+                if is_new {
+                    self.toggle_new_album();
+                } else {
+                    self.toggle_edit_album();
+                }
+                self.refresh_albums().await;
+            }
+            KeyCode::Tab => popup.focus_next_field(),
+            _ => popup.handle_key(key_event),
+        }
+    }
+
+    // typing, Tab and Enter for whichever playlist popup is open
+    async fn handle_playlist_popup_keypress(&mut self, key_event: KeyEvent) {
+        let is_new = self.mode == AppMode::New;
+        let selected_id = self
+            .get_selected_playlist()
+            .map_or(0, |playlist| playlist.id);
+        let popup = if is_new {
+            &mut self.new_playlist_popup
+        } else {
+            &mut self.edit_playlist_popup
+        };
+        match key_event.code {
+            // submit playlist and return to Normal mode if it has a name
+            KeyCode::Enter if popup.has_name() => {
+                popup.set_playlist_id(selected_id);
+                let result = popup.submit(self.store.as_ref()).await;
+                popup.set_all_input_modes(InputMode::Normal);
+                self.report(
+                    result,
+                    if is_new {
+                        "Added the playlist"
+                    } else {
+                        "Updated the playlist"
+                    },
+                );
+                if is_new {
+                    self.toggle_new_playlist();
+                } else {
+                    self.toggle_edit_playlist();
+                }
+                self.refresh_playlists().await;
+            }
+            KeyCode::Tab => popup.focus_next_field(),
+            _ => popup.handle_key(key_event),
+        }
+    }
+
+    // typing, Tab and Enter for the new media type popup. A media type that can't be added
+    // leaves the popup open to fix it.
+    async fn handle_media_type_popup_keypress(&mut self, key_event: KeyEvent) {
+        let popup = &mut self.media_type_popup;
+        match key_event.code {
+            KeyCode::Enter if popup.has_name() => {
+                let name = popup.name_box.get_input().trim().to_owned();
+                match popup.submit(self.store.as_ref()).await {
+                    Ok(_) => {
+                        self.notify(
+                            Severity::Success,
+                            format!("Added {} to the media types", name),
+                        );
+                        self.media_type_popup.set_all_input_modes(InputMode::Normal);
+                        self.toggle_new_media_type();
+                        self.refresh_media_types().await;
+                    }
+                    Err(error) => self.report_error(error),
+                }
+            }
+            KeyCode::Tab => popup.focus_next_field(),
+            _ => popup.handle_key(key_event),
+        }
+    }
+
+    // returns the layout for the app: the searchbar, the table, the debug info if it's on,
+    // and a line for the status bar at the bottom
     fn get_layout(&self, frame: &Frame) -> Rc<[Rect]> {
-        let frame_percentage = if self.debug { 70 } else { 90 };
         // layout constraints if debug
         if self.debug {
             Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Percentage(80 - frame_percentage),
-                    Constraint::Percentage(frame_percentage),
+                    Constraint::Percentage(10),
+                    Constraint::Min(0),
                     Constraint::Percentage(20),
+                    Constraint::Length(1),
                 ])
                 .split(frame.size())
         } else {
@@ -513,8 +1251,9 @@ impl App {
             Layout::default()
                 .direction(Direction::Vertical)
                 .constraints([
-                    Constraint::Percentage(100 - frame_percentage),
-                    Constraint::Percentage(frame_percentage),
+                    Constraint::Percentage(10),
+                    Constraint::Min(0),
+                    Constraint::Length(1),
                 ])
                 .split(frame.size())
         }
@@ -568,8 +1307,14 @@ impl App {
 
         self.edit_popup.clear_all_boxes(); // clear textboxes and populate with selected song info
         if self.mode == AppMode::Edit {
-            let selected_song = &self.get_selected_song();
-            self.edit_popup.populate_textboxes_with_song(selected_song);
+            // with songs marked the boxes start empty, and only what's typed in is changed
+            self.edit_popup
+                .set_song_ids(self.marked.iter().copied().collect());
+            if let (false, Some(selected_song)) =
+                (self.edit_popup.is_bulk(), self.get_selected_song())
+            {
+                self.edit_popup.populate_textboxes_with_song(&selected_song);
+            }
 
             if !self.edit_popup.are_any_boxes_editing_mode() {
                 // set title box mode to Editing
@@ -578,82 +1323,1238 @@ impl App {
         }
     }
 
-    fn toggle_delete_song(&mut self) {
-        // Commenting this function would be redundant
+    fn toggle_new_album(&mut self) {
+        // same as toggle_new_song, for the album popup
         self.mode = if self.mode == AppMode::Normal {
-            AppMode::Delete
+            AppMode::New
         } else {
             AppMode::Normal
         };
         self.esc_mode = !self.esc_mode;
+        if self.mode == AppMode::New {
+            self.new_album_popup.focus_first_field();
+        }
     }
 
-    async fn submit_search_query(&mut self, query: String) {
-        // submit query, if it fails print an error
-        self.songs = match get_songs_matching(&self.pool, query).await {
-            Ok(songs) => songs,
-            Err(error) => {
-                eprintln!("Error searching for songs: {}", error);
-                vec![Song::new(
-                    500,
-                    " Error",
-                    "Searching",
-                    "Songs. ",
-                    500,
-                    "Error ",
-                )]
-            }
+    fn toggle_edit_album(&mut self) {
+        // same as toggle_edit_song, for the album popup
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::Edit
+        } else {
+            AppMode::Normal
         };
+        self.esc_mode = !self.esc_mode;
+
+        self.edit_album_popup.clear_all_boxes();
+        if self.mode == AppMode::Edit {
+            match self.get_selected_album() {
+                Some(selected_album) => {
+                    self.edit_album_popup
+                        .populate_textboxes_with_album(&selected_album);
+                    self.edit_album_popup.focus_first_field();
+                }
+                // nothing to edit, so don't open the popup
+                None => {
+                    self.mode = AppMode::Normal;
+                    self.esc_mode = false;
+                }
+            }
+        }
     }
 
-    // correlates the selected_row to a song ID, returns song
-    fn get_selected_song(&mut self) -> Song {
-        let mut ids = Vec::new(); // create ids vec
+    // switch between every song and the albums table
+    async fn toggle_albums(&mut self) {
+        match self.view {
+            View::Songs | View::Artists | View::Playlists | View::MediaTypes => {
+                self.view = View::Albums;
+                self.refresh_albums().await;
+            }
+            // "A" leaves an open playlist for every song, like it leaves an open album
+            View::Albums | View::Playlist | View::Trash => self.show_all_songs().await,
+        }
+    }
 
-        for song in &self.songs {
-            // add song id's to ids vec
-            ids.push(song.id);
+    // switch between every song and the artists table
+    async fn toggle_artists(&mut self) {
+        match self.view {
+            View::Songs
+            | View::Albums
+            | View::Playlists
+            | View::Playlist
+            | View::Trash
+            | View::MediaTypes => {
+                self.view = View::Artists;
+                self.merge_from = None;
+                self.refresh_artists().await;
+            }
+            View::Artists => self.show_all_songs().await,
         }
-        // get selected song id using index of selected row
-        if let Some(selected_id) = ids.get(self.selected_row) {
-            for song in &self.songs {
-                // return the song matching the id
-                if song.id == *selected_id {
-                    return song.clone();
-                }
+    }
+
+    // switch between every song and the playlists table.
+    // From an open playlist, go back to the list.
+    async fn toggle_playlists(&mut self) {
+        match self.view {
+            View::Playlists => self.show_all_songs().await,
+            _ => {
+                self.view = View::Playlists;
+                self.refresh_playlists().await;
             }
         }
-        // if no songs in ids, there are no songs in database.
-        // print error, return to New mode so the user can add a song
-        eprintln!("No songs in database");
-        self.mode = AppMode::New;
-        Song::default() // lol
     }
 
-    // delete the song and print any errors
-    async fn purge_song(&mut self, song: Song) {
-        match delete_song(&self.pool, song.id).await {
-            Ok(_) => {}
-            Err(error) => {
-                eprintln!("Error deleting songs: {}", error)
+    // switch between every song and the media types on the list
+    async fn toggle_media_types(&mut self) {
+        match self.view {
+            View::MediaTypes => self.show_all_songs().await,
+            _ => {
+                self.view = View::MediaTypes;
+                self.refresh_media_types().await;
             }
+        }
+    }
+
+    fn toggle_new_media_type(&mut self) {
+        // same as toggle_new_playlist, for the media type popup
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::New
+        } else {
+            AppMode::Normal
         };
+        self.esc_mode = !self.esc_mode;
+        self.media_type_popup.clear_all_boxes();
+        if self.mode == AppMode::New {
+            self.media_type_popup.set_taken(&self.media_types);
+            self.media_type_popup.focus_first_field();
+        }
     }
-}
 
-fn song_to_row(song: &Song) -> Row {
-    // add a space to the start of the title
-    let mut title = song.title.clone();
-    title.insert(0, ' ');
-    // create a row from the song's fields
-    Row::new(vec![
-        title,
-        song.artist.clone(),
-        song.album.clone(),
-        song.release_year.to_string(),
-        song.media_type.clone(),
-    ])
-}
+    // switch between every song and the trash
+    async fn toggle_trash(&mut self) {
+        match self.view {
+            View::Trash => self.show_all_songs().await,
+            _ => {
+                self.view = View::Trash;
+                self.refresh_trash().await;
+            }
+        }
+    }
+
+    // take the selected song out of the trash, back where it was
+    async fn restore_selected_song(&mut self) {
+        let Some(trashed) = self.trash.get(self.selected_trash_row) else {
+            return;
+        };
+        let title = trashed.song.title.clone();
+        let result = self
+            .store
+            .restore_song(trashed.song.id)
+            .await
+            .context("restore the song");
+        self.report(result, format!("Restored {}", title));
+        self.refresh_trash().await;
+    }
+
+    fn toggle_new_playlist(&mut self) {
+        // same as toggle_new_album, for the playlist popup
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::New
+        } else {
+            AppMode::Normal
+        };
+        self.esc_mode = !self.esc_mode;
+        self.new_playlist_popup.clear_all_boxes();
+        if self.mode == AppMode::New {
+            self.new_playlist_popup.focus_first_field();
+        }
+    }
+
+    fn toggle_edit_playlist(&mut self) {
+        // same as toggle_edit_album, for the playlist popup
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::Edit
+        } else {
+            AppMode::Normal
+        };
+        self.esc_mode = !self.esc_mode;
+
+        self.edit_playlist_popup.clear_all_boxes();
+        if self.mode == AppMode::Edit {
+            match self.get_selected_playlist() {
+                Some(selected_playlist) => {
+                    self.edit_playlist_popup
+                        .populate_textboxes_with_playlist(&selected_playlist);
+                    self.edit_playlist_popup.focus_first_field();
+                }
+                // nothing to edit, so don't open the popup
+                None => {
+                    self.mode = AppMode::Normal;
+                    self.esc_mode = false;
+                }
+            }
+        }
+    }
+
+    // show the songs on the selected playlist, and make it the one "+" adds to
+    async fn open_selected_playlist(&mut self) {
+        if let Some(selected_playlist) = self.get_selected_playlist() {
+            self.playlist = Some(selected_playlist);
+            self.view = View::Playlist;
+            self.selected_entry_row = 0;
+            self.refresh_entries().await;
+        }
+    }
+
+    // "+" puts the selected song at the end of the playlist opened last
+    async fn add_selected_song_to_playlist(&mut self) {
+        let (Some(playlist), Some(song)) = (&self.playlist, self.songs.get(self.selected_row))
+        else {
+            return;
+        };
+        let message = format!("Added {} to {}", song.title, playlist.name);
+        let result = self
+            .store
+            .add_song_to_playlist(playlist.id, song.id)
+            .await
+            .context("add the song to the playlist");
+        self.report(result, message);
+    }
+
+    // swap the selected entry with the one above or below it, and keep it selected
+    async fn move_selected_entry(&mut self, up: bool) {
+        let selected = self.selected_entry_row;
+        let other = if up {
+            selected.checked_sub(1)
+        } else {
+            Some(selected + 1)
+        };
+        let (Some(entry), Some(other_entry)) = (
+            self.entries.get(selected),
+            other.and_then(|other| self.entries.get(other)),
+        ) else {
+            return;
+        };
+        match self
+            .store
+            .swap_playlist_entries(entry.entry_id, other_entry.entry_id)
+            .await
+            .context("move the song on the playlist")
+        {
+            Ok(_) => self.selected_entry_row = other.unwrap_or(selected),
+            Err(error) => self.report_error(error),
+        }
+        self.refresh_entries().await;
+    }
+
+    async fn remove_selected_entry(&mut self) {
+        if let Some(entry) = self.entries.get(self.selected_entry_row) {
+            if let Err(error) = self
+                .store
+                .remove_playlist_entry(entry.entry_id)
+                .await
+                .context("take the song off the playlist")
+            {
+                self.report_error(error);
+            }
+            self.refresh_entries().await;
+        }
+    }
+
+    async fn show_all_songs(&mut self) {
+        self.view = View::Songs;
+        self.album = None;
+        self.refresh_songs().await;
+    }
+
+    fn toggle_rename_artist(&mut self) {
+        // change mode to Edit and fill the box with the artist's current name, or back to Normal
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::Edit
+        } else {
+            AppMode::Normal
+        };
+        self.esc_mode = !self.esc_mode;
+
+        self.rename_box.clear_input();
+        self.rename_box.set_input_mode(InputMode::Normal);
+        if self.mode == AppMode::Edit {
+            match self.get_selected_artist() {
+                Some(selected_artist) => {
+                    self.rename_box.set_input(selected_artist.name);
+                    self.rename_box.set_input_mode(InputMode::Editing);
+                }
+                // nothing to rename, so don't open the popup
+                None => {
+                    self.mode = AppMode::Normal;
+                    self.esc_mode = false;
+                }
+            }
+        }
+    }
+
+    // "X" asks for a file to write the songs on screen to, so a search result can be exported
+    fn toggle_export(&mut self) {
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::Export
+        } else {
+            AppMode::Normal
+        };
+        self.esc_mode = !self.esc_mode;
+
+        self.export_box.clear_input();
+        self.export_box.set_input_mode(InputMode::Normal);
+        if self.mode == AppMode::Export {
+            self.export_box.set_input("songs.csv".to_owned());
+            self.export_box.set_input_mode(InputMode::Editing);
+        }
+    }
+
+    // "H" shows every change made to the selected song and who made it, newest first
+    async fn toggle_history(&mut self) {
+        if self.mode == AppMode::History {
+            self.mode = AppMode::Normal;
+            self.esc_mode = false;
+            return;
+        }
+        let Some(song) = self.songs.get(self.selected_row) else {
+            return;
+        };
+        match self
+            .store
+            .get_song_history(song.id)
+            .await
+            .context("load the song's history")
+        {
+            Ok(entries) => {
+                self.history = entries.iter().flat_map(AuditEntry::field_changes).collect()
+            }
+            Err(error) => {
+                self.report_error(error);
+                return;
+            }
+        }
+        self.history_row = 0;
+        self.mode = AppMode::History;
+        self.esc_mode = true;
+    }
+
+    // the first "M" picks the artist to merge away, the second picks who they merge into
+    fn pick_artist_to_merge(&mut self) {
+        let Some(selected_artist) = self.get_selected_artist() else {
+            return;
+        };
+        match &self.merge_from {
+            None => self.merge_from = Some(selected_artist),
+            // picking the same artist twice cancels the merge
+            Some(from) if from.id == selected_artist.id => self.merge_from = None,
+            Some(_) => {
+                self.mode = AppMode::Merge;
+                self.esc_mode = true;
+            }
+        }
+    }
+
+    // show only the songs on the selected album
+    async fn open_selected_album(&mut self) {
+        if let Some(selected_album) = self.get_selected_album() {
+            self.album = Some(selected_album);
+            self.view = View::Songs;
+            self.selected_row = 0;
+            self.refresh_songs().await;
+        }
+    }
+
+    // space marks the selected song, or unmarks it
+    fn toggle_mark(&mut self) {
+        if let Some(song) = self.songs.get(self.selected_row) {
+            if !self.marked.remove(&song.id) {
+                self.marked.insert(song.id);
+            }
+        }
+    }
+
+    // the first "v" starts a range at the selected row, the second marks the songs from there
+    // to the row selected then
+    async fn toggle_mark_range(&mut self) {
+        match self.mark_anchor {
+            Some(_) => self.mark_range().await,
+            None => self.mark_anchor = Some(self.selected_row),
+        }
+    }
+
+    // marks the songs in the range "v" started, if one was
+    async fn mark_range(&mut self) {
+        let Some(anchor) = self.mark_anchor.take() else {
+            return;
+        };
+        let rows = anchor.min(self.selected_row)..anchor.max(self.selected_row) + 1;
+        match self
+            .songs
+            .ids(self.store.as_ref(), rows)
+            .await
+            .context("mark the songs")
+        {
+            Ok(song_ids) => self.marked.extend(song_ids),
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    // "*" marks every song the search found, or unmarks them if they're all marked already
+    async fn mark_all(&mut self) {
+        self.mark_anchor = None;
+        match self
+            .songs
+            .all(self.store.as_ref())
+            .await
+            .context("mark the songs")
+        {
+            Ok(songs) => {
+                if songs.iter().all(|song| self.marked.contains(&song.id)) {
+                    for song in &songs {
+                        self.marked.remove(&song.id);
+                    }
+                } else {
+                    self.marked.extend(songs.iter().map(|song| song.id));
+                }
+            }
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    fn toggle_delete_song(&mut self) {
+        // Commenting this function would be redundant
+        self.mode = if self.mode == AppMode::Normal {
+            AppMode::Delete
+        } else {
+            AppMode::Normal
+        };
+        self.esc_mode = !self.esc_mode;
+    }
+
+    // syntax errors show up as soon as they're typed, the search itself waits for typing to pause
+    fn schedule_search(&mut self) {
+        self.search_error = match self.fuzzy {
+            true => None,
+            false => Query::parse(self.searchbar.get_input()).err(),
+        };
+        if self.search_error.is_none() {
+            self.pending_search = Some(Instant::now() + SEARCH_DELAY);
+        }
+    }
+
+    // starts searching in the background, replacing any search that hasn't finished.
+    // The songs arrive in show_search_result.
+    fn submit_search_query(&mut self, query: String) {
+        self.last_query = query.clone();
+        let parsed = match self.fuzzy {
+            // the query language doesn't apply, so there's no syntax to get wrong
+            true => None,
+            // a syntax error is shown under the searchbar, and the table keeps the last results
+            false => match Query::parse(&query) {
+                Ok(parsed) => Some(parsed),
+                Err(error) => {
+                    self.search_error = Some(error);
+                    return;
+                }
+            },
+        };
+        self.search_error = None;
+        self.cancel_search();
+
+        let store = Arc::clone(&self.store);
+        let sender = self.search_sender.clone();
+        let generation = self.search_generation;
+        let sort = self.sort.clone();
+        self.search_task = Some(tokio::spawn(async move {
+            let songs = match parsed {
+                Some(parsed) => SongPager::open(store.as_ref(), parsed, sort).await,
+                // rank every song against the query, best match first
+                None => SongPager::fuzzy(store.as_ref(), &query, sort).await,
+            };
+            // the app only stops listening when it exits
+            let _ = sender.send(Loaded::Search { generation, songs });
+        }));
+    }
+
+    // stops the running search, and makes sure its songs are ignored if they were already sent
+    fn cancel_search(&mut self) {
+        self.search_generation += 1;
+        if let Some(task) = self.search_task.take() {
+            task.abort();
+        }
+    }
+
+    fn show_loaded(&mut self, loaded: Loaded) {
+        match loaded {
+            Loaded::Search { generation, songs } => self.show_search_result(generation, songs),
+            Loaded::Pages { generation, songs } => self.show_pages(generation, songs),
+        }
+    }
+
+    fn show_search_result(&mut self, generation: u64, songs: Result<SongPager, sqlx::Error>) {
+        if generation != self.search_generation {
+            return;
+        }
+        self.search_task = None;
+        // a range being marked was rows of the old list; the marked songs themselves stay marked
+        self.mark_anchor = None;
+        // if the search failed the table is left empty, and the status bar says why
+        let songs = match songs.context("search for songs") {
+            Ok(songs) => songs,
+            Err(error) => {
+                self.report_error(error);
+                SongPager::default()
+            }
+        };
+        self.show_songs(songs);
+    }
+
+    // put a new list of songs in the table. Pages still being fetched for the old one are dropped.
+    fn show_songs(&mut self, songs: SongPager) {
+        self.cancel_page_load();
+        self.songs = songs;
+    }
+
+    // the songs table with more of its pages cached. If they couldn't be fetched the table keeps
+    // what it has, and the same rows aren't asked for again until it scrolls.
+    fn show_pages(&mut self, generation: u64, songs: Result<SongPager, sqlx::Error>) {
+        if generation != self.page_generation {
+            return;
+        }
+        self.page_task = None;
+        match songs.context("load the songs") {
+            Ok(songs) => self.songs = songs,
+            Err(error) => self.report_error(error),
+        }
+    }
+
+    // stops fetching pages, and makes sure they are ignored if they were already sent
+    fn cancel_page_load(&mut self) {
+        self.page_generation += 1;
+        self.requested_rows = None;
+        if let Some(task) = self.page_task.take() {
+            task.abort();
+        }
+    }
+
+    // switch between the query language and fuzzy search, and run the search again the new way
+    fn toggle_fuzzy(&mut self) {
+        self.fuzzy = !self.fuzzy;
+        let query = if self.mode == AppMode::Search {
+            self.searchbar.get_input().to_string()
+        } else {
+            self.last_query.clone()
+        };
+        self.album = None;
+        self.pending_search = None;
+        self.submit_search_query(query);
+    }
+
+    // sort the songs table by a column, or turn it around if it's sorted by that column already.
+    // The songs are loaded again in the new order, keeping to the search or album on screen.
+    async fn sort_songs(&mut self, field: Field) {
+        self.sort.toggle(field);
+        self.selected_row = 0;
+        self.reload_songs().await;
+    }
+
+    // load the songs table again, keeping to the search or album on screen
+    async fn reload_songs(&mut self) {
+        match self.album {
+            Some(_) => self.refresh_songs().await,
+            None => self.submit_search_query(self.last_query.clone()),
+        }
+    }
+
+    // "u" undoes the last change to a song, even one made before a restart, and Ctrl-R redoes it.
+    // The table on screen is reloaded to show it.
+    async fn replay_change(&mut self, undo: bool) {
+        let (result, done) = match undo {
+            true => (self.store.undo().await, "Undid"),
+            false => (self.store.redo().await, "Redid"),
+        };
+        match result.context(if undo {
+            "undo the change"
+        } else {
+            "redo the change"
+        }) {
+            Ok(Some(change)) => {
+                self.notify(Severity::Info, format!("{} {}", done, change.describe()))
+            }
+            Ok(None) if undo => self.notify(Severity::Warning, "Nothing to undo"),
+            Ok(None) => self.notify(Severity::Warning, "Nothing to redo"),
+            Err(error) => self.report_error(error),
+        }
+        match self.view {
+            View::Songs => self.reload_songs().await,
+            View::Albums => self.refresh_albums().await,
+            View::Artists => self.refresh_artists().await,
+            View::Playlists => self.refresh_playlists().await,
+            View::Playlist => self.refresh_entries().await,
+            View::Trash => self.refresh_trash().await,
+            View::MediaTypes => self.refresh_media_types().await,
+        }
+    }
+
+    // reload the songs table, keeping to the open album if there is one
+    async fn refresh_songs(&mut self) {
+        match self.album.as_ref().map(|album| album.id) {
+            Some(album_id) => {
+                // a search finishing late would replace the album's songs
+                self.pending_search = None;
+                self.cancel_search();
+                let query = Query::on_album(album_id);
+                self.mark_anchor = None;
+                let songs = match SongPager::open(self.store.as_ref(), query, self.sort.clone())
+                    .await
+                    .context("load the album's songs")
+                {
+                    Ok(songs) => songs,
+                    Err(error) => {
+                        self.report_error(error);
+                        SongPager::default()
+                    }
+                };
+                self.show_songs(songs);
+            }
+            None => self.submit_search_query("".to_owned()),
+        }
+    }
+
+    // scroll the songs table to keep the selected row on screen, and fetch the songs it shows in
+    // the background. A screenful above and below are fetched too, so scrolling a bit finds them
+    // cached. Rows that haven't arrived yet are drawn empty, and keys keep working meanwhile.
+    fn load_visible_songs(&mut self) {
+        let total = self.songs.len();
+        let height = self.table_height.max(1);
+        self.selected_row = self.selected_row.min(total.saturating_sub(1));
+        if self.selected_row < self.table_offset {
+            self.table_offset = self.selected_row;
+        } else if self.selected_row >= self.table_offset + height {
+            self.table_offset = self.selected_row + 1 - height;
+        }
+        self.table_offset = self.table_offset.min(total.saturating_sub(height));
+
+        let rows = (self.table_offset.saturating_sub(height), 3 * height);
+        if self.page_task.is_some()
+            || self.requested_rows == Some(rows)
+            || self.songs.is_cached(rows.0, rows.1)
+        {
+            return;
+        }
+        self.requested_rows = Some(rows);
+        // the pages are fetched into a copy, which replaces the table's list when it's done
+        let mut songs = self.songs.clone();
+        let store = Arc::clone(&self.store);
+        let sender = self.search_sender.clone();
+        let generation = self.page_generation;
+        self.page_task = Some(tokio::spawn(async move {
+            let songs = songs
+                .load(store.as_ref(), rows.0, rows.1)
+                .await
+                .map(|()| songs);
+            // the app only stops listening when it exits
+            let _ = sender.send(Loaded::Pages { generation, songs });
+        }));
+    }
+
+    // reload the albums table, show any errors
+    async fn refresh_albums(&mut self) {
+        self.albums = match self.store.get_all_albums().await.context("load the albums") {
+            Ok(albums) => albums,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        // keep the selection on the table if rows went away
+        self.selected_album_row = self
+            .selected_album_row
+            .min(self.albums.len().saturating_sub(1));
+    }
+
+    // hand the song popups what's in the library already, to suggest while typing, and the song
+    // and album popups the media types to pick from
+    async fn load_vocabulary(&mut self) {
+        let vocabulary = match self.fetch_vocabulary().await {
+            Ok(vocabulary) => vocabulary,
+            Err(error) => {
+                self.report_error(error);
+                Vocabulary::default()
+            }
+        };
+        let vocabulary = Arc::new(vocabulary);
+        let choices: Vec<Choice> = vocabulary
+            .media_types
+            .iter()
+            .map(|media_type| media_type.choice())
+            .collect();
+        self.new_album_popup.set_media_types(choices.clone());
+        self.edit_album_popup.set_media_types(choices);
+        self.new_popup.set_vocabulary(vocabulary.clone());
+        self.edit_popup.set_vocabulary(vocabulary);
+    }
+
+    async fn fetch_vocabulary(&self) -> Result<Vocabulary, AppError> {
+        let artists = self
+            .store
+            .get_all_artists()
+            .await
+            .context("load the artists")?;
+        Ok(Vocabulary {
+            artists: artists.into_iter().map(|artist| artist.name).collect(),
+            albums: self
+                .store
+                .get_all_albums()
+                .await
+                .context("load the albums")?,
+            media_types: self
+                .store
+                .get_media_types()
+                .await
+                .context("load the media types")?,
+        })
+    }
+
+    // reload the artists table, show any errors
+    async fn refresh_artists(&mut self) {
+        self.artists = match self
+            .store
+            .get_all_artists()
+            .await
+            .context("load the artists")
+        {
+            Ok(artists) => artists,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        // keep the selection on the table if rows went away
+        self.selected_artist_row = self
+            .selected_artist_row
+            .min(self.artists.len().saturating_sub(1));
+    }
+
+    // reload the media types table, show any errors
+    async fn refresh_media_types(&mut self) {
+        self.media_types = match self
+            .store
+            .get_media_types()
+            .await
+            .context("load the media types")
+        {
+            Ok(media_types) => media_types,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        self.selected_media_type_row = self
+            .selected_media_type_row
+            .min(self.media_types.len().saturating_sub(1));
+    }
+
+    // reload the playlists table, show any errors
+    async fn refresh_playlists(&mut self) {
+        self.playlists = match self
+            .store
+            .get_all_playlists()
+            .await
+            .context("load the playlists")
+        {
+            Ok(playlists) => playlists,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        // keep the selection on the table if rows went away
+        self.selected_playlist_row = self
+            .selected_playlist_row
+            .min(self.playlists.len().saturating_sub(1));
+    }
+
+    // reload the songs in the trash, show any errors
+    async fn refresh_trash(&mut self) {
+        self.trash = match self
+            .store
+            .get_trashed_songs()
+            .await
+            .context("load the trash")
+        {
+            Ok(trash) => trash,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        self.selected_trash_row = self
+            .selected_trash_row
+            .min(self.trash.len().saturating_sub(1));
+    }
+
+    // reload the songs on the open playlist, show any errors
+    async fn refresh_entries(&mut self) {
+        let Some(playlist) = &self.playlist else {
+            return;
+        };
+        self.entries = match self
+            .store
+            .get_playlist_entries(playlist.id)
+            .await
+            .context("load the playlist")
+        {
+            Ok(entries) => entries,
+            Err(error) => {
+                self.report_error(error);
+                Vec::new()
+            }
+        };
+        self.selected_entry_row = self
+            .selected_entry_row
+            .min(self.entries.len().saturating_sub(1));
+    }
+
+    // number of rows in the table on screen
+    fn row_count(&self) -> usize {
+        match self.view {
+            View::Songs => self.songs.len(),
+            View::Albums => self.albums.len(),
+            View::Artists => self.artists.len(),
+            View::Playlists => self.playlists.len(),
+            View::Playlist => self.entries.len(),
+            View::Trash => self.trash.len(),
+            View::MediaTypes => self.media_types.len(),
+        }
+    }
+
+    // selected row of the table on screen
+    fn selected_row_mut(&mut self) -> &mut usize {
+        match self.view {
+            View::Songs => &mut self.selected_row,
+            View::Albums => &mut self.selected_album_row,
+            View::Artists => &mut self.selected_artist_row,
+            View::Playlists => &mut self.selected_playlist_row,
+            View::Playlist => &mut self.selected_entry_row,
+            View::Trash => &mut self.selected_trash_row,
+            View::MediaTypes => &mut self.selected_media_type_row,
+        }
+    }
+
+    fn get_selected_artist(&self) -> Option<Artist> {
+        self.artists.get(self.selected_artist_row).cloned()
+    }
+
+    fn get_selected_playlist(&self) -> Option<Playlist> {
+        self.playlists.get(self.selected_playlist_row).cloned()
+    }
+
+    fn get_selected_album(&self) -> Option<Album> {
+        self.albums.get(self.selected_album_row).cloned()
+    }
+
+    // returns the song in the selected_row, None if the table is empty
+    fn get_selected_song(&self) -> Option<Song> {
+        self.songs.get(self.selected_row).cloned()
+    }
+
+    // move the song to the trash and say how it went
+    async fn purge_song(&mut self, song: Song) {
+        let result = self
+            .store
+            .delete_song(song.id)
+            .await
+            .context("delete the song");
+        self.report(result, format!("Moved {} to the trash", song.title));
+    }
+
+    // move every marked song to the trash in one go and say how it went
+    async fn purge_marked_songs(&mut self) {
+        let song_ids: Vec<u32> = self.marked.iter().copied().collect();
+        match self
+            .store
+            .delete_songs(&song_ids)
+            .await
+            .context("delete the songs")
+        {
+            Ok(count) => self.notify(
+                Severity::Success,
+                format!("Moved {} to the trash", songs(count as usize)),
+            ),
+            Err(error) => self.report_error(error),
+        }
+        self.marked.clear();
+    }
+
+    // trashes the album's songs, and deletes the album unless songs in the trash are still on it
+    async fn delete_album(&mut self, album: Album) {
+        let result = self
+            .store
+            .delete_album(album.id)
+            .await
+            .context("delete the album");
+        let message = match result {
+            Ok(0) => format!(
+                "Moved the songs on {} to the trash, purge them to delete the album",
+                album.title
+            ),
+            _ => format!("Deleted {}", album.title),
+        };
+        self.report(result, message);
+    }
+
+    // writes every song in the table to a CSV file, returns how many there were
+    async fn export_songs(&self, path: &Path) -> Result<usize, AppError> {
+        // only the songs near the screen are loaded, so fetch the whole list again
+        let songs = self
+            .songs
+            .all(self.store.as_ref())
+            .await
+            .context("load the songs to export")?;
+        File::create(path)
+            .and_then(|mut file| cli::write_songs(&mut file, &songs, OutputFormat::Csv))
+            .map_err(|error| AppError::File {
+                path: path.to_owned(),
+                error,
+            })?;
+        Ok(songs.len())
+    }
+
+    // shows a message in the status bar until it times out
+    fn notify(&mut self, severity: Severity, message: impl Into<String>) {
+        self.status = Some(Status::new(severity, message));
+    }
+
+    fn report_error(&mut self, error: AppError) {
+        self.notify(Severity::Error, error.to_string());
+    }
+
+    // shows how an action went: the message if it worked, the error if it didn't
+    fn report<T>(&mut self, result: Result<T, AppError>, success: impl Into<String>) {
+        match result {
+            Ok(_) => self.notify(Severity::Success, success),
+            Err(error) => self.report_error(error),
+        }
+    }
+}
+
+// waits until a deadline, or forever if there isn't one, for the run loop's timers
+async fn sleep_until(deadline: Option<Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+        None => std::future::pending().await,
+    }
+}
+
+// table of the songs in a range of rows with its header and column widths.
+// Marked songs, and the rows of a range being marked, stand out in blue.
+fn song_table<'a>(
+    songs: &'a SongPager,
+    range: std::ops::Range<usize>,
+    marked: &BTreeSet<u32>,
+    marking: Option<std::ops::RangeInclusive<usize>>,
+) -> Table<'a> {
+    // initialize table rows, with the characters a fuzzy search matched highlighted
+    let rows: Vec<Row> = range
+        .filter_map(|index| {
+            let song = songs.get(index)?;
+            let row = match songs.highlights(index) {
+                Some(highlights) => highlighted_song_to_row(song, highlights),
+                None => song_to_row(song),
+            };
+            let is_marked = marked.contains(&song.id)
+                || marking.as_ref().is_some_and(|rows| rows.contains(&index));
+            Some(match is_marked {
+                true => row.style(Style::new().white().on_blue()),
+                false => row,
+            })
+        })
+        .collect();
+    // Table row headers, with an arrow on the columns the songs are sorted by.
+    // The hollow arrow is the column that breaks ties.
+    let header = Row::new(
+        [" Title", "Artist", "Album", "Year", "Media Type"]
+            .into_iter()
+            .zip(Field::ALL)
+            .map(|(name, field)| {
+                let arrow = match songs.sort().and_then(|sort| sort.direction(field)) {
+                    Some((false, true)) => " ▲",
+                    Some((true, true)) => " ▼",
+                    Some((false, false)) => " △",
+                    Some((true, false)) => " ▽",
+                    None => "",
+                };
+                Cell::from(format!("{}{}", name, arrow).bold())
+            })
+            .collect::<Vec<_>>(),
+    );
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(30),
+            Constraint::Percentage(10),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(header)
+}
+
+// table of albums with its header and column widths
+fn album_table(albums: &[Album]) -> Table<'_> {
+    let rows: Vec<Row> = albums.iter().map(album_to_row).collect();
+    let header = Row::new(vec![
+        Cell::from(" Title".bold()),
+        Cell::from("Artist".bold()),
+        Cell::from("Year".bold()),
+        Cell::from("Media Type".bold()),
+        Cell::from("Songs".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+            Constraint::Percentage(20),
+            Constraint::Percentage(15),
+        ],
+    )
+    .header(header)
+}
+
+// table of artists with its header and column widths
+fn artist_table(artists: &[Artist]) -> Table<'_> {
+    let rows: Vec<Row> = artists.iter().map(artist_to_row).collect();
+    let header = Row::new(vec![
+        Cell::from(" Name".bold()),
+        Cell::from("Songs".bold()),
+        Cell::from("Albums".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(60),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(header)
+}
+
+// table of the media types on the list with its header and column widths
+fn media_type_table(media_types: &[MediaType]) -> Table<'_> {
+    let rows: Vec<Row> = media_types
+        .iter()
+        .map(|media_type| {
+            Row::new(vec![
+                format!(" {}", media_type.name),
+                media_type.details(),
+                media_type.aliases.join(", "),
+                media_type.song_count.to_string(),
+                media_type.album_count.to_string(),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec![
+        Cell::from(" Name".bold()),
+        Cell::from("Format".bold()),
+        Cell::from("Also Known As".bold()),
+        Cell::from("Songs".bold()),
+        Cell::from("Albums".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Percentage(35),
+            Constraint::Percentage(10),
+            Constraint::Percentage(10),
+        ],
+    )
+    .header(header)
+}
+
+// table of playlists with its header and column widths
+fn playlist_table(playlists: &[Playlist]) -> Table<'_> {
+    let rows: Vec<Row> = playlists
+        .iter()
+        .map(|playlist| {
+            Row::new(vec![
+                format!(" {}", playlist.name),
+                playlist.description.clone(),
+                playlist.song_count.to_string(),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec![
+        Cell::from(" Name".bold()),
+        Cell::from("Description".bold()),
+        Cell::from("Songs".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(55),
+            Constraint::Percentage(15),
+        ],
+    )
+    .header(header)
+}
+
+// the songs in the trash with when each was deleted
+fn trash_table(trash: &[TrashedSong]) -> Table<'_> {
+    let rows: Vec<Row> = trash
+        .iter()
+        .map(|trashed| {
+            Row::new(vec![
+                format!(" {}", trashed.song.title),
+                trashed.song.artist.clone(),
+                trashed.song.album.clone(),
+                trashed.deleted_at.clone(),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec![
+        Cell::from(" Title".bold()),
+        Cell::from("Artist".bold()),
+        Cell::from("Album".bold()),
+        Cell::from("Deleted (UTC)".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(30),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(header)
+}
+
+// the songs on a playlist, numbered in playlist order
+fn entry_table(entries: &[PlaylistEntry]) -> Table<'_> {
+    let rows: Vec<Row> = entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| {
+            Row::new(vec![
+                format!(" {}", index + 1),
+                entry.song.title.clone(),
+                entry.song.artist.clone(),
+                entry.song.album.clone(),
+                entry.song.release_year.to_string(),
+                entry.song.media_type.clone(),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec![
+        Cell::from(" #".bold()),
+        Cell::from("Title".bold()),
+        Cell::from("Artist".bold()),
+        Cell::from("Album".bold()),
+        Cell::from("Year".bold()),
+        Cell::from("Media Type".bold()),
+    ]);
+    Table::new(
+        rows,
+        [
+            Constraint::Percentage(5),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(25),
+            Constraint::Percentage(10),
+            Constraint::Percentage(20),
+        ],
+    )
+    .header(header)
+}
+
+fn artist_to_row(artist: &Artist) -> Row<'_> {
+    Row::new(vec![
+        format!(" {}", artist.name),
+        artist.song_count.to_string(),
+        artist.album_count.to_string(),
+    ])
+}
+
+fn album_to_row(album: &Album) -> Row<'_> {
+    Row::new(vec![
+        format!(" {}", album.title),
+        album.artist.clone(),
+        album.release_year.to_string(),
+        album.media_type.clone(),
+        album.song_count.to_string(),
+    ])
+}
+
+// "1 song", "2 songs"
+fn songs(count: usize) -> String {
+    match count {
+        1 => "1 song".to_owned(),
+        count => format!("{} songs", count),
+    }
+}
+
+fn song_to_row(song: &Song) -> Row<'_> {
+    // add a space to the start of the title
+    let mut title = song.title.clone();
+    title.insert(0, ' ');
+    // create a row from the song's fields
+    Row::new(vec![
+        title,
+        song.artist.clone(),
+        song.album.clone(),
+        song.release_year.to_string(),
+        song.media_type.clone(),
+    ])
+}
+
+// like song_to_row, with the matched characters of the title, artist and album highlighted
+fn highlighted_song_to_row<'a>(song: &'a Song, highlights: &fuzzy::Highlights) -> Row<'a> {
+    let title = highlight(&song.title, &highlights[0]);
+    let mut title_spans = vec![Span::raw(" ")];
+    title_spans.extend(title.spans);
+    Row::new(vec![
+        Cell::from(Line::from(title_spans)),
+        Cell::from(highlight(&song.artist, &highlights[1])),
+        Cell::from(highlight(&song.album, &highlights[2])),
+        Cell::from(song.release_year.to_string()),
+        Cell::from(song.media_type.as_str()),
+    ])
+}
+
+// splits text into spans, with the chars in the ranges bold and yellow
+fn highlight<'a>(text: &'a str, ranges: &[std::ops::Range<usize>]) -> Line<'a> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut matched = String::new();
+    for (index, c) in text.chars().enumerate() {
+        if ranges.iter().any(|range| range.contains(&index)) {
+            if !plain.is_empty() {
+                spans.push(Span::raw(std::mem::take(&mut plain)));
+            }
+            matched.push(c);
+        } else {
+            if !matched.is_empty() {
+                spans.push(std::mem::take(&mut matched).yellow().bold());
+            }
+            plain.push(c);
+        }
+    }
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+    if !matched.is_empty() {
+        spans.push(matched.yellow().bold());
+    }
+    Line::from(spans)
+}
 
 // center a rectangular area and return it given x and y percentages
 fn centered_rect(area: Rect, percent_x: u16, percent_y: u16) -> Rect {
@@ -684,6 +2585,10 @@ where
     // title and instructions for popup box
     let title = match menu.get_popup_mode() {
         PopupMode::New => Title::from(" New Song "),
+        PopupMode::Edit if menu.is_bulk() => Title::from(format!(
+            " Edit {} (empty fields are left as they are) ",
+            songs(menu.get_song_count())
+        )),
         PopupMode::Edit => Title::from(" Edit Song "),
     };
     let instructions = Title::from(Line::from(vec![
@@ -722,41 +2627,391 @@ where
 
     frame.render_widget(popup_block, area); // render popup block
 
-    // render text boxes
-    let title_block = Block::default().borders(Borders::ALL);
-    frame.render_widget(
-        menu.title_box.get_widget().block(title_block),
+    // render text boxes, with the rest of the selected suggestion after the one being typed into
+    let areas = [
         vert_layout[0],
+        vert_layout[1],
+        vert_layout[2],
+        horiz_layout[0],
+        horiz_layout[1],
+    ];
+    for (field, box_area) in Field::ALL.into_iter().zip(areas) {
+        match menu.text_box(field) {
+            Some(text_box) => {
+                let completion = menu.get_completion(field);
+                text_box.render_with_completion(
+                    frame,
+                    field_block(&menu, field),
+                    box_area,
+                    completion,
+                );
+            }
+            None => menu
+                .media_type_picker
+                .render(frame, field_block(&menu, field), box_area),
+        }
+    }
+
+    // the dropdown goes over the boxes below the one it's for
+    match menu.editing_field() {
+        Some(Field::MediaType) => menu.media_type_picker.render_list(frame, areas[4]),
+        Some(field) => render_suggestions(frame, &menu, areas[field as usize]),
+        None => {}
+    }
+}
+
+// the suggestions for the box being typed into, in a list under it, and the offer to fill in
+// the rest of the song from an album that's been typed in
+fn render_suggestions(frame: &mut Frame, menu: &Popup, box_area: Rect) {
+    let (suggestions, selected) = menu.get_suggestions();
+    let mut lines: Vec<Line> = suggestions
+        .iter()
+        .enumerate()
+        .map(|(index, suggestion)| {
+            let line = Line::from(vec![
+                format!(" {} ", suggestion.value).into(),
+                suggestion.detail.clone().dark_gray(),
+            ]);
+            if index == selected {
+                line.reversed()
+            } else {
+                line
+            }
+        })
+        .collect();
+    if let Some(album) = menu.get_album_offer() {
+        lines.push(Line::from(vec![
+            format!(
+                " Fill in {}, {}, {} from this album ",
+                album.artist, album.release_year, album.media_type
+            )
+            .into(),
+            "<Ctrl-Y>".yellow().bold(),
+        ]));
+    }
+    if lines.is_empty() {
+        return;
+    }
+    // as tall as the list, but no further than the bottom of the screen
+    let top = box_area.y + box_area.height;
+    let height = (lines.len() as u16 + 2).min(frame.size().bottom().saturating_sub(top));
+    if height < 3 {
+        return;
+    }
+    let instructions = match suggestions.is_empty() {
+        true => Title::from(""),
+        false => Title::from(Line::from(vec![
+            " Next ".into(),
+            "<Up/Down>".yellow().bold(),
+            " Accept ".into(),
+            "<Tab/Right> ".yellow().bold(),
+        ])),
+    };
+    let block = Block::default().borders(Borders::ALL).title(
+        instructions
+            .alignment(Alignment::Right)
+            .position(Position::Bottom),
     );
+    let dropdown = Rect::new(box_area.x, top, box_area.width, height);
+    frame.render_widget(Clear, dropdown);
+    frame.render_widget(Paragraph::new(lines).block(block), dropdown);
+}
 
-    let artist_block = Block::default().borders(Borders::ALL);
-    frame.render_widget(
-        menu.artist_box.get_widget().block(artist_block),
+// a song popup box's border, red with what's wrong underneath if its input isn't valid
+fn field_block(menu: &Popup, field: Field) -> Block<'static> {
+    let block = Block::default().borders(Borders::ALL);
+    match menu.get_error(field) {
+        Some(error) => block.border_style(Style::new().red()).title(
+            Title::from(Line::from(format!(" {} ", error.message)).red())
+                .position(Position::Bottom)
+                .alignment(Alignment::Left),
+        ),
+        None => block,
+    }
+}
+
+// render fn for the album popups, laid out like the song popup
+fn render_album_popup(frame: &mut Frame, menu: &AlbumPopup, area: Rect) {
+    let title = match menu.get_popup_mode() {
+        PopupMode::New => Title::from(" New Album "),
+        PopupMode::Edit => Title::from(" Edit Album "),
+    };
+    let instructions = Title::from(Line::from(vec![
+        " Cancel ".into(),
+        "<ESC>".yellow().bold(),
+        " Next Field ".into(),
+        "<Tab>".yellow().bold(),
+        " Submit ".into(),
+        "<Enter> ".yellow().bold(),
+    ]));
+    let popup_block = Block::default()
+        .borders(Borders::all())
+        .title(title.alignment(Alignment::Center))
+        .title(
+            instructions
+                .alignment(Alignment::Center)
+                .position(Position::Bottom),
+        );
+
+    let vert_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+        ])
+        .split(popup_block.inner(area));
+
+    let horiz_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .split(vert_layout[2]);
+
+    frame.render_widget(popup_block, area);
+
+    menu.title_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[0],
+    );
+    menu.artist_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
         vert_layout[1],
     );
+    menu.release_year_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        horiz_layout[0],
+    );
+    menu.media_type_picker.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        horiz_layout[1],
+    );
 
-    let album_block = Block::default().borders(Borders::ALL);
-    frame.render_widget(
-        menu.album_box.get_widget().block(album_block),
-        vert_layout[2],
+    // checkbox for copying the year and media type onto the album's songs, toggled with space
+    if let PopupMode::Edit = menu.get_popup_mode() {
+        let checkbox = if menu.get_update_songs() {
+            "[x]"
+        } else {
+            "[ ]"
+        };
+        let text = Text::from(format!(
+            " {} Also apply year and media type to this album's songs <Space>",
+            checkbox
+        ));
+        let text = if menu.is_update_songs_focused() {
+            text.yellow().bold()
+        } else {
+            text
+        };
+        frame.render_widget(
+            Paragraph::new(text).block(Block::default().borders(Borders::ALL)),
+            vert_layout[3],
+        );
+    }
+    menu.media_type_picker.render_list(frame, horiz_layout[1]);
+}
+
+// render fn for the playlist popups, a name and a description
+fn render_playlist_popup(frame: &mut Frame, menu: &PlaylistPopup, area: Rect) {
+    let title = match menu.get_popup_mode() {
+        PopupMode::New => Title::from(" New Playlist "),
+        PopupMode::Edit => Title::from(" Edit Playlist "),
+    };
+    let instructions = Title::from(Line::from(vec![
+        " Cancel ".into(),
+        "<ESC>".yellow().bold(),
+        " Next Field ".into(),
+        "<Tab>".yellow().bold(),
+        " Submit ".into(),
+        "<Enter> ".yellow().bold(),
+    ]));
+    let popup_block = Block::default()
+        .borders(Borders::all())
+        .title(title.alignment(Alignment::Center))
+        .title(
+            instructions
+                .alignment(Alignment::Center)
+                .position(Position::Bottom),
+        );
+
+    let vert_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(18), Constraint::Percentage(18)])
+        .split(popup_block.inner(area));
+
+    frame.render_widget(popup_block, area);
+    menu.name_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[0],
     );
+    menu.description_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[1],
+    );
+}
 
-    let year_block = Block::default().borders(Borders::ALL);
-    frame.render_widget(
-        menu.release_year_box.get_widget().block(year_block),
-        horiz_layout[0],
+// render fn for the new media type popup:
+// the name and format, then the format's details side by side
+fn render_media_type_popup(frame: &mut Frame, menu: &MediaTypePopup, area: Rect) {
+    let instructions = Title::from(Line::from(vec![
+        " Cancel ".into(),
+        "<ESC>".yellow().bold(),
+        " Next Field ".into(),
+        "<Tab>".yellow().bold(),
+        " Submit ".into(),
+        "<Enter> ".yellow().bold(),
+    ]));
+    let popup_block = Block::default()
+        .borders(Borders::all())
+        .title(Title::from(" New Media Type ").alignment(Alignment::Center))
+        .title(
+            instructions
+                .alignment(Alignment::Center)
+                .position(Position::Bottom),
+        );
+
+    let vert_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+            Constraint::Percentage(18),
+        ])
+        .split(popup_block.inner(area));
+
+    let details = menu.get_format().map_or(&[][..], |format| format.details());
+    let horiz_layout = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, details.len().max(1) as u32);
+            details.len()
+        ])
+        .split(vert_layout[2]);
+
+    frame.render_widget(popup_block, area);
+    menu.name_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[0],
+    );
+    menu.format_picker.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[1],
     );
+    for (&detail, detail_area) in details.iter().zip(horiz_layout.iter()) {
+        menu.detail_box(detail)
+            .render(frame, Block::default().borders(Borders::ALL), *detail_area);
+    }
+    menu.format_picker.render_list(frame, vert_layout[1]);
+}
 
-    let media_type_block = Block::default().borders(Borders::ALL);
-    frame.render_widget(
-        menu.media_type_box.get_widget().block(media_type_block),
-        horiz_layout[1],
+// render fn for popups with a single text box, like renaming an artist
+fn render_text_popup(frame: &mut Frame, title: &str, text_box: &TextBox, area: Rect) {
+    let instructions = Title::from(Line::from(vec![
+        " Cancel ".into(),
+        "<ESC>".yellow().bold(),
+        " Submit ".into(),
+        "<Enter> ".yellow().bold(),
+    ]));
+    let popup_block = Block::default()
+        .borders(Borders::all())
+        .title(Title::from(title).alignment(Alignment::Center))
+        .title(
+            instructions
+                .alignment(Alignment::Center)
+                .position(Position::Bottom),
+        );
+    let vert_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(18)])
+        .split(popup_block.inner(area));
+
+    frame.render_widget(popup_block, area);
+    text_box.render(
+        frame,
+        Block::default().borders(Borders::ALL),
+        vert_layout[0],
     );
 }
 
-// render function for the delete popup
-fn render_delete_popup(frame: &mut Frame, area: Rect) {
-    // instructions for the delete block
+// render fn for the history of a song, one row per field a change changed
+fn render_history_popup(
+    frame: &mut Frame,
+    title: &str,
+    changes: &[FieldChange],
+    selected_row: usize,
+    area: Rect,
+) {
+    let instructions = Title::from(Line::from(vec![
+        " Scroll ".into(),
+        "<↑↓>".yellow().bold(),
+        " Close ".into(),
+        "<ESC> ".yellow().bold(),
+    ]));
+    let mut popup_block = Block::default()
+        .borders(Borders::all())
+        .title(Title::from(title).alignment(Alignment::Center))
+        .title(
+            instructions
+                .alignment(Alignment::Center)
+                .position(Position::Bottom),
+        );
+    if changes.is_empty() {
+        popup_block = popup_block.title(
+            Title::from(" No changes recorded ".italic())
+                .alignment(Alignment::Left)
+                .position(Position::Bottom),
+        );
+    }
+    let rows: Vec<Row> = changes
+        .iter()
+        .map(|change| {
+            Row::new(vec![
+                Cell::from(format!(" {}", change.changed_at)),
+                Cell::from(change.changed_by.as_str()),
+                Cell::from(change.action),
+                Cell::from(change.field),
+                Cell::from(change.before.as_str().red()),
+                Cell::from(change.after.as_str().green()),
+            ])
+        })
+        .collect();
+    let header = Row::new(vec![
+        Cell::from(" When (UTC)".bold()),
+        Cell::from("By".bold()),
+        Cell::from("Change".bold()),
+        Cell::from("Field".bold()),
+        Cell::from("Before".bold()),
+        Cell::from("After".bold()),
+    ]);
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(21),
+            Constraint::Percentage(15),
+            Constraint::Length(8),
+            Constraint::Length(11),
+            Constraint::Percentage(30),
+            Constraint::Percentage(30),
+        ],
+    )
+    .header(header)
+    .highlight_style(Style::new().reversed())
+    .block(popup_block);
+    let mut table_state = TableState::default().with_selected(Some(selected_row));
+    frame.render_stateful_widget(table, area, &mut table_state);
+}
+
+// render function for yes/no popups like delete and merge
+fn render_confirm_popup(frame: &mut Frame, area: Rect, title: &str, question: String) {
+    // instructions for the confirm block
     let delete_instructions = Title::from(Line::from(vec![
         " Cancel".into(),
         "<ESC>".yellow().bold(),
@@ -766,7 +3021,7 @@ fn render_delete_popup(frame: &mut Frame, area: Rect) {
     // delete block
     let delete_block = Block::default()
         .borders(Borders::all())
-        .title(" Delete Song ")
+        .title(title)
         .title_alignment(Alignment::Center)
         .title(
             delete_instructions
@@ -776,7 +3031,7 @@ fn render_delete_popup(frame: &mut Frame, area: Rect) {
     // render dleete popup
     frame.render_widget(
         Paragraph::new(
-            Text::from(" Are you sure you want to delete this song? ")
+            Text::from(question)
                 .bold()
                 .red()
                 .rapid_blink()
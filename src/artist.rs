@@ -0,0 +1,10 @@
+// This is the program's model of an Artist, as listed in the artists table.
+// The counts come from the songs and albums that point at the artist.
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Artist {
+    pub id: u32,
+    pub name: String,
+    pub song_count: i64,
+    pub album_count: i64,
+}
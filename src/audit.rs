@@ -0,0 +1,170 @@
+// The audit log. Every song added, edited or deleted is written to the AuditLog table in the same
+// transaction as the change, with when it happened and who made it, so a wrong value can be traced
+// back to the change that introduced it. Unlike the journal, nothing is ever taken out of it:
+// undoing a change is a change of its own.
+
+use crate::{import::Field, journal::JournalEntry, song::Song};
+use serde::Serialize;
+use std::{
+    env,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+// a row of the AuditLog table, with the same song columns as a Journal row
+#[derive(Debug, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub changed_at: String, // "YYYY-MM-DD HH:MM:SS" in UTC
+    pub changed_by: String,
+    #[sqlx(flatten)]
+    pub entry: JournalEntry,
+}
+
+// one field of a song before and after a change, the rows of the history the CLI and TUI show
+#[derive(Debug, PartialEq, Serialize)]
+pub struct FieldChange {
+    pub changed_at: String,
+    pub changed_by: String,
+    pub song_id: u32,
+    pub action: &'static str,
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+impl AuditEntry {
+    // every field of an added or deleted song, and the fields an edit changed
+    pub fn field_changes(&self) -> Vec<FieldChange> {
+        let Some(change) = self.entry.change() else {
+            return Vec::new();
+        };
+        let (song_id, old, new) = change.columns();
+        let action = match (old, new) {
+            (None, _) => "added",
+            (Some(_), Some(_)) => "edited",
+            (Some(_), None) => "deleted",
+        };
+        Field::ALL
+            .into_iter()
+            .map(|field| FieldChange {
+                changed_at: self.changed_at.clone(),
+                changed_by: self.changed_by.clone(),
+                song_id,
+                action,
+                field: field.name(),
+                before: old.map(|song| value(song, field)).unwrap_or_default(),
+                after: new.map(|song| value(song, field)).unwrap_or_default(),
+            })
+            .filter(|change| change.before != change.after)
+            .collect()
+    }
+}
+
+fn value(song: &Song, field: Field) -> String {
+    match field {
+        Field::Title => song.title.clone(),
+        Field::Artist => song.artist.clone(),
+        Field::Album => song.album.clone(),
+        Field::ReleaseYear => song.release_year.to_string(),
+        Field::MediaType => song.media_type.clone(),
+    }
+}
+
+// the name changes are recorded under when none is configured: the user logged in to the OS
+pub fn os_user() -> String {
+    ["USER", "USERNAME"]
+        .into_iter()
+        .find_map(|var| env::var(var).ok().filter(|name| !name.trim().is_empty()))
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+// the time now as "YYYY-MM-DD HH:MM:SS" in UTC. Kept as text so it sorts and compares the same on
+// both backends, and a date range is just a range of strings.
+pub fn timestamp_now() -> String {
+    timestamp_days_ago(0)
+}
+
+// the time this many days ago, in the same format, for cutoffs like the trash's retention period
+pub fn timestamp_days_ago(days: u32) -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    timestamp(seconds.saturating_sub(u64::from(days) * 86400))
+}
+
+// the year it is now in UTC, the latest a song can have been released in give or take announcements
+pub fn current_year() -> i32 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    civil_date((seconds / 86400) as i64).0 as i32
+}
+
+fn timestamp(seconds: u64) -> String {
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    let (year, month, day) = civil_date(days as i64);
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02}",
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+// the year, month and day a number of days after 1970-01-01 falls on, see
+// http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+fn civil_date(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// checks a YYYY-MM-DD date from the command line, None if it isn't one
+pub fn parse_date(value: &str) -> Option<String> {
+    let mut parts = value.split('-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let (year, month, day): (u32, u32, u32) =
+        (year.parse().ok()?, month.parse().ok()?, day.parse().ok()?);
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days_in_month = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    (1..=days_in_month).contains(&day).then(|| value.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timestamps_and_dates() {
+        assert_eq!(timestamp(0), "1970-01-01 00:00:00");
+        // the leap day of a year divisible by 400, a second before midnight
+        assert_eq!(timestamp(951868799), "2000-02-29 23:59:59");
+        assert_eq!(timestamp(1718454896), "2024-06-15 12:34:56");
+
+        assert_eq!(parse_date("2024-02-29").as_deref(), Some("2024-02-29"));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("2024-6-15"), None);
+        assert_eq!(parse_date("yesterday"), None);
+    }
+}
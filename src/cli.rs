@@ -0,0 +1,892 @@
+// Scriptable subcommands. They do the same things as the TUI without opening it,
+// and print the songs they touched to stdout so the library can be used from pipelines and cron.
+
+use crate::{
+    audit::{self, FieldChange},
+    database::LibraryStore,
+    error::{ArgumentError, CommandError, ImportError},
+    import::{self, Field},
+    media_type::MediaType,
+    query::Query,
+    scan::{self, Scan, TrackStatus},
+    song::{Song, SongFields},
+    validate,
+};
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+};
+
+// the names parse_args treats as the start of a subcommand
+pub const COMMANDS: [&str; 10] = [
+    "list",
+    "search",
+    "add",
+    "edit",
+    "delete",
+    "import",
+    "export",
+    "scan",
+    "history",
+    "media-types",
+];
+
+// column headers, in the same order as the fields Song serializes
+const COLUMNS: [&str; 6] = [
+    "id",
+    "title",
+    "artist",
+    "album",
+    "release_year",
+    "media_type",
+];
+
+// column headers of the media types, the aliases separated by semicolons
+const MEDIA_TYPE_COLUMNS: [&str; 9] = [
+    "name",
+    "format",
+    "speed",
+    "size",
+    "bitrate",
+    "codec",
+    "aliases",
+    "song_count",
+    "album_count",
+];
+
+// column headers of the history, in the same order as the fields FieldChange serializes
+const HISTORY_COLUMNS: [&str; 7] = [
+    "changed_at",
+    "changed_by",
+    "song_id",
+    "action",
+    "field",
+    "before",
+    "after",
+];
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+impl OutputFormat {
+    pub fn parse(value: &str) -> Result<Self, ArgumentError> {
+        match value {
+            "table" => Ok(Self::Table),
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            _ => Err(ArgumentError::InvalidValue {
+                arg: "--format".to_owned(),
+                value: value.to_owned(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    List,
+    Search(String),
+    Add(Song),
+    Edit(u32, SongFields),
+    Delete(u32),
+    Import {
+        path: PathBuf,
+        mapping: Vec<(String, Field)>,
+        dry_run: bool,
+    },
+    // every song, or the ones matching the query, as CSV
+    Export {
+        query: Option<String>,
+        output: Option<PathBuf>,
+    },
+    // read the tags of the audio files in a folder
+    Scan {
+        folder: PathBuf,
+        dry_run: bool,
+        assume_yes: bool,
+    },
+    // the changes made to every song, or one, between two days (inclusive)
+    History {
+        song_id: Option<u32>,
+        from: Option<String>,
+        to: Option<String>,
+    },
+    // the media types on the list
+    MediaTypes,
+    // file every song and album with a media type under a type on the list
+    MapMediaType {
+        from: String,
+        into: String,
+    },
+}
+
+impl Command {
+    // parses the arguments after the subcommand's name. --format may be given here as well.
+    pub fn parse(
+        name: &str,
+        args: impl Iterator<Item = String>,
+        format: &mut OutputFormat,
+    ) -> Result<Self, ArgumentError> {
+        let mut args = args;
+        let mut fields = SongFields::default();
+        let mut mapping = Vec::new();
+        let mut dry_run = false;
+        let mut assume_yes = false;
+        let mut output = None;
+        let (mut from, mut to) = (None, None);
+        let mut positional = Vec::new();
+        while let Some(arg) = args.next() {
+            let mut value = |arg: &str| -> Result<String, ArgumentError> {
+                let value = args.next().ok_or(ArgumentError::InvalidNumberOfArguments)?;
+                if value.trim().is_empty() {
+                    return Err(ArgumentError::InvalidValue {
+                        arg: arg.to_owned(),
+                        value,
+                    });
+                }
+                Ok(value.trim().to_owned())
+            };
+            match arg.as_str() {
+                "-f" | "--format" => *format = OutputFormat::parse(&value(&arg)?)?,
+                "--title" => fields.title = Some(text(Field::Title, value(&arg)?)?),
+                "--artist" => fields.artist = Some(text(Field::Artist, value(&arg)?)?),
+                "--album" => fields.album = Some(text(Field::Album, value(&arg)?)?),
+                "--year" => {
+                    let year = value(&arg)?;
+                    let parsed = year.parse().map_err(|_| ArgumentError::InvalidValue {
+                        arg: arg.clone(),
+                        value: year,
+                    })?;
+                    let year =
+                        validate::release_year(parsed).map_err(ArgumentError::InvalidField)?;
+                    fields.release_year = Some(year);
+                }
+                "--media-type" => fields.media_type = Some(text(Field::MediaType, value(&arg)?)?),
+                "--map" => {
+                    // HEADER=FIELD, splitting at the last = so headers can contain one
+                    let map = value(&arg)?;
+                    let field = map
+                        .rsplit_once('=')
+                        .and_then(|(header, field)| Some((header, Field::from_name(field)?)));
+                    match field {
+                        Some((header, field)) => mapping.push((header.trim().to_owned(), field)),
+                        None => {
+                            return Err(ArgumentError::InvalidValue { arg, value: map });
+                        }
+                    }
+                }
+                "--dry-run" => dry_run = true,
+                "-y" | "--yes" => assume_yes = true,
+                "-o" | "--output" => output = Some(PathBuf::from(value(&arg)?)),
+                "--from" | "--to" => {
+                    let date = value(&arg)?;
+                    let Some(date) = audit::parse_date(&date) else {
+                        return Err(ArgumentError::InvalidValue { arg, value: date });
+                    };
+                    match arg.as_str() {
+                        "--from" => from = Some(date),
+                        _ => to = Some(date),
+                    }
+                }
+                // everything after -- is positional, for search queries that start with a dash
+                "--" => positional.extend(args.by_ref()),
+                _ if arg.starts_with("--") => return Err(ArgumentError::InvalidArgument),
+                _ => positional.push(arg),
+            }
+        }
+
+        // only add and edit take song fields, and the import and export options are their own
+        if !matches!(name, "add" | "edit") && !fields.is_empty()
+            || !matches!(name, "import" | "scan") && dry_run
+            || name != "import" && !mapping.is_empty()
+            || name != "scan" && assume_yes
+            || name != "export" && output.is_some()
+            || name != "history" && (from.is_some() || to.is_some())
+        {
+            return Err(ArgumentError::InvalidArgument);
+        }
+        match (name, positional.len()) {
+            ("list", 0) => Ok(Self::List),
+            ("search", 1..) => Ok(Self::Search(positional.join(" "))),
+            ("add", 0) => Ok(Self::Add(new_song(fields)?)),
+            ("edit", 1) if fields.is_empty() => Err(ArgumentError::MissingField(
+                "at least one of --title, --artist, --album, --year or --media-type",
+            )),
+            ("edit", 1) => Ok(Self::Edit(parse_id(&positional[0])?, fields)),
+            ("delete", 1) => Ok(Self::Delete(parse_id(&positional[0])?)),
+            ("import", 1) => Ok(Self::Import {
+                path: PathBuf::from(&positional[0]),
+                mapping,
+                dry_run,
+            }),
+            ("scan", 1) => Ok(Self::Scan {
+                folder: PathBuf::from(&positional[0]),
+                dry_run,
+                assume_yes,
+            }),
+            ("export", 0) => Ok(Self::Export {
+                query: None,
+                output,
+            }),
+            ("export", 1..) => Ok(Self::Export {
+                query: Some(positional.join(" ")),
+                output,
+            }),
+            ("history", 0) => Ok(Self::History {
+                song_id: None,
+                from,
+                to,
+            }),
+            ("history", 1) => Ok(Self::History {
+                song_id: Some(parse_id(&positional[0])?),
+                from,
+                to,
+            }),
+            ("media-types", 0) => Ok(Self::MediaTypes),
+            ("media-types", 3) if positional[0] == "map" => Ok(Self::MapMediaType {
+                from: text(Field::MediaType, positional[1].clone())?,
+                into: text(Field::MediaType, positional[2].clone())?,
+            }),
+            ("media-types", _) => Err(ArgumentError::InvalidArgument),
+            _ => Err(ArgumentError::InvalidNumberOfArguments),
+        }
+    }
+}
+
+fn parse_id(value: &str) -> Result<u32, ArgumentError> {
+    value.parse().map_err(|_| ArgumentError::InvalidValue {
+        arg: "<ID>".to_owned(),
+        value: value.to_owned(),
+    })
+}
+
+// checks the value of a song field flag against what the library can hold
+fn text(field: Field, value: String) -> Result<String, ArgumentError> {
+    validate::text(field, &value).map_err(ArgumentError::InvalidField)
+}
+
+// a new song from the fields given to add, which all have to be there
+fn new_song(fields: SongFields) -> Result<Song, ArgumentError> {
+    Ok(Song::new(
+        0,
+        &fields.title.ok_or(ArgumentError::MissingField("--title"))?,
+        &fields
+            .artist
+            .ok_or(ArgumentError::MissingField("--artist"))?,
+        &fields.album.ok_or(ArgumentError::MissingField("--album"))?,
+        fields
+            .release_year
+            .ok_or(ArgumentError::MissingField("--year"))?,
+        &fields
+            .media_type
+            .ok_or(ArgumentError::MissingField("--media-type"))?,
+    ))
+}
+
+// runs the command and prints the songs it listed, added, edited or deleted
+pub async fn run(
+    store: &dyn LibraryStore,
+    command: Command,
+    format: OutputFormat,
+) -> Result<(), CommandError> {
+    let songs = match command {
+        Command::List => store.get_all_songs().await?,
+        Command::Search(query) => store.search_songs(&Query::parse(&query)?).await?,
+        Command::Add(song) => {
+            check_media_type(store, &song.media_type).await?;
+            let id = store.create_song(song).await?;
+            vec![find_song(store, id).await?]
+        }
+        Command::Edit(id, fields) => {
+            if let Some(media_type) = &fields.media_type {
+                check_media_type(store, media_type).await?;
+            }
+            let song = fields.apply_to(find_song(store, id).await?);
+            store.update_song(id, song).await?;
+            vec![find_song(store, id).await?]
+        }
+        Command::Delete(id) => {
+            let song = find_song(store, id).await?;
+            store.delete_song(id).await?;
+            vec![song]
+        }
+        Command::Import {
+            path,
+            mapping,
+            dry_run,
+        } => return import_songs(store, path, &mapping, dry_run, format).await,
+        Command::Scan {
+            folder,
+            dry_run,
+            assume_yes,
+        } => return scan_folder(store, folder, dry_run, assume_yes).await,
+        Command::Export { query, output } => {
+            let songs = match query {
+                Some(query) => store.search_songs(&Query::parse(&query)?).await?,
+                None => store.get_all_songs().await?,
+            };
+            match output {
+                Some(path) => write_songs(&mut File::create(path)?, &songs, OutputFormat::Csv)?,
+                None => write_songs(&mut io::stdout().lock(), &songs, OutputFormat::Csv)?,
+            }
+            eprintln!("Exported {} songs", songs.len());
+            return Ok(());
+        }
+        Command::History { song_id, from, to } => {
+            return show_history(store, song_id, from, to, format).await
+        }
+        Command::MediaTypes => {
+            let media_types = store.get_media_types().await?;
+            write_media_types(&mut io::stdout().lock(), &media_types, format)?;
+            return Ok(());
+        }
+        Command::MapMediaType { from, into } => return map_media_type(store, &from, &into).await,
+    };
+    write_songs(&mut io::stdout().lock(), &songs, format)?;
+    Ok(())
+}
+
+// checks every row, then adds them all or none of them.
+// A dry run prints the songs that would be added instead of adding them.
+async fn import_songs(
+    store: &dyn LibraryStore,
+    path: PathBuf,
+    mapping: &[(String, Field)],
+    dry_run: bool,
+    format: OutputFormat,
+) -> Result<(), CommandError> {
+    let file = match File::open(&path) {
+        Ok(file) => file,
+        Err(error) => return Err(ImportError::Unreadable { path, error }.into()),
+    };
+    let media_types = store.get_media_types().await?;
+    let import = import::read_songs(file, mapping, &media_types)?;
+    if dry_run {
+        write_songs(&mut io::stdout().lock(), &import.songs, format)?;
+    }
+    for error in &import.errors {
+        eprintln!("{}: line {}: {}", path.display(), error.line, error.message);
+    }
+    if !import.errors.is_empty() {
+        return Err(ImportError::InvalidRows(import.errors.len()).into());
+    }
+
+    if dry_run {
+        eprintln!("Dry run: {} songs would be imported", import.songs.len());
+    } else {
+        let added = store.add_songs(import.songs).await?;
+        eprintln!("Imported {} songs", added);
+    }
+    Ok(())
+}
+
+// shows what a scan found, then imports the new and changed tracks once the user agrees.
+// Without a terminal to ask on, --yes is needed to import anything.
+async fn scan_folder(
+    store: &dyn LibraryStore,
+    folder: PathBuf,
+    dry_run: bool,
+    assume_yes: bool,
+) -> Result<(), CommandError> {
+    let scan = scan::scan_folder(store, &folder).await?;
+    write_review(&mut io::stdout().lock(), &scan)?;
+    for (path, error) in &scan.failures {
+        eprintln!("{}: {}", path.display(), error);
+    }
+    let (new, changed) = (
+        scan.count(TrackStatus::New),
+        scan.count(TrackStatus::Changed),
+    );
+    eprintln!(
+        "{} new, {} changed, {} unchanged, {} unreadable",
+        new,
+        changed,
+        scan.count(TrackStatus::Unchanged),
+        scan.failures.len()
+    );
+    if dry_run {
+        return Ok(());
+    }
+
+    if new + changed > 0 && !assume_yes {
+        if !io::stdin().is_terminal() {
+            eprintln!("Nothing was imported, pass --yes to import without asking");
+            return Ok(());
+        }
+        eprint!(
+            "Import {} new and update {} changed songs? [y/N] ",
+            new, changed
+        );
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer)?;
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            eprintln!("Nothing was imported");
+            return Ok(());
+        }
+    }
+    // unchanged files that were read still have their new size and modification time saved
+    scan.save(store).await?;
+    eprintln!("Imported {} new and updated {} changed songs", new, changed);
+    Ok(())
+}
+
+// prints each field every change in the range changed, oldest first
+async fn show_history(
+    store: &dyn LibraryStore,
+    song_id: Option<u32>,
+    from: Option<String>,
+    to: Option<String>,
+    format: OutputFormat,
+) -> Result<(), CommandError> {
+    // timestamps are text, so the whole of the last day is everything up to its last second
+    let from = format!("{} 00:00:00", from.as_deref().unwrap_or("0000-01-01"));
+    let to = format!("{} 23:59:59", to.as_deref().unwrap_or("9999-12-31"));
+    let entries = match song_id {
+        Some(song_id) => {
+            let mut entries = store.get_song_history(song_id).await?;
+            entries
+                .retain(|entry| (from.as_str()..=to.as_str()).contains(&entry.changed_at.as_str()));
+            entries.reverse();
+            entries
+        }
+        None => store.get_changes(&from, &to).await?,
+    };
+    let changes: Vec<FieldChange> = entries
+        .iter()
+        .flat_map(|entry| entry.field_changes())
+        .collect();
+    write_history(&mut io::stdout().lock(), &changes, format)?;
+    Ok(())
+}
+
+// files the songs and albums with a media type under a type on the list,
+// the type found by name ignoring case
+async fn map_media_type(
+    store: &dyn LibraryStore,
+    from: &str,
+    into: &str,
+) -> Result<(), CommandError> {
+    let media_types = store.get_media_types().await?;
+    let Some(into) = media_types
+        .iter()
+        .find(|media_type| media_type.name.to_lowercase() == into.to_lowercase())
+    else {
+        return Err(CommandError::UnknownMediaType(into.to_owned()));
+    };
+    let changed = store.map_media_type(from, into.id).await?;
+    eprintln!("Filed {} songs under {}", changed, into.name);
+    Ok(())
+}
+
+// fails with the name given if it's neither a media type on the list nor an alias of one
+async fn check_media_type(store: &dyn LibraryStore, name: &str) -> Result<(), CommandError> {
+    let media_types = store.get_media_types().await?;
+    validate::media_type(&media_types, name)
+        .map_err(|_| CommandError::UnknownMediaType(name.to_owned()))
+}
+
+async fn find_song(store: &dyn LibraryStore, id: u32) -> Result<Song, CommandError> {
+    store.get_song(id).await?.ok_or(CommandError::NotFound(id))
+}
+
+pub fn write_songs(out: &mut impl Write, songs: &[Song], format: OutputFormat) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => write_table(out, songs),
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, songs)?;
+            writeln!(out)
+        }
+        OutputFormat::Csv => {
+            // the header is written by hand so an empty result still has one
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(out);
+            writer.write_record(COLUMNS)?;
+            for song in songs {
+                writer.serialize(song)?;
+            }
+            writer.flush()
+        }
+    }
+}
+
+fn write_table(out: &mut impl Write, songs: &[Song]) -> io::Result<()> {
+    let rows = songs
+        .iter()
+        .map(|song| {
+            [
+                song.id.to_string(),
+                song.title.clone(),
+                song.artist.clone(),
+                song.album.clone(),
+                song.release_year.to_string(),
+                song.media_type.clone(),
+            ]
+        })
+        .collect();
+    write_columns(
+        out,
+        ["ID", "Title", "Artist", "Album", "Year", "Media Type"],
+        rows,
+    )
+}
+
+fn write_history(
+    out: &mut impl Write,
+    changes: &[FieldChange],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => {
+            let rows = changes
+                .iter()
+                .map(|change| {
+                    [
+                        change.changed_at.clone(),
+                        change.changed_by.clone(),
+                        change.song_id.to_string(),
+                        change.action.to_owned(),
+                        change.field.to_owned(),
+                        change.before.clone(),
+                        change.after.clone(),
+                    ]
+                })
+                .collect();
+            write_columns(
+                out,
+                [
+                    "When (UTC)",
+                    "By",
+                    "Song",
+                    "Change",
+                    "Field",
+                    "Before",
+                    "After",
+                ],
+                rows,
+            )
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, changes)?;
+            writeln!(out)
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(out);
+            writer.write_record(HISTORY_COLUMNS)?;
+            for change in changes {
+                writer.serialize(change)?;
+            }
+            writer.flush()
+        }
+    }
+}
+
+fn write_media_types(
+    out: &mut impl Write,
+    media_types: &[MediaType],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => {
+            let rows = media_types
+                .iter()
+                .map(|media_type| {
+                    [
+                        media_type.name.clone(),
+                        media_type.details(),
+                        media_type.aliases.join(", "),
+                        media_type.song_count.to_string(),
+                        media_type.album_count.to_string(),
+                    ]
+                })
+                .collect();
+            write_columns(
+                out,
+                ["Name", "Format", "Also Known As", "Songs", "Albums"],
+                rows,
+            )
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, media_types)?;
+            writeln!(out)
+        }
+        // CSV has no lists, so the row is written by hand
+        OutputFormat::Csv => {
+            let mut writer = csv::WriterBuilder::new()
+                .has_headers(false)
+                .from_writer(out);
+            writer.write_record(MEDIA_TYPE_COLUMNS)?;
+            for media_type in media_types {
+                let detail = |detail: &Option<String>| detail.clone().unwrap_or_default();
+                writer.write_record([
+                    media_type.name.clone(),
+                    media_type.format.clone(),
+                    detail(&media_type.speed),
+                    detail(&media_type.size),
+                    detail(&media_type.bitrate),
+                    detail(&media_type.codec),
+                    media_type.aliases.join(";"),
+                    media_type.song_count.to_string(),
+                    media_type.album_count.to_string(),
+                ])?;
+            }
+            writer.flush()
+        }
+    }
+}
+
+// the tracks a scan found, with what will happen to each of them
+fn write_review(out: &mut impl Write, scan: &Scan) -> io::Result<()> {
+    let rows = scan
+        .tracks
+        .iter()
+        .map(|track| {
+            [
+                track.status.to_string(),
+                track.song.title.clone(),
+                track.song.artist.clone(),
+                track.song.album.clone(),
+                track.song.release_year.to_string(),
+                track.song.media_type.clone(),
+                track.file.path.clone(),
+            ]
+        })
+        .collect();
+    write_columns(
+        out,
+        [
+            "Status",
+            "Title",
+            "Artist",
+            "Album",
+            "Year",
+            "Media Type",
+            "File",
+        ],
+        rows,
+    )
+}
+
+// columns padded to their widest value, like the TUI's table
+fn write_columns<const N: usize>(
+    out: &mut impl Write,
+    header: [&str; N],
+    rows: Vec<[String; N]>,
+) -> io::Result<()> {
+    let header = header.map(str::to_owned);
+    let mut widths = [0; N];
+    for row in std::iter::once(&header).chain(&rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for row in std::iter::once(&header).chain(&rows) {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        writeln!(out, "{}", line.join("  ").trim_end())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteStore;
+
+    fn parse_command(name: &str, args: &[&str]) -> Result<(Command, OutputFormat), ArgumentError> {
+        let mut format = OutputFormat::default();
+        let args = args.iter().map(|arg| arg.to_string());
+        let command = Command::parse(name, args, &mut format)?;
+        Ok((command, format))
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let (command, format) = parse_command("list", &["--format", "json"]).unwrap();
+        assert_eq!((command, format), (Command::List, OutputFormat::Json));
+        let (command, _) = parse_command("search", &["war", "pigs"]).unwrap();
+        assert_eq!(command, Command::Search("war pigs".to_owned()));
+        let (command, _) = parse_command("search", &["--", "-title"]).unwrap();
+        assert_eq!(command, Command::Search("-title".to_owned()));
+        let (command, _) = parse_command("delete", &["12", "-f", "csv"]).unwrap();
+        assert_eq!(command, Command::Delete(12));
+
+        let (command, _) = parse_command("edit", &["3", "--year", "1971"]).unwrap();
+        let expected = SongFields {
+            release_year: Some(1971),
+            ..Default::default()
+        };
+        assert_eq!(command, Command::Edit(3, expected));
+
+        let (command, _) = parse_command("history", &["7", "--from", "2024-06-01"]).unwrap();
+        let expected = Command::History {
+            song_id: Some(7),
+            from: Some("2024-06-01".to_owned()),
+            to: None,
+        };
+        assert_eq!(command, expected);
+
+        let (command, _) = parse_command("media-types", &["map", " 12in ", "Vinyl"]).unwrap();
+        let expected = Command::MapMediaType {
+            from: "12in".to_owned(),
+            into: "Vinyl".to_owned(),
+        };
+        assert_eq!(command, expected);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let add = ["--title", "Solo", "--artist", "Future", "--album", "HNDRXX"];
+        assert!(matches!(
+            parse_command("add", &add),
+            Err(ArgumentError::MissingField("--year"))
+        ));
+        assert!(matches!(
+            parse_command("edit", &["3"]),
+            Err(ArgumentError::MissingField(_))
+        ));
+        assert!(matches!(
+            parse_command("edit", &["three", "--year", "1971"]),
+            Err(ArgumentError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_command("add", &["--year", "last year"]),
+            Err(ArgumentError::InvalidValue { .. })
+        ));
+        assert!(matches!(
+            parse_command("edit", &["3", "--year", "19700"]),
+            Err(ArgumentError::InvalidField(_))
+        ));
+        let long_title = "x".repeat(61);
+        assert!(matches!(
+            parse_command("edit", &["3", "--title", &long_title]),
+            Err(ArgumentError::InvalidField(_))
+        ));
+        assert!(matches!(
+            parse_command("list", &["--format", "xml"]),
+            Err(ArgumentError::InvalidValue { .. })
+        ));
+        assert!(parse_command("list", &["--title", "Solo"]).is_err());
+        assert!(parse_command("search", &[]).is_err());
+        assert!(parse_command("delete", &["1", "2"]).is_err());
+        assert!(parse_command("list", &["--verbose"]).is_err());
+        assert!(matches!(
+            parse_command("history", &["--to", "2024-06-31"]),
+            Err(ArgumentError::InvalidValue { .. })
+        ));
+        assert!(parse_command("list", &["--from", "2024-06-01"]).is_err());
+        assert!(parse_command("media-types", &["map", "LP"]).is_err());
+        assert!(parse_command("media-types", &["merge", "LP", "Vinyl"]).is_err());
+    }
+
+    #[test]
+    fn test_output_formats() {
+        let songs = vec![
+            Song::new(1, "War Pigs", "Black Sabbath", "Paranoid", 1970, "Vinyl"),
+            Song::new(
+                12,
+                "Solo",
+                "Future",
+                "HNDRXX, Deluxe",
+                2017,
+                "Digital Download",
+            ),
+        ];
+        let output = |format| {
+            let mut out = Vec::new();
+            write_songs(&mut out, &songs, format).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        assert_eq!(
+            output(OutputFormat::Table),
+            "ID  Title     Artist         Album           Year  Media Type\n\
+             1   War Pigs  Black Sabbath  Paranoid        1970  Vinyl\n\
+             12  Solo      Future         HNDRXX, Deluxe  2017  Digital Download\n"
+        );
+        assert_eq!(
+            output(OutputFormat::Csv),
+            "id,title,artist,album,release_year,media_type\n\
+             1,War Pigs,Black Sabbath,Paranoid,1970,Vinyl\n\
+             12,Solo,Future,\"HNDRXX, Deluxe\",2017,Digital Download\n"
+        );
+        let json: serde_json::Value = serde_json::from_str(&output(OutputFormat::Json)).unwrap();
+        assert_eq!(json[1]["album"], "HNDRXX, Deluxe");
+        assert_eq!(json[0]["release_year"], 1970);
+        assert!(json[0].get("artist_id").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_run_commands() {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let song = Song::new(0, "Solo", "Future", "HNDRXX", 2017, "Digital Download");
+        run(&store, Command::Add(song), OutputFormat::Csv)
+            .await
+            .unwrap();
+        let id = store.get_all_songs().await.unwrap()[0].id;
+
+        let fields = SongFields {
+            title: Some("Solo (Reprise)".to_owned()),
+            ..Default::default()
+        };
+        run(&store, Command::Edit(id, fields), OutputFormat::Csv)
+            .await
+            .unwrap();
+        let song = store.get_song(id).await.unwrap().unwrap();
+        assert_eq!(song.title, "Solo (Reprise)");
+        assert_eq!(song.album, "HNDRXX");
+
+        let fields = SongFields {
+            media_type: Some("Wax Cylinder".to_owned()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            run(&store, Command::Edit(id, fields), OutputFormat::Csv).await,
+            Err(CommandError::UnknownMediaType(_))
+        ));
+
+        run(&store, Command::Delete(id), OutputFormat::Csv)
+            .await
+            .unwrap();
+        assert!(matches!(
+            run(&store, Command::Delete(id), OutputFormat::Csv).await,
+            Err(CommandError::NotFound(_))
+        ));
+        assert!(matches!(
+            run(
+                &store,
+                Command::Search("colour:blue".to_owned()),
+                OutputFormat::Csv
+            )
+            .await,
+            Err(CommandError::Query(_))
+        ));
+
+        let map = |from: &str, into: &str| Command::MapMediaType {
+            from: from.to_owned(),
+            into: into.to_owned(),
+        };
+        assert!(matches!(
+            run(&store, map("Wax Cylinder", "Shellac"), OutputFormat::Csv).await,
+            Err(CommandError::UnknownMediaType(_))
+        ));
+        run(&store, map("Wax Cylinder", "vinyl"), OutputFormat::Csv)
+            .await
+            .unwrap();
+        let media_types = store.get_media_types().await.unwrap();
+        let vinyl = media_types
+            .iter()
+            .find(|media_type| media_type.name == "Vinyl");
+        assert!(vinyl.unwrap().aliases.contains(&"Wax Cylinder".to_owned()));
+    }
+}
@@ -0,0 +1,176 @@
+// Suggestions for the song popups' artist and album boxes, drawn from the values already in the
+// library, so a new song gets spelled the way the library already spells it instead of adding
+// "Chief keef" next to "Chief Keef". Media types are picked from their list instead of typed.
+
+use crate::{album::Album, fuzzy, import::Field, media_type::MediaType};
+
+// how many suggestions the dropdown under a box shows
+pub const MAX_SUGGESTIONS: usize = 5;
+
+// the values already in the library, loaded when a song popup opens
+#[derive(Debug, Default)]
+pub struct Vocabulary {
+    pub artists: Vec<String>,
+    pub albums: Vec<Album>,
+    pub media_types: Vec<MediaType>, // for the media type pickers
+}
+
+// a value to complete a box with, and what to show next to it, like the artist of an album
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub value: String,
+    pub detail: String,
+}
+
+impl Vocabulary {
+    // the values that could complete what's been typed into a field's box, best first
+    pub fn suggest(&self, field: Field, input: &str) -> Vec<Suggestion> {
+        match field {
+            Field::Artist => suggest(
+                input,
+                self.artists.iter().map(|artist| (artist, String::new())),
+            ),
+            Field::Album => suggest(
+                input,
+                self.albums.iter().map(|album| {
+                    let detail = format!("{}, {}", album.artist, album.release_year);
+                    (&album.title, detail)
+                }),
+            ),
+            Field::Title | Field::ReleaseYear | Field::MediaType => Vec::new(),
+        }
+    }
+
+    // the album with this title, preferring the one by this artist when several artists have one
+    pub fn find_album(&self, title: &str, artist: &str) -> Option<&Album> {
+        let albums: Vec<&Album> = self
+            .albums
+            .iter()
+            .filter(|album| album.title == title)
+            .collect();
+        albums
+            .iter()
+            .find(|album| album.artist.eq_ignore_ascii_case(artist))
+            .or(albums.first())
+            .copied()
+    }
+}
+
+fn suggest<'a>(input: &str, values: impl Iterator<Item = (&'a String, String)>) -> Vec<Suggestion> {
+    let input = input.trim();
+    let values: Vec<(&String, String)> = values.collect();
+    // a value that's typed in exactly has nothing left to complete
+    if input.is_empty() || values.iter().any(|(value, _)| *value == input) {
+        return Vec::new();
+    }
+    let mut ranked: Vec<(usize, &String, String)> = values
+        .into_iter()
+        .filter_map(|(value, detail)| Some((rank(input, value)?, value, detail)))
+        .collect();
+    // the closest completion of each kind first: "Black Sabbath" before "Black Sabbath Tribute"
+    ranked.sort_by(|a, b| (a.0, a.1.len(), a.1).cmp(&(b.0, b.1.len(), b.1)));
+    ranked
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, value, detail)| Suggestion {
+            value: value.clone(),
+            detail,
+        })
+        .collect()
+}
+
+// how well a value completes the input, ignoring case, lower is better: it starts with the input,
+// a word of it does, it has it somewhere, or its start is the input with a typo or two
+pub fn rank(input: &str, value: &str) -> Option<usize> {
+    let (input, value) = (input.to_lowercase(), value.to_lowercase());
+    if value.starts_with(&input) {
+        Some(0)
+    } else if value
+        .split_whitespace()
+        .any(|word| word.starts_with(&input))
+    {
+        Some(1)
+    } else if value.contains(&input) {
+        Some(2)
+    } else {
+        let length = input.chars().count();
+        let start: String = value.chars().take(length).collect();
+        let edits = fuzzy::edit_distance(&input, &start);
+        (edits <= fuzzy::allowed_typos(length)).then_some(2 + edits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_type::Format;
+
+    fn vocabulary() -> Vocabulary {
+        Vocabulary {
+            artists: [
+                "Black Sabbath Tribute Band",
+                "Black Sabbath",
+                "Chief Keef",
+                "The Black Keys",
+                "Jack Black",
+                "Radiohead",
+            ]
+            .map(str::to_owned)
+            .to_vec(),
+            albums: vec![
+                Album::new(0, "Greatest Hits", "Queen", 1981, "CD"),
+                Album::new(0, "Greatest Hits", "ABBA", 1975, "Vinyl"),
+            ],
+            media_types: vec![
+                MediaType::new("CD", Format::Cd),
+                MediaType::new("Vinyl", Format::Vinyl),
+            ],
+        }
+    }
+
+    fn values(suggestions: Vec<Suggestion>) -> Vec<String> {
+        suggestions.into_iter().map(|found| found.value).collect()
+    }
+
+    #[test]
+    fn test_suggestions_are_ranked() {
+        let vocabulary = vocabulary();
+        // prefixes first, shortest first, then a word's prefix, then anywhere in the value
+        assert_eq!(
+            values(vocabulary.suggest(Field::Artist, "black")),
+            vec![
+                "Black Sabbath",
+                "Black Sabbath Tribute Band",
+                "Jack Black",
+                "The Black Keys"
+            ]
+        );
+        // the spelling the library already has wins over a new one
+        assert_eq!(
+            values(vocabulary.suggest(Field::Artist, "chief keef")),
+            vec!["Chief Keef"]
+        );
+        assert_eq!(
+            values(vocabulary.suggest(Field::Artist, "radoihead")),
+            vec!["Radiohead"]
+        );
+        assert!(vocabulary.suggest(Field::Artist, "Radiohead").is_empty());
+        assert!(vocabulary.suggest(Field::Artist, " ").is_empty());
+        assert!(vocabulary.suggest(Field::Title, "black").is_empty());
+        // media types have a picker instead
+        assert!(vocabulary.suggest(Field::MediaType, "v").is_empty());
+    }
+
+    #[test]
+    fn test_albums_are_found_by_artist() {
+        let vocabulary = vocabulary();
+        let suggestions = vocabulary.suggest(Field::Album, "greatest");
+        assert_eq!(suggestions[0].detail, "Queen, 1981");
+        assert_eq!(suggestions[1].detail, "ABBA, 1975");
+        let album = vocabulary.find_album("Greatest Hits", "abba").unwrap();
+        assert_eq!(album.media_type, "Vinyl");
+        let album = vocabulary.find_album("Greatest Hits", "").unwrap();
+        assert_eq!(album.artist, "Queen");
+        assert!(vocabulary.find_album("Greatest", "Queen").is_none());
+    }
+}
@@ -1,143 +1,424 @@
-// Database module contains all database functions for connecting and interacting with the DB
+// Database module contains the storage abstraction the app talks to, and picks a backend at startup
 
-use crate::song::Song;
-use sqlx::{
-    mysql::{MySqlConnectOptions, MySqlQueryResult},
-    MySql, MySqlPool, Pool,
+mod mariadb;
+mod sqlite;
+mod store;
+
+use crate::{
+    album::Album,
+    artist::Artist,
+    audit::AuditEntry,
+    journal::Change,
+    media_type::MediaType,
+    playlist::{Playlist, PlaylistEntry},
+    query::Query,
+    scan::ScannedFile,
+    song::{Song, SongFields, TrashedSong},
+    sort::Sort,
 };
-use std::process::{Command, Output};
-
-pub async fn connect_to_database(url: &str) -> Result<Pool<MySql>, sqlx::Error> {
-    // Configure database connection options
-    let opts: MySqlConnectOptions = match url.parse() {
-        Ok(valid_url) => valid_url,
-        Err(error) => panic!("Invalid URL. Error: {}", error),
-    };
-    // Attempt to connect to the database
-    match MySqlPool::connect_with(opts.clone()).await {
-        Ok(connection) => Ok(connection),
-        Err(err) => {
-            // If connection fails, attempt to start the database server
-            eprintln!(
-                "Failed to connect to the database, attempting to reconnect: {}",
-                err
-            );
-            start_database_server().await?;
-
-            // Retry connecting
-            MySqlPool::connect_with(opts).await
-        }
-    }
-}
+use async_trait::async_trait;
+use std::{fmt::Debug, sync::Arc};
 
-#[allow(unused_assignments)]
-async fn start_database_server() -> Result<(), std::io::Error> {
-    // attempt to run the appropriate command depending on OS
-    let os = std::env::consts::OS;
-    let mut output = Command::new("").output();
-
-    match os {
-        "linux" => output = start_db_unix(),
-        "windows" => output = start_db_windows(),
-        _ => {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Unsupported,
-                "Unsupported operating system: {os}",
-            ));
-        }
-    };
+pub use mariadb::MariaDbStore;
+pub use sqlite::SqliteStore;
 
-    let out = output?;
+// every column of a Song, with the artist and album names joined in, for the songs that aren't in the trash.
+// Backends add AND clauses to this.
+const SELECT_SONGS: &str = "SELECT s.id, s.title, s.artist_id, ar.name AS artist, s.album_id, al.title AS album, s.release_year, s.media_type \
+    FROM Songs s JOIN Artists ar ON ar.id = s.artist_id JOIN Albums al ON al.id = s.album_id WHERE s.deleted_at IS NULL";
 
-    // Check if command execution was successful
-    if out.status.success() {
-        println!("Database server started successfully");
-        Ok(())
-    } else {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            format!("Failed to start database server: {:?}", out.stderr),
-        ))
-    }
-}
+// the same joins as SELECT_SONGS, so the same AND clauses count the songs they match
+const COUNT_SONGS: &str = "SELECT COUNT(*) \
+    FROM Songs s JOIN Artists ar ON ar.id = s.artist_id JOIN Albums al ON al.id = s.album_id WHERE s.deleted_at IS NULL";
 
-fn start_db_unix() -> Result<Output, std::io::Error> {
-    // Command to start MariaDB server, adjust according to your setup
-    Command::new("mariadbd").output()
-}
+// the same columns as SELECT_SONGS for the songs in the trash, so purging them can audit what went.
+// Backends add AND clauses to this.
+const SELECT_TRASHED_SONGS: &str = "SELECT s.id, s.title, s.artist_id, ar.name AS artist, s.album_id, al.title AS album, s.release_year, s.media_type \
+    FROM Songs s JOIN Artists ar ON ar.id = s.artist_id JOIN Albums al ON al.id = s.album_id WHERE s.deleted_at IS NOT NULL";
 
-fn start_db_windows() -> Result<Output, std::io::Error> {
-    // Command to start MySQL service on Windows
-    Command::new("net").arg("start").arg("mariadb").output()
-}
+// the songs in the trash with when they were deleted, most recently deleted first
+const SELECT_TRASH: &str = "SELECT s.id, s.title, s.artist_id, ar.name AS artist, s.album_id, al.title AS album, s.release_year, s.media_type, s.deleted_at \
+    FROM Songs s JOIN Artists ar ON ar.id = s.artist_id JOIN Albums al ON al.id = s.album_id WHERE s.deleted_at IS NOT NULL \
+    ORDER BY s.deleted_at DESC, s.id DESC";
 
-// Add a song to DB
-pub async fn add_song(pool: &MySqlPool, new_song: Song) -> Result<MySqlQueryResult, sqlx::Error> {
-    let result = sqlx::query("INSERT INTO Songs (id, title, artist, album, release_year, media_type) VALUES (?, ?, ?, ?, ?, ?)")
-    .bind(0)
-    .bind(new_song.title)
-    .bind(new_song.artist)
-    .bind(new_song.album)
-    .bind(new_song.release_year)
-    .bind(new_song.media_type)
-    .execute(pool).await?;
-    Ok(result)
+// where a page of songs starts, for tables that load a window of a long list at a time.
+// A page starts next to a song rather than at a row number, so a page after or before a song
+// never skips or repeats one, even if songs are added or deleted between pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PageCursor {
+    First,
+    After(u32),
+    Before(u32),
+    Last,
+}
+// a value bound to a placeholder of PageCursor::clauses: the id of the song a page starts next to,
+// or the words songs are ranked by
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PageValue<'a> {
+    Id(u32),
+    Words(&'a str),
 }
 
-// Search function to look for songs like a keyword
-pub async fn get_songs_matching(
-    pool: &MySqlPool,
-    keyword: String,
-) -> Result<Vec<Song>, sqlx::Error> {
-    let keyword_like = format!("%{}%", keyword);
-
-    let ids: Vec<Song> = sqlx::query_as!(Song,
-        "SELECT * FROM Songs WHERE title LIKE ? OR artist LIKE ? OR album LIKE ? OR release_year LIKE ? OR media_type LIKE ?",
-        keyword_like,
-        keyword_like,
-        keyword_like,
-        keyword_like,
-        keyword_like
-    )
-    .fetch_all(pool)
-    .await?;
-    Ok(ids)
+impl PageCursor {
+    // the condition added to the query's, the ORDER BY, and the values to bind for them in order.
+    // relevance is the search's words when the backend ranks songs by them, see Sort::order_by.
+    fn clauses<'a>(
+        &self,
+        sort: &Sort,
+        relevance: Option<&'a str>,
+    ) -> (String, String, Vec<PageValue<'a>>) {
+        let backwards = self.is_backwards();
+        let order_by = sort.order_by(relevance.is_some());
+        // the placeholders in a sort expression are all for the words
+        let words = |expression: &str| {
+            let count = expression.matches('?').count();
+            std::iter::repeat_n(relevance.map(PageValue::Words), count).flatten()
+        };
+        let order = order_by
+            .iter()
+            .map(|(expression, descending)| match *descending != backwards {
+                true => format!("{} DESC", expression),
+                false => format!("{} ASC", expression),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        let order_values = order_by
+            .iter()
+            .flat_map(|(expression, _)| words(expression));
+        let (Self::After(id) | Self::Before(id)) = self else {
+            return (String::new(), order, order_values.collect());
+        };
+        // an expression compared with its value for the song the page starts next to
+        let compared = |expression: &str| {
+            let value = match expression {
+                "s.id" => None,
+                expression => Some(words(expression)),
+            };
+            words(expression)
+                .chain(value.into_iter().flatten())
+                .chain([PageValue::Id(*id)])
+        };
+        // a song comes after the one the page starts from if it sorts after it on the first key,
+        // or ties on the first key and sorts after it on the second, and so on down to the id
+        let mut values = Vec::new();
+        let alternatives: Vec<String> = order_by
+            .iter()
+            .enumerate()
+            .map(|(index, (expression, descending))| {
+                let mut parts: Vec<String> = order_by[..index]
+                    .iter()
+                    .map(|(tied, _)| {
+                        values.extend(compared(tied));
+                        format!("{} = {}", tied, sort_value(tied))
+                    })
+                    .collect();
+                let operator = if *descending != backwards { "<" } else { ">" };
+                values.extend(compared(expression));
+                parts.push(format!(
+                    "{} {} {}",
+                    expression,
+                    operator,
+                    sort_value(expression)
+                ));
+                parts.join(" AND ")
+            })
+            .collect();
+        values.extend(order_values);
+        (
+            format!(" AND ({})", alternatives.join(" OR ")),
+            order,
+            values,
+        )
+    }
+
+    // pages read backwards from the end come back in reverse
+    fn is_backwards(&self) -> bool {
+        matches!(self, Self::Before(_) | Self::Last)
+    }
 }
 
-// gets every song
-pub async fn get_all_songs(pool: &MySqlPool) -> Result<Vec<Song>, sqlx::Error> {
-    let songs = sqlx::query_as!(Song, "SELECT * FROM Songs")
-        .fetch_all(pool)
-        .await?;
-    Ok(songs)
+// the value a sort expression has for the song with the id bound to its placeholder
+fn sort_value(expression: &str) -> String {
+    match expression {
+        "s.id" => "?".to_owned(),
+        expression => format!(
+            "(SELECT {expression} FROM Songs s JOIN Artists ar ON ar.id = s.artist_id \
+             JOIN Albums al ON al.id = s.album_id WHERE s.id = ?)"
+        ),
+    }
 }
 
-// update a song with new info
-pub async fn update_song(
-    pool: &MySqlPool,
-    song_id: u32,
-    song_fields: Song,
-) -> Result<MySqlQueryResult, sqlx::Error> {
-    let result = sqlx::query("UPDATE Songs SET title = ?, artist = ?, album = ?, release_year = ?, media_type = ? WHERE id = ?").bind(song_fields.title).bind(song_fields.artist).bind(song_fields.album).bind(song_fields.release_year).bind(song_fields.media_type).bind(song_id).execute(pool).await?;
-    Ok(result)
+// the columns of a Journal row, see journal.rs. Backends add WHERE and ORDER BY clauses to this.
+const SELECT_JOURNAL: &str =
+    "SELECT id, song_id, old_title, old_artist, old_album, old_release_year, old_media_type, \
+    new_title, new_artist, new_album, new_release_year, new_media_type FROM Journal";
+
+const INSERT_JOURNAL: &str = "INSERT INTO Journal (song_id, old_title, old_artist, old_album, old_release_year, old_media_type, \
+    new_title, new_artist, new_album, new_release_year, new_media_type) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+// the columns of an AuditLog row, see audit.rs. Backends add WHERE and ORDER BY clauses to this.
+const SELECT_AUDIT: &str = "SELECT id, song_id, changed_at, changed_by, old_title, old_artist, old_album, old_release_year, old_media_type, \
+    new_title, new_artist, new_album, new_release_year, new_media_type FROM AuditLog";
+
+const INSERT_AUDIT: &str = "INSERT INTO AuditLog (song_id, changed_at, changed_by, old_title, old_artist, old_album, old_release_year, old_media_type, \
+    new_title, new_artist, new_album, new_release_year, new_media_type) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)";
+
+// every column of an Album plus how many songs are on it, sorted by artist then title.
+// Songs in the trash aren't counted here or in the artists' and playlists' counts.
+const SELECT_ALBUMS: &str = "SELECT al.id, al.title, ar.name AS artist, al.release_year, al.media_type, COUNT(s.id) AS song_count \
+    FROM Albums al JOIN Artists ar ON ar.id = al.artist_id LEFT JOIN Songs s ON s.album_id = al.id AND s.deleted_at IS NULL \
+    GROUP BY al.id, al.title, ar.name, al.release_year, al.media_type ORDER BY ar.name, al.title";
+
+// every artist with how many songs and albums point at it, sorted by name
+const SELECT_ARTISTS: &str = "SELECT ar.id, ar.name, \
+    (SELECT COUNT(*) FROM Songs s WHERE s.artist_id = ar.id AND s.deleted_at IS NULL) AS song_count, \
+    (SELECT COUNT(*) FROM Albums al WHERE al.artist_id = ar.id) AS album_count \
+    FROM Artists ar ORDER BY ar.name";
+
+// every media type on the managed list with how many songs and albums have it, sorted by name
+const SELECT_MEDIA_TYPES: &str = "SELECT mt.id, mt.name, mt.format, mt.speed, mt.size, mt.bitrate, mt.codec, \
+    (SELECT COUNT(*) FROM Songs s WHERE s.media_type = mt.name AND s.deleted_at IS NULL) AS song_count, \
+    (SELECT COUNT(*) FROM Albums al WHERE al.media_type = mt.name) AS album_count \
+    FROM MediaTypes mt ORDER BY mt.name";
+
+// every playlist with how many songs are on it, sorted by name
+const SELECT_PLAYLISTS: &str = "SELECT p.id, p.name, p.description, COUNT(s.id) AS song_count \
+    FROM Playlists p LEFT JOIN PlaylistSongs ps ON ps.playlist_id = p.id \
+    LEFT JOIN Songs s ON s.id = ps.song_id AND s.deleted_at IS NULL \
+    GROUP BY p.id, p.name, p.description ORDER BY p.name";
+
+// the songs on one playlist in order, each with its entry id. The ? is the playlist id.
+// A song in the trash keeps its place on the playlist, hidden until it's restored.
+const SELECT_PLAYLIST_ENTRIES: &str = "SELECT ps.id AS entry_id, \
+    s.id, s.title, s.artist_id, ar.name AS artist, s.album_id, al.title AS album, s.release_year, s.media_type \
+    FROM PlaylistSongs ps JOIN Songs s ON s.id = ps.song_id \
+    JOIN Artists ar ON ar.id = s.artist_id JOIN Albums al ON al.id = s.album_id \
+    WHERE ps.playlist_id = ? AND s.deleted_at IS NULL ORDER BY ps.position, ps.id";
+
+// LibraryStore is every operation the TUI needs from a database.
+// Each backend implements it with the same behavior, so App and Popup don't care which one they have.
+#[async_trait]
+pub trait LibraryStore: Debug + Send + Sync {
+    // Add a song, returns the new song's id.
+    // The artist and album are looked up by name (ignoring case) and created if they don't exist yet.
+    // Adding, updating and deleting a song is written to the undo journal along with the change.
+    // Every change to a song, including imports, scans, undo and redo, is also written to the audit log.
+    async fn create_song(&self, new_song: Song) -> Result<u32, sqlx::Error>;
+
+    // Add a song, returns the number of rows affected
+    async fn add_song(&self, new_song: Song) -> Result<u64, sqlx::Error> {
+        self.create_song(new_song).await?;
+        Ok(1)
+    }
+
+    // Add many songs in one transaction, returns the number added.
+    // If any of them fails, none of them are added. Each one is journaled like an add_song.
+    async fn add_songs(&self, new_songs: Vec<Song>) -> Result<u64, sqlx::Error>;
+
+    // gets one song by id, None if there is no such song
+    async fn get_song(&self, song_id: u32) -> Result<Option<Song>, sqlx::Error>;
+
+    // Search function to look for songs matching a parsed query, see query.rs
+    async fn search_songs(&self, query: &Query) -> Result<Vec<Song>, sqlx::Error>;
+
+    // gets up to limit songs matching a query, in the sort order, starting from the cursor.
+    // A backend that ranks the query's words by relevance puts the best matches first, see Sort::order_by.
+    async fn get_songs_page(
+        &self,
+        query: &Query,
+        sort: &Sort,
+        cursor: PageCursor,
+        limit: u32,
+    ) -> Result<Vec<Song>, sqlx::Error>;
+
+    // counts the songs matching a query
+    async fn count_songs(&self, query: &Query) -> Result<u64, sqlx::Error>;
+
+    // gets every song
+    async fn get_all_songs(&self) -> Result<Vec<Song>, sqlx::Error>;
+
+    // update a song with new info, returns the number of rows affected.
+    // Names are resolved to ids the same way as in add_song.
+    async fn update_song(&self, song_id: u32, song_fields: Song) -> Result<u64, sqlx::Error>;
+
+    // move a song to the trash, returns the number of rows affected.
+    // Songs in the trash are left out of every other query, as if they were deleted.
+    async fn delete_song(&self, song_id: u32) -> Result<u64, sqlx::Error>;
+
+    // give many songs the fields that are set in one transaction, returns the number of songs changed.
+    // Each song is journaled and audited like an update_song, so undo takes them back one at a time.
+    async fn update_songs(&self, song_ids: &[u32], fields: &SongFields)
+        -> Result<u64, sqlx::Error>;
+
+    // move many songs to the trash in one transaction, returns the number moved
+    async fn delete_songs(&self, song_ids: &[u32]) -> Result<u64, sqlx::Error>;
+
+    // gets every song in the trash with when it was deleted, most recently deleted first
+    async fn get_trashed_songs(&self) -> Result<Vec<TrashedSong>, sqlx::Error>;
+
+    // take a song out of the trash, returns the number of rows affected.
+    // It's journaled and audited like adding the song.
+    async fn restore_song(&self, song_id: u32) -> Result<u64, sqlx::Error>;
+
+    // delete a song in the trash for good, returns the number of rows affected.
+    // Purging is audited as a deletion, like every other change to a song.
+    // An album the purged songs leave without songs is deleted along with them.
+    async fn purge_song(&self, song_id: u32) -> Result<u64, sqlx::Error>;
+
+    // delete every song put in the trash before a "YYYY-MM-DD HH:MM:SS" time for good,
+    // returns the number of songs deleted. Albums are deleted along with them like in purge_song.
+    async fn purge_trash(&self, before: &str) -> Result<u64, sqlx::Error>;
+
+    // undo the latest song change that hasn't been undone yet, in one transaction.
+    // Returns the change that was undone, None if there's nothing to undo.
+    async fn undo(&self) -> Result<Option<Change>, sqlx::Error>;
+
+    // make the change undone last again, None if there's nothing to redo.
+    // Changes can be redone until the next add, update or delete.
+    async fn redo(&self) -> Result<Option<Change>, sqlx::Error>;
+
+    // every change made to one song, newest first
+    async fn get_song_history(&self, song_id: u32) -> Result<Vec<AuditEntry>, sqlx::Error>;
+
+    // every change made to any song between two "YYYY-MM-DD HH:MM:SS" times (inclusive), oldest first
+    async fn get_changes(&self, from: &str, to: &str) -> Result<Vec<AuditEntry>, sqlx::Error>;
+
+    // Add an album, returns the number of rows affected. The artist is resolved like in add_song.
+    async fn add_album(&self, new_album: Album) -> Result<u64, sqlx::Error>;
+
+    // gets every album along with its number of songs
+    async fn get_all_albums(&self) -> Result<Vec<Album>, sqlx::Error>;
+
+    // update an album with new info, returns the number of album rows affected.
+    // If update_songs is true, every song on the album gets the album's year and media type as well.
+    // Each song that changes is journaled like an update_song.
+    async fn update_album(
+        &self,
+        album_id: u32,
+        album_fields: Album,
+        update_songs: bool,
+    ) -> Result<u64, sqlx::Error>;
+
+    // move every song on an album to the trash, journaled like delete_song, then delete the album
+    // if no song points at it anymore, trash included. Returns the number of album rows affected,
+    // 0 while songs in the trash still need it. Purging them deletes the album then.
+    async fn delete_album(&self, album_id: u32) -> Result<u64, sqlx::Error>;
+
+    // gets every artist along with their number of songs and albums
+    async fn get_all_artists(&self) -> Result<Vec<Artist>, sqlx::Error>;
+
+    // every media type on the managed list with its aliases and number of songs and albums, by name.
+    // Songs and albums saved with an alias or another case of a type's name are filed under the type,
+    // and saving one with a media type that isn't on the list fails, only add_media_type adds types.
+    async fn get_media_types(&self) -> Result<Vec<MediaType>, sqlx::Error>;
+
+    // add a media type to the list, returns the number of rows affected.
+    // An alias with the same name stops being one, so the new type gets its songs from then on.
+    async fn add_media_type(&self, new_media_type: MediaType) -> Result<u64, sqlx::Error>;
+
+    // file every song and album with a media type (ignoring case) under another type, and keep it
+    // as an alias of that type. A type on the list with that name is merged into the other one.
+    // Returns the number of songs changed.
+    async fn map_media_type(&self, from: &str, into_media_type_id: u32)
+        -> Result<u64, sqlx::Error>;
+
+    // rename an artist, returns the number of rows affected.
+    // Songs and albums point at the artist by id, so they follow automatically.
+    // Renaming to another artist's name fails; merge them instead.
+    async fn rename_artist(&self, artist_id: u32, new_name: String) -> Result<u64, sqlx::Error>;
+
+    // move every song and album of one artist onto another and delete the first, in one transaction.
+    // Albums with the same title end up as one album. Returns the number of songs moved.
+    async fn merge_artists(
+        &self,
+        from_artist_id: u32,
+        into_artist_id: u32,
+    ) -> Result<u64, sqlx::Error>;
+
+    // Add a playlist, returns the number of rows affected
+    async fn add_playlist(&self, new_playlist: Playlist) -> Result<u64, sqlx::Error>;
+
+    // gets every playlist along with its number of songs
+    async fn get_all_playlists(&self) -> Result<Vec<Playlist>, sqlx::Error>;
+
+    // change a playlist's name and description, returns the number of rows affected
+    async fn update_playlist(
+        &self,
+        playlist_id: u32,
+        playlist_fields: Playlist,
+    ) -> Result<u64, sqlx::Error>;
+
+    // delete a playlist and its entries, returns the number of playlist rows affected.
+    // The songs themselves stay in the library.
+    async fn delete_playlist(&self, playlist_id: u32) -> Result<u64, sqlx::Error>;
+
+    // gets the songs on a playlist in order
+    async fn get_playlist_entries(
+        &self,
+        playlist_id: u32,
+    ) -> Result<Vec<PlaylistEntry>, sqlx::Error>;
+
+    // add a song to the end of a playlist, returns the number of rows affected
+    async fn add_song_to_playlist(
+        &self,
+        playlist_id: u32,
+        song_id: u32,
+    ) -> Result<u64, sqlx::Error>;
+
+    // swap the positions of two entries on a playlist, returns the number of rows affected
+    async fn swap_playlist_entries(
+        &self,
+        first_entry_id: u32,
+        second_entry_id: u32,
+    ) -> Result<u64, sqlx::Error>;
+
+    // take one entry off its playlist, returns the number of rows affected
+    async fn remove_playlist_entry(&self, entry_id: u32) -> Result<u64, sqlx::Error>;
+
+    // every file a folder scan has imported, with the size and modification time it had then
+    async fn get_scanned_files(&self) -> Result<Vec<ScannedFile>, sqlx::Error>;
+
+    // saves the songs read from scanned files in one transaction, returns the number of files saved.
+    // A file with a song_id updates that song, the others add a new one, journaled like any other.
+    // Each file's path, size and modification time are recorded for the next scan.
+    async fn save_scanned_files(&self, files: Vec<(ScannedFile, Song)>)
+        -> Result<u64, sqlx::Error>;
 }
 
-// delete a song by id
-pub async fn delete_song(pool: &MySqlPool, song_id: u32) -> Result<MySqlQueryResult, sqlx::Error> {
-    let result = sqlx::query("DELETE FROM Songs WHERE id = ?")
-        .bind(song_id)
-        .execute(pool)
-        .await?;
-    Ok(result)
+// connect to the backend named by the URL's scheme
+// mysql:// and mariadb:// go to MariaDB, sqlite:// goes to a single-file SQLite library.
+// user is the name the changes made through the store are recorded under in the audit log.
+pub async fn connect_to_database(
+    url: &str,
+    user: &str,
+) -> Result<Arc<dyn LibraryStore>, sqlx::Error> {
+    if url.starts_with("sqlite:") {
+        Ok(Arc::new(SqliteStore::connect(url).await?.with_user(user)))
+    } else if let Some(rest) = url.strip_prefix("mariadb://") {
+        // sqlx only knows the mysql:// scheme, but the wire protocol is the same
+        Ok(Arc::new(
+            MariaDbStore::connect(&format!("mysql://{rest}"))
+                .await?
+                .with_user(user),
+        ))
+    } else if url.starts_with("mysql://") {
+        Ok(Arc::new(MariaDbStore::connect(url).await?.with_user(user)))
+    } else {
+        Err(sqlx::Error::Configuration(
+            format!(
+                "Unsupported database URL \"{url}\", expected mysql://, mariadb:// or sqlite://"
+            )
+            .into(),
+        ))
+    }
 }
 
 // Tests for DB functions
 #[cfg(test)]
 mod tests {
 
-    // TO RUN TESTS: YOU MUST SET DATABASE_URL TO music-test
-    // cargo sqlx prepare -D mysql://root:@localhost:3306/music-test
-    // Don't forget to prepare sqlx for the actual database when done running tests ;)
+    // TO RUN TESTS: YOU MUST HAVE AN EMPTY DATABASE CALLED music-test
+    // The migrations create the tables the first time a test connects.
 
     // These tests are meant to be run in sequence; Don't just `cargo test`, run each one individually.
 
@@ -149,71 +430,99 @@ mod tests {
     // test song creation
     #[tokio::test]
     async fn test_create() {
-        let pool = connect_to_database(URL).await.unwrap();
-        let test_song = Song::new(0, "Testing", "Unit Tests", "Under Test", 2024, "N/A");
-        let result = add_song(&pool, test_song).await.unwrap();
-        println!("rows affected by create: {}", result.rows_affected());
+        let store = connect_to_database(URL, "test").await.unwrap();
+        let test_song = Song::new(0, "Testing", "Unit Tests", "Under Test", 2024, "CD");
+        let rows_affected = store.add_song(test_song).await.unwrap();
+        println!("rows affected by create: {}", rows_affected);
         // assert the query executed
-        assert!(result.rows_affected() == 1);
+        assert!(rows_affected == 1);
     }
 
     // test song retrieval
     #[tokio::test]
     async fn test_retrieve() {
-        let pool = connect_to_database(URL).await.unwrap();
+        let store = connect_to_database(URL, "test").await.unwrap();
         let test_song = "Test";
-        let matching_songs = get_songs_matching(&pool, test_song.to_owned())
+        let matching_songs = store
+            .search_songs(&Query::parse(test_song).unwrap())
             .await
             .unwrap();
 
         println!("Songs that match {}: {:?}", test_song, matching_songs);
         // assert the result contains keyword ignoring case
         assert!(
-            matching_songs.get(0).unwrap().title.contains("Test")
-                || matching_songs.get(0).unwrap().title.contains("test")
+            matching_songs.first().unwrap().title.contains("Test")
+                || matching_songs.first().unwrap().title.contains("test")
         );
     }
 
     // test updating a song
     #[tokio::test]
     async fn test_update() {
-        let pool = connect_to_database(URL).await.unwrap();
-        let updated_song = Song::new(0, "testing again", "Unit Tests", "Testing 2", 2024, "N/A");
-        let song_list = get_songs_matching(&pool, "Testing".to_owned())
+        let store = connect_to_database(URL, "test").await.unwrap();
+        let updated_song = Song::new(0, "testing again", "Unit Tests", "Testing 2", 2024, "CD");
+        let song_list = store
+            .search_songs(&Query::parse("Testing").unwrap())
             .await
             .unwrap();
-        let test_song = song_list.get(0).unwrap();
+        let test_song = song_list.first().unwrap();
         println!("Song with id {} will be updated", test_song.id);
 
-        let result = update_song(&pool, test_song.id, updated_song)
-            .await
-            .unwrap();
-        println!("Rows affected by update: {:?}", result);
+        let rows_affected = store.update_song(test_song.id, updated_song).await.unwrap();
+        println!("Rows affected by update: {:?}", rows_affected);
         // assert the update was sucessful
-        assert!(
-            result.rows_affected() == 1,
-            "rows affected: {}",
-            result.rows_affected()
-        );
+        assert!(rows_affected == 1, "rows affected: {}", rows_affected);
     }
 
     // test deleting a song
     #[tokio::test]
     async fn test_delete() {
-        let pool = connect_to_database(URL).await.unwrap();
-        let all_songs = get_all_songs(&pool).await.unwrap();
+        let store = connect_to_database(URL, "test").await.unwrap();
+        let all_songs = store.get_all_songs().await.unwrap();
 
         println!("Songs before delete: {:?}", all_songs);
         let lowest_id = get_lowest_id(&all_songs);
-        delete_song(&pool, lowest_id).await.unwrap();
+        store.delete_song(lowest_id).await.unwrap();
 
-        let new_songs = get_all_songs(&pool).await.unwrap();
+        let new_songs = store.get_all_songs().await.unwrap();
         println!("Songs after delete: {:?}", new_songs);
         assert_ne!(new_songs.len(), all_songs.len()); // assert the total # of songs decreased
     }
 
+    // test that plain words are found through the FULLTEXT index, also after a rename
+    #[tokio::test]
+    async fn test_fulltext_search() {
+        let store = connect_to_database(URL, "test").await.unwrap();
+        let test_song = Song::new(
+            0,
+            "Fulltext Testing",
+            "Indexed Artist",
+            "Indexed Album",
+            2024,
+            "CD",
+        );
+        let id = store.create_song(test_song).await.unwrap();
+        let found = store
+            .search_songs(&Query::parse("fulltext indexed").unwrap())
+            .await
+            .unwrap();
+        assert!(found.iter().any(|song| song.id == id));
+
+        let artist_id = store.get_song(id).await.unwrap().unwrap().artist_id;
+        store
+            .rename_artist(artist_id, "Renamed Artist".to_owned())
+            .await
+            .unwrap();
+        let found = store
+            .search_songs(&Query::parse("renamed").unwrap())
+            .await
+            .unwrap();
+        assert!(found.iter().any(|song| song.id == id));
+        store.delete_song(id).await.unwrap();
+    }
+
     // helper function to get the song with lowest id
-    fn get_lowest_id(songs: &Vec<Song>) -> u32 {
+    fn get_lowest_id(songs: &[Song]) -> u32 {
         let mut lowest = 999;
         for song in songs {
             if song.id < lowest {
@@ -222,4 +531,30 @@ mod tests {
         }
         lowest
     }
+
+    // doesn't need the database: the words are bound wherever the relevance is compared
+    #[test]
+    fn test_page_clauses_rank_by_relevance() {
+        let sort = Sort::default();
+        let (keyset, order, values) = PageCursor::First.clauses(&sort, Some("war pigs"));
+        assert_eq!(keyset, "");
+        assert_eq!(order, format!("{} DESC, s.id ASC", crate::sort::RELEVANCE));
+        assert_eq!(values, vec![PageValue::Words("war pigs")]);
+
+        let (keyset, order, values) = PageCursor::After(7).clauses(&sort, Some("war pigs"));
+        let placeholders = keyset.matches('?').count() + order.matches('?').count();
+        assert_eq!(placeholders, values.len());
+        let words = PageValue::Words("war pigs");
+        let id = PageValue::Id(7);
+        assert_eq!(
+            values,
+            // relevance < the song's, or the same relevance and id > 7, then the ORDER BY's
+            vec![words, words, id, words, words, id, id, words]
+        );
+
+        // without words the pages are in the order songs were added
+        let (_, order, values) = PageCursor::Last.clauses(&sort, None);
+        assert_eq!(order, "s.id DESC");
+        assert!(values.is_empty());
+    }
 }
@@ -0,0 +1,101 @@
+// MariaDB/MySQL implementation of LibraryStore. This is the original backend of the app.
+
+use super::store::{Backend, SqlStore};
+use crate::query::{Query, SqlValue};
+use sqlx::{
+    mysql::{MySqlConnectOptions, MySqlQueryResult},
+    MySql, MySqlPool,
+};
+use std::process::{Command, Output};
+
+pub type MariaDbStore = SqlStore<MySql>;
+
+impl Backend for MySql {
+    const UPSERT_SCANNED_FILE: &'static str =
+        "INSERT INTO ScannedFiles (path, song_id, modified, size) VALUES (?, ?, ?, ?) \
+        ON DUPLICATE KEY UPDATE song_id = VALUES(song_id), modified = VALUES(modified), size = VALUES(size)";
+
+    fn last_insert_id(result: &MySqlQueryResult) -> u32 {
+        result.last_insert_id() as u32
+    }
+
+    fn rows_affected(result: &MySqlQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    // plain words are looked up in the FULLTEXT index on search_text, most relevant songs first
+    fn condition(query: &Query) -> (String, Vec<SqlValue>, Option<String>) {
+        query.to_fulltext_sql()
+    }
+}
+
+impl MariaDbStore {
+    // connect to the server, then bring the schema up to date
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        // Configure database connection options
+        let opts: MySqlConnectOptions = url.parse()?;
+        // Attempt to connect to the database
+        let pool = match MySqlPool::connect_with(opts.clone()).await {
+            Ok(pool) => pool,
+            Err(error) => {
+                // If connection fails, attempt to start the database server. If that fails too, the
+                // connect error is returned with why the server couldn't be started.
+                if let Err(start_error) = start_database_server().await {
+                    return Err(sqlx::Error::Io(std::io::Error::new(
+                        start_error.kind(),
+                        format!(
+                            "{} (could not start the database server: {})",
+                            error, start_error
+                        ),
+                    )));
+                }
+
+                // Retry connecting
+                MySqlPool::connect_with(opts).await?
+            }
+        };
+        // apply any migrations this database hasn't seen yet
+        sqlx::migrate!("./migrations/mariadb").run(&pool).await?;
+        Ok(Self::new(pool))
+    }
+}
+
+async fn start_database_server() -> Result<(), std::io::Error> {
+    // attempt to run the appropriate command depending on OS
+    let os = std::env::consts::OS;
+    let output = match os {
+        "linux" => start_db_unix(),
+        "windows" => start_db_windows(),
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("Unsupported operating system: {os}"),
+            ));
+        }
+    };
+
+    let out = output?;
+
+    // Check if command execution was successful
+    if out.status.success() {
+        Ok(())
+    } else {
+        // what the command printed, or how it exited if it printed nothing
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let reason = match stderr.trim() {
+            "" => out.status.to_string(),
+            stderr => stderr.to_owned(),
+        };
+        Err(std::io::Error::other(reason))
+    }
+}
+
+fn start_db_unix() -> Result<Output, std::io::Error> {
+    // Command to start MariaDB server, adjust according to your setup
+    Command::new("mariadbd").output()
+}
+
+fn start_db_windows() -> Result<Output, std::io::Error> {
+    // Command to start MySQL service on Windows
+    Command::new("net").arg("start").arg("mariadb").output()
+}
@@ -0,0 +1,1026 @@
+// SQLite implementation of LibraryStore, so the library can live in a single file without a database server
+
+use super::store::{Backend, SqlStore};
+use crate::query::{Query, SqlValue};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions, SqliteQueryResult},
+    Sqlite,
+};
+use std::str::FromStr;
+
+pub type SqliteStore = SqlStore<Sqlite>;
+
+impl Backend for Sqlite {
+    const UPSERT_SCANNED_FILE: &'static str =
+        "INSERT INTO ScannedFiles (path, song_id, modified, size) VALUES (?, ?, ?, ?) \
+        ON CONFLICT (path) DO UPDATE SET song_id = excluded.song_id, modified = excluded.modified, size = excluded.size";
+
+    fn last_insert_id(result: &SqliteQueryResult) -> u32 {
+        result.last_insert_rowid() as u32
+    }
+
+    fn rows_affected(result: &SqliteQueryResult) -> u64 {
+        result.rows_affected()
+    }
+
+    // without a FULLTEXT index there's no relevance to rank by
+    fn condition(query: &Query) -> (String, Vec<SqlValue>, Option<String>) {
+        let (condition, values) = query.to_sql();
+        (condition, values, None)
+    }
+}
+
+impl SqliteStore {
+    // open (or create) the library file and bring the schema up to date
+    pub async fn connect(url: &str) -> Result<Self, sqlx::Error> {
+        let opts = SqliteConnectOptions::from_str(url)?.create_if_missing(true);
+        // SQLite only allows one writer at a time, and an in-memory database
+        // only exists on the connection that created it, so one connection is plenty
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect_with(opts)
+            .await?;
+        // apply any migrations this file hasn't seen yet
+        sqlx::migrate!("./migrations/sqlite").run(&pool).await?;
+        Ok(Self::new(pool))
+    }
+}
+
+// These run against an in-memory database, so unlike the MariaDB tests they don't need a server
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        album::Album,
+        audit::AuditEntry,
+        database::{LibraryStore, PageCursor},
+        import::Field,
+        journal::Change,
+        media_type::{Format, MediaType},
+        playlist::{Playlist, PlaylistEntry},
+        song::{Song, SongFields},
+        sort::Sort,
+    };
+
+    async fn test_store() -> SqliteStore {
+        SqliteStore::connect("sqlite::memory:").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_crud_round_trip() {
+        let store = test_store().await;
+        let test_song = Song::new(0, "Testing", "Unit Tests", "Under Test", 2024, "CD");
+        assert_eq!(store.add_song(test_song).await.unwrap(), 1);
+
+        let songs = store.get_all_songs().await.unwrap();
+        assert_eq!(songs.len(), 1);
+        let id = songs[0].id;
+
+        let updated_song = Song::new(0, "testing again", "Unit Tests", "Testing 2", 2024, "CD");
+        assert_eq!(store.update_song(id, updated_song).await.unwrap(), 1);
+        let songs = store.get_all_songs().await.unwrap();
+        assert_eq!(songs[0].title, "testing again");
+        assert_eq!(songs[0].album, "Testing 2");
+
+        assert_eq!(store.delete_song(id).await.unwrap(), 1);
+        assert!(store.get_all_songs().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_migrations_are_recorded_once() {
+        let path = std::env::temp_dir().join(format!("music-library-{}.db", std::process::id()));
+        let url = format!("sqlite://{}", path.display());
+
+        // connecting twice must not re-run (or fail on) already applied migrations
+        let store = SqliteStore::connect(&url).await.unwrap();
+        store.pool.close().await;
+        let store = SqliteStore::connect(&url).await.unwrap();
+
+        let versions: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM _sqlx_migrations ORDER BY version")
+                .fetch_all(&store.pool)
+                .await
+                .unwrap();
+        assert_eq!(versions.first(), Some(&1));
+        assert!(versions.windows(2).all(|pair| pair[0] < pair[1]));
+
+        store.pool.close().await;
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_names_resolve_to_existing_rows_ignoring_case() {
+        let store = test_store().await;
+        store
+            .add_song(Song::new(
+                0,
+                "Love Sosa",
+                "Chief Keef",
+                "Finally Rich",
+                2012,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+        store
+            .add_song(Song::new(
+                0,
+                "I Don't Like",
+                " chief keef ",
+                "finally rich",
+                2012,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+
+        let songs = store.get_all_songs().await.unwrap();
+        assert_eq!(songs[0].artist_id, songs[1].artist_id);
+        assert_eq!(songs[0].album_id, songs[1].album_id);
+        // the first spelling is the one that sticks
+        assert_eq!(songs[1].artist, "Chief Keef");
+        assert_eq!(songs[1].album, "Finally Rich");
+
+        let artists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Artists")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(artists, 1);
+    }
+
+    #[tokio::test]
+    async fn test_migration_converts_free_text_songs() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        // build a library the way it looked before songs referenced albums and artists
+        let mut migrator = sqlx::migrate!("./migrations/sqlite");
+        let all_migrations = migrator.migrations.clone();
+        migrator.migrations = all_migrations[..1].to_vec().into();
+        migrator.run(&pool).await.unwrap();
+        sqlx::raw_sql(
+            "INSERT INTO Artists VALUES ('Chief Keef'), ('Radiohead');
+            INSERT INTO Albums (title, artist, release_year, media_type) VALUES ('Finally Rich', 'Chief Keef', 2012, 'Digital Download');
+            INSERT INTO Songs (id, title, artist, album, release_year, media_type) VALUES
+                (7, 'Love Sosa', 'Chief keef', 'Finally Rich', 2012, 'Digital Download'),
+                (9, 'My Iron Lung', 'Radiohead', 'The Bends', 1995, 'Digital Download');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrator.migrations = all_migrations;
+        migrator.run(&pool).await.unwrap();
+        let store = SqliteStore::new(pool).with_user("test");
+
+        let songs = store.get_all_songs().await.unwrap();
+        assert_eq!(songs.len(), 2);
+        let love_sosa = songs.iter().find(|song| song.id == 7).unwrap();
+        assert_eq!(love_sosa.artist, "Chief Keef");
+        assert_eq!(love_sosa.album, "Finally Rich");
+        // albums only mentioned by a song are created too
+        let iron_lung = songs.iter().find(|song| song.id == 9).unwrap();
+        assert_eq!(iron_lung.album, "The Bends");
+
+        let artists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM Artists")
+            .fetch_one(&store.pool)
+            .await
+            .unwrap();
+        assert_eq!(artists, 2);
+    }
+
+    #[tokio::test]
+    async fn test_migration_maps_media_types() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        // the spellings of the same media type from before there was a list of them
+        let mut migrator = sqlx::migrate!("./migrations/sqlite");
+        let all_migrations = migrator.migrations.clone();
+        migrator.migrations = all_migrations
+            .iter()
+            .filter(|migration| migration.version < 9)
+            .cloned()
+            .collect::<Vec<_>>()
+            .into();
+        migrator.run(&pool).await.unwrap();
+        sqlx::raw_sql(
+            "INSERT INTO Artists (id, name) VALUES (1, 'Black Sabbath');
+            INSERT INTO Albums (id, title, artist_id, release_year, media_type) VALUES (1, 'Paranoid', 1, 1970, ' vinyl ');
+            INSERT INTO Songs (title, artist_id, album_id, release_year, media_type) VALUES
+                ('Paranoid', 1, 1, 1970, 'vinyl'),
+                ('Iron Man', 1, 1, 1970, 'LP'),
+                ('War Pigs', 1, 1, 1970, '12in'),
+                ('Planet Caravan', 1, 1, 1970, 'Minidisc');",
+        )
+        .execute(&pool)
+        .await
+        .unwrap();
+
+        migrator.migrations = all_migrations;
+        migrator.run(&pool).await.unwrap();
+        let store = SqliteStore::new(pool).with_user("test");
+
+        let songs = store.get_all_songs().await.unwrap();
+        let media_type = |title: &str| {
+            let song = songs.iter().find(|song| song.title == title).unwrap();
+            song.media_type.clone()
+        };
+        assert_eq!(media_type("Paranoid"), "Vinyl");
+        assert_eq!(media_type("Iron Man"), "Vinyl");
+        assert_eq!(media_type("War Pigs"), "Vinyl");
+        // a value the list doesn't know becomes a type of its own, to be mapped later
+        assert_eq!(media_type("Planet Caravan"), "Minidisc");
+        let media_types = store.get_media_types().await.unwrap();
+        let minidisc = media_types
+            .iter()
+            .find(|media_type| media_type.name == "Minidisc");
+        assert_eq!(minidisc.unwrap().format, "other");
+        let albums = store.get_all_albums().await.unwrap();
+        assert_eq!(albums[0].media_type, "Vinyl");
+    }
+
+    #[tokio::test]
+    async fn test_media_types_are_a_managed_list() {
+        let store = test_store().await;
+        // aliases and other cases are filed under the type on the list
+        store
+            .add_song(Song::new(
+                0,
+                "Paranoid",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "lp",
+            ))
+            .await
+            .unwrap();
+        let songs = store.get_all_songs().await.unwrap();
+        assert_eq!(songs[0].media_type, "Vinyl");
+        let albums = store.get_all_albums().await.unwrap();
+        assert_eq!(albums[0].media_type, "Vinyl");
+
+        // an unknown value is an error instead of a new type on the list
+        let result = store
+            .add_song(Song::new(
+                0,
+                "Iron Man",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "Shellac",
+            ))
+            .await;
+        assert!(matches!(result, Err(sqlx::Error::Configuration(_))));
+        let media_types = store.get_media_types().await.unwrap();
+        assert!(!media_types
+            .iter()
+            .any(|media_type| media_type.name == "Shellac"));
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 1);
+
+        // a new type takes over an alias with its name
+        let mut record = MediaType::new("Record", Format::Vinyl);
+        record.speed = Some("33 rpm".to_owned());
+        assert_eq!(store.add_media_type(record).await.unwrap(), 1);
+        let media_types = store.get_media_types().await.unwrap();
+        let vinyl = media_types
+            .iter()
+            .find(|media_type| media_type.name == "Vinyl")
+            .unwrap();
+        assert!(!vinyl.aliases.contains(&"Record".to_owned()));
+        assert!(media_types
+            .iter()
+            .any(|media_type| media_type.name == "Record"));
+
+        // mapping a type onto another merges it, keeping its name as an alias
+        store
+            .add_media_type(MediaType::new("Shellac", Format::Other))
+            .await
+            .unwrap();
+        store
+            .add_song(Song::new(
+                0,
+                "Iron Man",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "Shellac",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(store.map_media_type("shellac", vinyl.id).await.unwrap(), 1);
+        let songs = store.get_all_songs().await.unwrap();
+        assert!(songs.iter().all(|song| song.media_type == "Vinyl"));
+        let media_types = store.get_media_types().await.unwrap();
+        assert!(!media_types
+            .iter()
+            .any(|media_type| media_type.name == "Shellac"));
+        store
+            .add_song(Song::new(
+                0,
+                "War Pigs",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "SHELLAC",
+            ))
+            .await
+            .unwrap();
+        let songs = store.get_all_songs().await.unwrap();
+        assert!(songs.iter().all(|song| song.media_type == "Vinyl"));
+        // nothing left to map
+        assert_eq!(store.map_media_type("Shellac", vinyl.id).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_album_crud_and_propagation() {
+        let store = test_store().await;
+        store
+            .add_album(Album::new(0, "Paranoid", "Black Sabbath", 1970, "Vinyl"))
+            .await
+            .unwrap();
+        // the song lands on the existing album instead of creating another one
+        store
+            .add_song(Song::new(
+                0,
+                "War Pigs",
+                "black sabbath",
+                "paranoid",
+                1970,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+
+        let albums = store.get_all_albums().await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].song_count, 1);
+        let album_id = albums[0].id;
+
+        // without propagation the song keeps its own year and media type
+        let remaster = Album::new(0, "Paranoid", "Black Sabbath", 2016, "CD");
+        store
+            .update_album(album_id, remaster.clone(), false)
+            .await
+            .unwrap();
+        let songs = store
+            .search_songs(&Query::on_album(album_id))
+            .await
+            .unwrap();
+        assert_eq!(songs[0].release_year, 1970);
+        assert_eq!(songs[0].media_type, "Vinyl");
+        // the song's media type and the album's are both counted
+        let media_types = store.get_media_types().await.unwrap();
+        let count = |name: &str| {
+            let media_type = media_types
+                .iter()
+                .find(|media_type| media_type.name == name);
+            media_type.map(|media_type| (media_type.song_count, media_type.album_count))
+        };
+        assert_eq!(count("Vinyl"), Some((1, 0)));
+        assert_eq!(count("CD"), Some((0, 1)));
+
+        store.update_album(album_id, remaster, true).await.unwrap();
+        let songs = store
+            .search_songs(&Query::on_album(album_id))
+            .await
+            .unwrap();
+        assert_eq!(songs[0].release_year, 2016);
+        assert_eq!(songs[0].media_type, "CD");
+
+        // deleting the album trashes its songs, and can be undone
+        assert_eq!(store.delete_album(album_id).await.unwrap(), 0);
+        assert!(store.get_all_songs().await.unwrap().is_empty());
+        assert_eq!(store.get_trashed_songs().await.unwrap().len(), 1);
+        store.undo().await.unwrap();
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 1);
+
+        // the album stays until no song in the trash is on it, and purging them deletes it
+        store.delete_album(album_id).await.unwrap();
+        assert_eq!(store.get_all_albums().await.unwrap().len(), 1);
+        store.purge_trash("9999-12-31 23:59:59").await.unwrap();
+        assert!(store.get_all_albums().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rename_and_merge_artists() {
+        let store = test_store().await;
+        store
+            .add_song(Song::new(
+                0,
+                "Love Sosa",
+                "Chief Keef",
+                "Finally Rich",
+                2012,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+        store
+            .add_song(Song::new(
+                0,
+                "Hate Bein' Sober",
+                "Cheif Keef",
+                "finally rich",
+                2012,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+        store
+            .add_song(Song::new(
+                0,
+                "Faneto",
+                "Cheif Keef",
+                "Back from the Dead 2",
+                2014,
+                "Digital Download",
+            ))
+            .await
+            .unwrap();
+
+        let artists = store.get_all_artists().await.unwrap();
+        assert_eq!(artists.len(), 2);
+        let typo = artists
+            .iter()
+            .find(|artist| artist.name == "Cheif Keef")
+            .unwrap();
+        let keef = artists
+            .iter()
+            .find(|artist| artist.name == "Chief Keef")
+            .unwrap();
+        assert_eq!((typo.song_count, typo.album_count), (2, 2));
+
+        // renaming onto an existing name is refused
+        assert!(store
+            .rename_artist(typo.id, "chief keef".to_owned())
+            .await
+            .is_err());
+
+        assert_eq!(store.merge_artists(typo.id, keef.id).await.unwrap(), 2);
+        let artists = store.get_all_artists().await.unwrap();
+        assert_eq!(artists.len(), 1);
+        // the two copies of Finally Rich became one album
+        assert_eq!((artists[0].song_count, artists[0].album_count), (3, 2));
+        let albums = store.get_all_albums().await.unwrap();
+        let finally_rich = albums
+            .iter()
+            .find(|album| album.title == "Finally Rich")
+            .unwrap();
+        assert_eq!(finally_rich.song_count, 2);
+
+        store
+            .rename_artist(keef.id, "Chief Keef (Sosa)".to_owned())
+            .await
+            .unwrap();
+        let songs = store.get_all_songs().await.unwrap();
+        assert!(songs.iter().all(|song| song.artist == "Chief Keef (Sosa)"));
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_any_field() {
+        let store = test_store().await;
+        store
+            .add_song(Song::new(
+                0,
+                "My Iron Lung",
+                "Radiohead",
+                "The Bends",
+                1995,
+                "Digital Download",
+            ))
+            .await
+            .unwrap();
+        store
+            .add_song(Song::new(
+                0,
+                "War Pigs",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+
+        let by_artist = store
+            .search_songs(&Query::parse("radio").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(by_artist.len(), 1);
+        assert_eq!(by_artist[0].title, "My Iron Lung");
+
+        let by_year = store
+            .search_songs(&Query::parse("1970").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(by_year.len(), 1);
+        assert_eq!(by_year[0].artist, "Black Sabbath");
+
+        let everything = store
+            .search_songs(&Query::parse("").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(everything.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_query() {
+        let store = test_store().await;
+        store
+            .add_songs(vec![
+                Song::new(0, "Planet Telex", "Radiohead", "The Bends", 1995, "Vinyl"),
+                Song::new(0, "Planet Telex (Live)", "Radiohead", "Live", 1998, "Vinyl"),
+                Song::new(0, "Reckoner", "Radiohead", "In Rainbows", 2007, "Vinyl"),
+                Song::new(0, "1995", "Gang Starr", "Hard To Earn", 1994, "CD"),
+                Song::new(0, "100% Pure", "Saint Etienne", "Tiger Bay", 1994, "CD"),
+            ])
+            .await
+            .unwrap();
+        let titles = |query: &'static str| {
+            let store = &store;
+            async move {
+                let mut titles: Vec<String> = store
+                    .search_songs(&Query::parse(query).unwrap())
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|song| song.title)
+                    .collect();
+                titles.sort();
+                titles
+            }
+        };
+
+        assert_eq!(
+            titles("artist:radiohead media:vinyl year:1990..1999 -title:live").await,
+            vec!["Planet Telex"]
+        );
+        // a plain year still matches titles, a qualified one only the release year
+        assert_eq!(titles("1995").await, vec!["1995", "Planet Telex"]);
+        assert_eq!(titles("year:1995").await, vec!["Planet Telex"]);
+        assert_eq!(titles("year:2000..").await, vec!["Reckoner"]);
+        assert_eq!(
+            titles("\"telex (live)\"").await,
+            vec!["Planet Telex (Live)"]
+        );
+        assert_eq!(titles("title:%").await, vec!["100% Pure"]);
+    }
+
+    #[tokio::test]
+    async fn test_sorted_pages() {
+        let store = test_store().await;
+        store
+            .add_songs(vec![
+                Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"),
+                Song::new(0, "Song 2", "Blur", "Blur", 1997, "CD"),
+                Song::new(
+                    0,
+                    "Take On Me",
+                    "a-ha",
+                    "Hunting High and Low",
+                    1985,
+                    "Vinyl",
+                ),
+                Song::new(
+                    0,
+                    "Can I Kick It?",
+                    "A Tribe Called Quest",
+                    "People's",
+                    1990,
+                    "CD",
+                ),
+                Song::new(0, "Beetlebum", "Blur", "Blur", 1997, "CD"),
+                Song::new(0, "Waterloo", "ABBA", "Waterloo", 1974, "Vinyl"),
+            ])
+            .await
+            .unwrap();
+        // pages of two, so the ties on Blur's year are split across pages
+        let pages = |sort: Sort| {
+            let store = &store;
+            async move {
+                let mut titles = Vec::new();
+                let mut cursor = PageCursor::First;
+                loop {
+                    let page = store
+                        .get_songs_page(&Query::default(), &sort, cursor, 2)
+                        .await
+                        .unwrap();
+                    let Some(last) = page.last() else {
+                        return titles;
+                    };
+                    cursor = PageCursor::After(last.id);
+                    titles.extend(page.into_iter().map(|song| song.title));
+                }
+            }
+        };
+
+        // "The" and "A" don't count, and case doesn't either
+        let mut by_artist = Sort::default();
+        by_artist.toggle(Field::Artist);
+        assert_eq!(
+            pages(by_artist).await,
+            vec![
+                "Take On Me",
+                "Waterloo",
+                "Help!",
+                "Song 2",
+                "Beetlebum",
+                "Can I Kick It?"
+            ]
+        );
+
+        // newest first, with ties by title
+        let mut by_year = Sort::default();
+        by_year.toggle(Field::Title);
+        by_year.toggle(Field::ReleaseYear);
+        by_year.toggle(Field::ReleaseYear);
+        let newest_first = vec![
+            "Beetlebum",
+            "Song 2",
+            "Can I Kick It?",
+            "Take On Me",
+            "Waterloo",
+            "Help!",
+        ];
+        assert_eq!(pages(by_year.clone()).await, newest_first);
+        // reading backwards from the end gives the same order
+        let mut last = store
+            .get_songs_page(&Query::default(), &by_year, PageCursor::Last, 2)
+            .await
+            .unwrap();
+        let mut titles: Vec<String> = last.iter().map(|song| song.title.clone()).collect();
+        while !last.is_empty() {
+            last = store
+                .get_songs_page(
+                    &Query::default(),
+                    &by_year,
+                    PageCursor::Before(last[0].id),
+                    2,
+                )
+                .await
+                .unwrap();
+            titles.splice(0..0, last.iter().map(|song| song.title.clone()));
+        }
+        assert_eq!(titles, newest_first);
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo() {
+        let store = test_store().await;
+        assert_eq!(store.undo().await.unwrap(), None);
+        let id = store
+            .create_song(Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"))
+            .await
+            .unwrap();
+        let edited = Song::new(0, "Yesterday", "The Beatles", "Help!", 1965, "Vinyl");
+        store.update_song(id, edited).await.unwrap();
+        store.delete_song(id).await.unwrap();
+        assert!(store.get_song(id).await.unwrap().is_none());
+
+        // the deleted song comes back with its id, then with its old title
+        let undone = store.undo().await.unwrap().unwrap();
+        assert!(matches!(undone, Change::Deleted(_)));
+        assert_eq!(
+            store.get_song(id).await.unwrap().unwrap().title,
+            "Yesterday"
+        );
+        store.undo().await.unwrap();
+        assert_eq!(store.get_song(id).await.unwrap().unwrap().title, "Help!");
+        store.undo().await.unwrap();
+        assert!(store.get_all_songs().await.unwrap().is_empty());
+        assert_eq!(store.undo().await.unwrap(), None);
+
+        // redo goes forward again, until something else changes
+        store.redo().await.unwrap();
+        store.redo().await.unwrap();
+        assert_eq!(
+            store.get_song(id).await.unwrap().unwrap().title,
+            "Yesterday"
+        );
+        store
+            .create_song(Song::new(
+                0,
+                "Blackbird",
+                "The Beatles",
+                "The White Album",
+                1968,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(store.redo().await.unwrap(), None);
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_undo_an_import() {
+        let store = test_store().await;
+        let songs = vec![
+            Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"),
+            Song::new(0, "Yesterday", "The Beatles", "Help!", 1965, "Vinyl"),
+        ];
+        store.add_songs(songs).await.unwrap();
+
+        // each imported song is undone on its own, last one first
+        let undone = store.undo().await.unwrap().unwrap();
+        assert_eq!(undone.describe(), "adding Yesterday");
+        store.undo().await.unwrap();
+        assert!(store.get_all_songs().await.unwrap().is_empty());
+        store.redo().await.unwrap();
+        assert_eq!(store.get_all_songs().await.unwrap()[0].title, "Help!");
+    }
+
+    #[tokio::test]
+    async fn test_undo_an_album_retag() {
+        let store = test_store().await;
+        let id = store
+            .create_song(Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"))
+            .await
+            .unwrap();
+        let album_id = store.get_song(id).await.unwrap().unwrap().album_id;
+        let remaster = Album::new(album_id, "Help!", "The Beatles", 2009, "CD");
+        store.update_album(album_id, remaster, true).await.unwrap();
+        assert_eq!(
+            store.get_song(id).await.unwrap().unwrap().release_year,
+            2009
+        );
+
+        // the song gets its own year and media type back, the album keeps the new ones
+        let undone = store.undo().await.unwrap().unwrap();
+        assert!(matches!(undone, Change::Edited { .. }));
+        let song = store.get_song(id).await.unwrap().unwrap();
+        assert_eq!(
+            (song.release_year, song.media_type.as_str()),
+            (1965, "Vinyl")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let store = test_store().await.with_user("jack");
+        let id = store
+            .create_song(Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"))
+            .await
+            .unwrap();
+        let edited = Song::new(0, "Yesterday", "The Beatles", "Help!", 1965, "Vinyl");
+        store.update_song(id, edited).await.unwrap();
+        let artist_id = store.get_song(id).await.unwrap().unwrap().artist_id;
+        store
+            .rename_artist(artist_id, "Beatles".to_owned())
+            .await
+            .unwrap();
+        store.undo().await.unwrap();
+
+        // newest first: the undone edit, the rename, the edit and the song being added
+        let history = store.get_song_history(id).await.unwrap();
+        assert_eq!(history.len(), 4);
+        assert!(history.iter().all(|entry| entry.changed_by == "jack"));
+        let fields = |entry: &AuditEntry| {
+            entry
+                .field_changes()
+                .into_iter()
+                .map(|change| (change.action, change.field, change.before, change.after))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            fields(&history[0]),
+            vec![(
+                "edited",
+                "title",
+                "Yesterday".to_owned(),
+                "Help!".to_owned()
+            )]
+        );
+        assert_eq!(
+            fields(&history[1]),
+            vec![(
+                "edited",
+                "artist",
+                "The Beatles".to_owned(),
+                "Beatles".to_owned()
+            )]
+        );
+        assert_eq!(fields(&history[3]).len(), 5);
+
+        // undoing doesn't take anything out of the log, and deleting an album logs its songs
+        let album_id = store.get_song(id).await.unwrap().unwrap().album_id;
+        store.delete_album(album_id).await.unwrap();
+        let history = store.get_song_history(id).await.unwrap();
+        assert_eq!(fields(&history[0])[0].0, "deleted");
+
+        let today = &history[0].changed_at[..10];
+        let changes = store
+            .get_changes(&format!("{today} 00:00:00"), &format!("{today} 23:59:59"))
+            .await
+            .unwrap();
+        assert_eq!(changes.len(), 5);
+        assert!(store
+            .get_changes("2000-01-01 00:00:00", "2000-12-31 23:59:59")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_trash() {
+        let store = test_store().await;
+        let id = store
+            .create_song(Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"))
+            .await
+            .unwrap();
+        store
+            .create_song(Song::new(
+                0,
+                "Yesterday",
+                "The Beatles",
+                "Help!",
+                1965,
+                "Vinyl",
+            ))
+            .await
+            .unwrap();
+        store.delete_song(id).await.unwrap();
+
+        // a song in the trash is left out of everything else
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 1);
+        assert!(store.get_song(id).await.unwrap().is_none());
+        assert_eq!(
+            store
+                .count_songs(&Query::parse("title:help").unwrap())
+                .await
+                .unwrap(),
+            0
+        );
+        assert_eq!(store.get_all_albums().await.unwrap()[0].song_count, 1);
+        assert_eq!(store.update_song(id, Song::default()).await.unwrap(), 0);
+        let trash = store.get_trashed_songs().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].song.title, "Help!");
+
+        // restoring can be undone, which puts it back in the trash
+        assert_eq!(store.restore_song(id).await.unwrap(), 1);
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 2);
+        store.undo().await.unwrap();
+        assert_eq!(store.get_trashed_songs().await.unwrap().len(), 1);
+
+        // purging is for good, and only purges songs in the trash
+        let other = store.get_all_songs().await.unwrap()[0].id;
+        assert_eq!(store.purge_song(other).await.unwrap(), 0);
+        assert_eq!(store.purge_trash("2000-01-01 00:00:00").await.unwrap(), 0);
+        assert_eq!(store.purge_trash("9999-12-31 23:59:59").await.unwrap(), 1);
+        assert!(store.get_trashed_songs().await.unwrap().is_empty());
+        assert_eq!(store.restore_song(id).await.unwrap(), 0);
+        // and still leaves a deletion in the song's history
+        let history = store.get_song_history(id).await.unwrap();
+        assert_eq!(history.len(), 5);
+        assert!(matches!(
+            history[0].entry.change(),
+            Some(Change::Deleted(_))
+        ));
+
+        store.delete_song(other).await.unwrap();
+        assert_eq!(store.purge_song(other).await.unwrap(), 1);
+        let history = store.get_song_history(other).await.unwrap();
+        assert!(matches!(
+            history[0].entry.change(),
+            Some(Change::Deleted(_))
+        ));
+        assert!(matches!(
+            history[1].entry.change(),
+            Some(Change::Deleted(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_bulk_edit_and_delete() {
+        let store = test_store().await;
+        let mut ids = Vec::new();
+        for title in ["War Pigs", "Iron Man", "Paranoid"] {
+            let song = Song::new(0, title, "Black Sabbath", "Paranoid", 1970, "CD");
+            ids.push(store.create_song(song).await.unwrap());
+        }
+
+        // only the fields given change, and songs that already have them aren't touched
+        let fields = SongFields {
+            media_type: Some("Vinyl".to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(store.update_songs(&ids[..2], &fields).await.unwrap(), 2);
+        assert_eq!(store.update_songs(&ids[..2], &fields).await.unwrap(), 0);
+        let songs = store.get_all_songs().await.unwrap();
+        let media_types: Vec<&str> = songs.iter().map(|song| song.media_type.as_str()).collect();
+        assert_eq!(media_types, ["Vinyl", "Vinyl", "CD"]);
+        assert!(songs
+            .iter()
+            .all(|song| song.album == "Paranoid" && song.release_year == 1970));
+
+        // each song is journaled on its own
+        store.undo().await.unwrap();
+        assert_eq!(
+            store.get_song(ids[1]).await.unwrap().unwrap().media_type,
+            "CD"
+        );
+
+        assert_eq!(store.delete_songs(&ids).await.unwrap(), 3);
+        assert!(store.get_all_songs().await.unwrap().is_empty());
+        assert_eq!(store.get_trashed_songs().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_add_songs_is_all_or_nothing() {
+        let store = test_store().await;
+        let songs = vec![
+            Song::new(0, "War Pigs", "Black Sabbath", "Paranoid", 1970, "Vinyl"),
+            Song::new(0, "Iron Man", "Black Sabbath", "Paranoid", 1970, "Vinyl"),
+        ];
+        assert_eq!(store.add_songs(songs.clone()).await.unwrap(), 2);
+        assert_eq!(store.get_all_albums().await.unwrap().len(), 1);
+
+        // make the last insert of the next batch fail
+        sqlx::query(
+            "CREATE TRIGGER fail_insert BEFORE INSERT ON Songs WHEN NEW.title = 'Fail' \
+             BEGIN SELECT RAISE(ABORT, 'failed on purpose'); END",
+        )
+        .execute(&store.pool)
+        .await
+        .unwrap();
+        let songs = vec![
+            Song::new(0, "Solo", "Future", "HNDRXX", 2017, "Digital Download"),
+            Song::new(0, "Fail", "Future", "HNDRXX", 2017, "Digital Download"),
+        ];
+        assert!(store.add_songs(songs).await.is_err());
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 2);
+        assert_eq!(store.get_all_artists().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_playlists() {
+        let store = test_store().await;
+        for title in ["War Pigs", "Iron Man", "Paranoid"] {
+            let song = Song::new(0, title, "Black Sabbath", "Paranoid", 1970, "Vinyl");
+            store.add_song(song).await.unwrap();
+        }
+        let songs = store.get_all_songs().await.unwrap();
+        store
+            .add_playlist(Playlist::new(0, "Road Trip", "for the car"))
+            .await
+            .unwrap();
+        let playlist_id = store.get_all_playlists().await.unwrap()[0].id;
+        for song in &songs {
+            store
+                .add_song_to_playlist(playlist_id, song.id)
+                .await
+                .unwrap();
+        }
+        // the same song can be on a playlist twice
+        store
+            .add_song_to_playlist(playlist_id, songs[0].id)
+            .await
+            .unwrap();
+
+        let titles = |entries: Vec<PlaylistEntry>| -> Vec<String> {
+            entries.into_iter().map(|entry| entry.song.title).collect()
+        };
+        let entries = store.get_playlist_entries(playlist_id).await.unwrap();
+        assert_eq!(
+            titles(entries.clone()),
+            ["War Pigs", "Iron Man", "Paranoid", "War Pigs"]
+        );
+
+        store
+            .swap_playlist_entries(entries[0].entry_id, entries[1].entry_id)
+            .await
+            .unwrap();
+        store
+            .remove_playlist_entry(entries[3].entry_id)
+            .await
+            .unwrap();
+        let entries = store.get_playlist_entries(playlist_id).await.unwrap();
+        assert_eq!(titles(entries), ["Iron Man", "War Pigs", "Paranoid"]);
+
+        // deleting a song takes it off the playlist instead of leaving a dangling entry
+        store.delete_song(songs[1].id).await.unwrap();
+        let entries = store.get_playlist_entries(playlist_id).await.unwrap();
+        assert_eq!(titles(entries), ["War Pigs", "Paranoid"]);
+        assert_eq!(store.get_all_playlists().await.unwrap()[0].song_count, 2);
+
+        let renamed = Playlist::new(0, "Long Drive", "");
+        store.update_playlist(playlist_id, renamed).await.unwrap();
+        assert_eq!(
+            store.get_all_playlists().await.unwrap()[0].name,
+            "Long Drive"
+        );
+
+        // deleting the playlist leaves the songs alone
+        assert_eq!(store.delete_playlist(playlist_id).await.unwrap(), 1);
+        assert!(store.get_all_playlists().await.unwrap().is_empty());
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 2);
+    }
+}
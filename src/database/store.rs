@@ -0,0 +1,1160 @@
+// The LibraryStore both backends share. The SQL is written so it runs on MariaDB and SQLite alike,
+// and the few places where they differ are left to the Backend trait, see mariadb.rs and sqlite.rs.
+
+use super::{
+    LibraryStore, PageCursor, PageValue, COUNT_SONGS, INSERT_AUDIT, INSERT_JOURNAL, SELECT_ALBUMS,
+    SELECT_ARTISTS, SELECT_AUDIT, SELECT_JOURNAL, SELECT_MEDIA_TYPES, SELECT_PLAYLISTS,
+    SELECT_PLAYLIST_ENTRIES, SELECT_SONGS, SELECT_TRASH, SELECT_TRASHED_SONGS,
+};
+use crate::{
+    album::Album,
+    artist::Artist,
+    audit::{self, AuditEntry},
+    journal::{Change, JournalEntry},
+    media_type::MediaType,
+    playlist::{Playlist, PlaylistEntry},
+    query::{Query, SqlValue},
+    scan::ScannedFile,
+    song::{Song, SongFields, TrashedSong},
+    sort::{Sort, RELEVANCE},
+    validate,
+};
+use async_trait::async_trait;
+use sqlx::{
+    database::HasArguments, ColumnIndex, Database, Decode, Encode, Executor, FromRow,
+    IntoArguments, Pool, Transaction, Type,
+};
+use std::collections::BTreeSet;
+
+// what one database does its own way
+pub trait Backend: Database {
+    // upserts the ScannedFiles row of a path, binding path, song_id, modified and size
+    const UPSERT_SCANNED_FILE: &'static str;
+
+    // the id an INSERT gave the row it added
+    fn last_insert_id(result: &Self::QueryResult) -> u32;
+
+    // how many rows a statement changed
+    fn rows_affected(result: &Self::QueryResult) -> u64;
+
+    // a query as an SQL condition with the values to bind, and the words to rank songs by
+    // if the backend can rank them by relevance, see Sort::order_by
+    fn condition(query: &Query) -> (String, Vec<SqlValue>, Option<String>);
+}
+
+// a library in a database that has a Backend, see SqliteStore and MariaDbStore
+#[derive(Debug)]
+pub struct SqlStore<DB: Backend> {
+    pub(super) pool: Pool<DB>, // database connection pool
+    user: String,              // the name changes are recorded under in the audit log
+}
+
+impl<DB: Backend> SqlStore<DB> {
+    // a store on a pool whose schema is up to date, recording changes under the OS user's name
+    pub(super) fn new(pool: Pool<DB>) -> Self {
+        Self {
+            pool,
+            user: audit::os_user(),
+        }
+    }
+
+    // records the changes made through this store under a name other than the OS user's
+    pub fn with_user(self, user: &str) -> Self {
+        Self {
+            user: user.to_owned(),
+            ..self
+        }
+    }
+}
+
+// The bounds are what the queries need from a backend: its connections run them, and it can bind
+// and read every type they use. Both MariaDB and SQLite have all of it.
+#[async_trait]
+impl<DB> LibraryStore for SqlStore<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    usize: ColumnIndex<DB::Row>,
+    for<'q> bool: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> u32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<i32>: Encode<'q, DB>,
+    for<'q> Option<u32>: Encode<'q, DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    for<'q> Option<&'q str>: Encode<'q, DB>,
+    for<'r> Song: FromRow<'r, DB::Row>,
+    for<'r> TrashedSong: FromRow<'r, DB::Row>,
+    for<'r> Album: FromRow<'r, DB::Row>,
+    for<'r> Artist: FromRow<'r, DB::Row>,
+    for<'r> MediaType: FromRow<'r, DB::Row>,
+    for<'r> Playlist: FromRow<'r, DB::Row>,
+    for<'r> PlaylistEntry: FromRow<'r, DB::Row>,
+    for<'r> JournalEntry: FromRow<'r, DB::Row>,
+    for<'r> AuditEntry: FromRow<'r, DB::Row>,
+    for<'r> ScannedFile: FromRow<'r, DB::Row>,
+{
+    async fn create_song(&self, new_song: Song) -> Result<u32, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let new_song = Self::resolve_media_type_of(&mut tx, new_song).await?;
+        let id = Self::insert_song(&mut tx, &new_song, None).await?;
+        let change = Change::Added(Song { id, ..new_song });
+        Self::record_change(&mut tx, &change).await?;
+        Self::audit(&mut tx, &self.user, &change).await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    async fn add_songs(&self, new_songs: Vec<Song>) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut added = 0;
+        for new_song in new_songs {
+            let new_song = Self::resolve_media_type_of(&mut tx, new_song).await?;
+            let id = Self::insert_song(&mut tx, &new_song, None).await?;
+            let change = Change::Added(Song { id, ..new_song });
+            Self::record_change(&mut tx, &change).await?;
+            Self::audit(&mut tx, &self.user, &change).await?;
+            added += 1;
+        }
+        // dropping the transaction without committing rolls back everything on an error above
+        tx.commit().await?;
+        Ok(added)
+    }
+
+    async fn get_song(&self, song_id: u32) -> Result<Option<Song>, sqlx::Error> {
+        let song = sqlx::query_as(&format!("{SELECT_SONGS} AND s.id = ?"))
+            .bind(song_id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(song)
+    }
+
+    // songs are listed most relevant first if the backend ranks the query's words
+    async fn search_songs(&self, query: &Query) -> Result<Vec<Song>, sqlx::Error> {
+        let (condition, values, relevance) = DB::condition(query);
+        let sql = match relevance {
+            Some(_) => format!("{SELECT_SONGS} AND ({condition}) ORDER BY {RELEVANCE} DESC"),
+            None => format!("{SELECT_SONGS} AND ({condition})"),
+        };
+        let mut songs = sqlx::query_as(&sql);
+        for value in values {
+            songs = match value {
+                SqlValue::Text(text) => songs.bind(text),
+                SqlValue::Integer(number) => songs.bind(number),
+            };
+        }
+        if let Some(relevance) = relevance {
+            songs = songs.bind(relevance);
+        }
+        songs.fetch_all(&self.pool).await
+    }
+
+    async fn get_songs_page(
+        &self,
+        query: &Query,
+        sort: &Sort,
+        cursor: PageCursor,
+        limit: u32,
+    ) -> Result<Vec<Song>, sqlx::Error> {
+        let (condition, values, relevance) = DB::condition(query);
+        let (keyset, order, page_values) = cursor.clauses(sort, relevance.as_deref());
+        let sql = format!("{SELECT_SONGS} AND ({condition}){keyset} ORDER BY {order} LIMIT ?");
+        let mut songs = sqlx::query_as(&sql);
+        for value in values {
+            songs = match value {
+                SqlValue::Text(text) => songs.bind(text),
+                SqlValue::Integer(number) => songs.bind(number),
+            };
+        }
+        for value in page_values {
+            songs = match value {
+                PageValue::Id(id) => songs.bind(id),
+                PageValue::Words(words) => songs.bind(words),
+            };
+        }
+        let mut songs: Vec<Song> = songs.bind(limit).fetch_all(&self.pool).await?;
+        if cursor.is_backwards() {
+            songs.reverse();
+        }
+        Ok(songs)
+    }
+
+    async fn count_songs(&self, query: &Query) -> Result<u64, sqlx::Error> {
+        let (condition, values, _) = DB::condition(query);
+        let sql = format!("{COUNT_SONGS} AND ({condition})");
+        let mut count = sqlx::query_scalar(&sql);
+        for value in values {
+            count = match value {
+                SqlValue::Text(text) => count.bind(text),
+                SqlValue::Integer(number) => count.bind(number),
+            };
+        }
+        let count: i64 = count.fetch_one(&self.pool).await?;
+        Ok(count as u64)
+    }
+
+    async fn get_all_songs(&self) -> Result<Vec<Song>, sqlx::Error> {
+        let songs = sqlx::query_as(SELECT_SONGS).fetch_all(&self.pool).await?;
+        Ok(songs)
+    }
+
+    async fn update_song(&self, song_id: u32, song_fields: Song) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(before) = Self::fetch_song(&mut tx, song_id).await? else {
+            return Ok(0);
+        };
+        let after = Song {
+            id: song_id,
+            ..song_fields
+        };
+        let after = Self::resolve_media_type_of(&mut tx, after).await?;
+        let updated = Self::write_song(&mut tx, &after).await?;
+        let change = Change::Edited { before, after };
+        Self::record_change(&mut tx, &change).await?;
+        Self::audit(&mut tx, &self.user, &change).await?;
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn delete_song(&self, song_id: u32) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let Some(song) = Self::fetch_song(&mut tx, song_id).await? else {
+            return Ok(0);
+        };
+        let deleted = Self::trash(&mut tx, &self.user, song, &audit::timestamp_now()).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn update_songs(
+        &self,
+        song_ids: &[u32],
+        fields: &SongFields,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut updated = 0;
+        for &song_id in song_ids {
+            let Some(before) = Self::fetch_song(&mut tx, song_id).await? else {
+                continue;
+            };
+            let after =
+                Self::resolve_media_type_of(&mut tx, fields.apply_to(before.clone())).await?;
+            // a song that already has the values has nothing to undo
+            if !before.differs_from(&after) {
+                continue;
+            }
+            updated += Self::write_song(&mut tx, &after).await?;
+            let change = Change::Edited { before, after };
+            Self::record_change(&mut tx, &change).await?;
+            Self::audit(&mut tx, &self.user, &change).await?;
+        }
+        tx.commit().await?;
+        Ok(updated)
+    }
+
+    async fn delete_songs(&self, song_ids: &[u32]) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let deleted_at = audit::timestamp_now();
+        let mut deleted = 0;
+        for &song_id in song_ids {
+            let Some(song) = Self::fetch_song(&mut tx, song_id).await? else {
+                continue;
+            };
+            deleted += Self::trash(&mut tx, &self.user, song, &deleted_at).await?;
+        }
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn get_trashed_songs(&self) -> Result<Vec<TrashedSong>, sqlx::Error> {
+        let songs = sqlx::query_as(SELECT_TRASH).fetch_all(&self.pool).await?;
+        Ok(songs)
+    }
+
+    async fn restore_song(&self, song_id: u32) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let restored = Self::restore(&mut tx, song_id).await?;
+        let Some(song) = Self::fetch_song(&mut tx, song_id).await? else {
+            return Ok(0);
+        };
+        if restored > 0 {
+            let change = Change::Added(song);
+            Self::record_change(&mut tx, &change).await?;
+            Self::audit(&mut tx, &self.user, &change).await?;
+        }
+        tx.commit().await?;
+        Ok(restored)
+    }
+
+    async fn purge_song(&self, song_id: u32) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let songs: Vec<Song> = sqlx::query_as(&format!("{SELECT_TRASHED_SONGS} AND s.id = ?"))
+            .bind(song_id)
+            .fetch_all(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM Songs WHERE id = ? AND deleted_at IS NOT NULL")
+            .bind(song_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::delete_emptied_albums(&mut tx, &songs).await?;
+        Self::audit_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn purge_trash(&self, before: &str) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let songs: Vec<Song> =
+            sqlx::query_as(&format!("{SELECT_TRASHED_SONGS} AND s.deleted_at < ?"))
+                .bind(before)
+                .fetch_all(&mut *tx)
+                .await?;
+        let result = sqlx::query("DELETE FROM Songs WHERE deleted_at < ?")
+            .bind(before)
+            .execute(&mut *tx)
+            .await?;
+        Self::delete_emptied_albums(&mut tx, &songs).await?;
+        Self::audit_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn undo(&self) -> Result<Option<Change>, sqlx::Error> {
+        self.replay(true).await
+    }
+
+    async fn redo(&self) -> Result<Option<Change>, sqlx::Error> {
+        self.replay(false).await
+    }
+
+    async fn get_song_history(&self, song_id: u32) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let entries = sqlx::query_as(&format!(
+            "{SELECT_AUDIT} WHERE song_id = ? ORDER BY id DESC"
+        ))
+        .bind(song_id)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    async fn get_changes(&self, from: &str, to: &str) -> Result<Vec<AuditEntry>, sqlx::Error> {
+        let entries = sqlx::query_as(&format!(
+            "{SELECT_AUDIT} WHERE changed_at BETWEEN ? AND ? ORDER BY id"
+        ))
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+        Ok(entries)
+    }
+
+    async fn add_album(&self, new_album: Album) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let artist_id = Self::resolve_artist(&mut tx, &new_album.artist).await?;
+        let media_type = Self::resolve_media_type(&mut tx, &new_album.media_type).await?;
+        let result = sqlx::query(
+            "INSERT INTO Albums (title, artist_id, release_year, media_type) VALUES (?, ?, ?, ?)",
+        )
+        .bind(new_album.title.trim())
+        .bind(artist_id)
+        .bind(new_album.release_year)
+        .bind(media_type)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn get_all_albums(&self) -> Result<Vec<Album>, sqlx::Error> {
+        let albums = sqlx::query_as(SELECT_ALBUMS).fetch_all(&self.pool).await?;
+        Ok(albums)
+    }
+
+    async fn update_album(
+        &self,
+        album_id: u32,
+        album_fields: Album,
+        update_songs: bool,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // the album's title and artist are every song's on it, so those change too
+        let songs = Self::fetch_songs_where(&mut tx, "s.album_id = ?", album_id).await?;
+        let artist_id = Self::resolve_artist(&mut tx, &album_fields.artist).await?;
+        let media_type = Self::resolve_media_type(&mut tx, &album_fields.media_type).await?;
+        let result = sqlx::query(
+            "UPDATE Albums SET title = ?, artist_id = ?, release_year = ?, media_type = ? WHERE id = ?",
+        )
+        .bind(album_fields.title.trim())
+        .bind(artist_id)
+        .bind(album_fields.release_year)
+        .bind(&media_type)
+        .bind(album_id)
+        .execute(&mut *tx)
+        .await?;
+        if update_songs {
+            sqlx::query("UPDATE Songs SET release_year = ?, media_type = ? WHERE album_id = ?")
+                .bind(album_fields.release_year)
+                .bind(&media_type)
+                .bind(album_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        Self::journal_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn delete_album(&self, album_id: u32) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // the songs go to the trash like any deleted song, so they can be restored or undone
+        let deleted_at = audit::timestamp_now();
+        for song in Self::fetch_songs_where(&mut tx, "s.album_id = ?", album_id).await? {
+            Self::trash(&mut tx, &self.user, song, &deleted_at).await?;
+        }
+        // songs in the trash still point at the album, so it stays until they've been purged
+        let deleted = Self::delete_album_if_empty(&mut tx, album_id).await?;
+        tx.commit().await?;
+        Ok(deleted)
+    }
+
+    async fn get_all_artists(&self) -> Result<Vec<Artist>, sqlx::Error> {
+        let artists = sqlx::query_as(SELECT_ARTISTS).fetch_all(&self.pool).await?;
+        Ok(artists)
+    }
+
+    async fn get_media_types(&self) -> Result<Vec<MediaType>, sqlx::Error> {
+        let mut media_types: Vec<MediaType> = sqlx::query_as(SELECT_MEDIA_TYPES)
+            .fetch_all(&self.pool)
+            .await?;
+        let aliases: Vec<(u32, String)> =
+            sqlx::query_as("SELECT media_type_id, alias FROM MediaTypeAliases ORDER BY alias")
+                .fetch_all(&self.pool)
+                .await?;
+        for (media_type_id, alias) in aliases {
+            if let Some(media_type) = media_types
+                .iter_mut()
+                .find(|media_type| media_type.id == media_type_id)
+            {
+                media_type.aliases.push(alias);
+            }
+        }
+        Ok(media_types)
+    }
+
+    async fn add_media_type(&self, new_media_type: MediaType) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let name = new_media_type.name.trim();
+        sqlx::query("DELETE FROM MediaTypeAliases WHERE LOWER(alias) = LOWER(?)")
+            .bind(name)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query(
+            "INSERT INTO MediaTypes (name, format, speed, size, bitrate, codec) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(name)
+        .bind(&new_media_type.format)
+        .bind(&new_media_type.speed)
+        .bind(&new_media_type.size)
+        .bind(&new_media_type.bitrate)
+        .bind(&new_media_type.codec)
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn map_media_type(
+        &self,
+        from: &str,
+        into_media_type_id: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let from = from.trim();
+        if from.is_empty() {
+            return Ok(0);
+        }
+        let mut tx = self.pool.begin().await?;
+        let into: String = sqlx::query_scalar("SELECT name FROM MediaTypes WHERE id = ?")
+            .bind(into_media_type_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let songs: Vec<Song> = sqlx::query_as(&format!(
+            "{SELECT_SONGS} AND LOWER(TRIM(s.media_type)) = LOWER(?)"
+        ))
+        .bind(from)
+        .fetch_all(&mut *tx)
+        .await?;
+        // songs in the trash are filed under the type too, so restoring one doesn't bring the old value back
+        for table in ["Songs", "Albums"] {
+            sqlx::query(&format!(
+                "UPDATE {table} SET media_type = ? WHERE LOWER(TRIM(media_type)) = LOWER(?)"
+            ))
+            .bind(&into)
+            .bind(from)
+            .execute(&mut *tx)
+            .await?;
+        }
+        // a type on the list by that name goes, and its aliases move along with its songs
+        let from_id: Option<u32> = sqlx::query_scalar(
+            "SELECT id FROM MediaTypes WHERE LOWER(name) = LOWER(?) AND id <> ?",
+        )
+        .bind(from)
+        .bind(into_media_type_id)
+        .fetch_optional(&mut *tx)
+        .await?;
+        if let Some(from_id) = from_id {
+            sqlx::query("UPDATE MediaTypeAliases SET media_type_id = ? WHERE media_type_id = ?")
+                .bind(into_media_type_id)
+                .bind(from_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM MediaTypes WHERE id = ?")
+                .bind(from_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        // the value is kept as an alias, so songs saved with it later are filed the same way
+        if from.to_lowercase() != into.to_lowercase() {
+            sqlx::query("DELETE FROM MediaTypeAliases WHERE LOWER(alias) = LOWER(?)")
+                .bind(from)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("INSERT INTO MediaTypeAliases (alias, media_type_id) VALUES (?, ?)")
+                .bind(from)
+                .bind(into_media_type_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        let changed = songs.iter().filter(|song| song.media_type != into).count();
+        Self::audit_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(changed as u64)
+    }
+
+    async fn rename_artist(&self, artist_id: u32, new_name: String) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let songs = Self::fetch_songs_where(&mut tx, "s.artist_id = ?", artist_id).await?;
+        let result = sqlx::query("UPDATE Artists SET name = ? WHERE id = ?")
+            .bind(new_name.trim())
+            .bind(artist_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::audit_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn merge_artists(
+        &self,
+        from_artist_id: u32,
+        into_artist_id: u32,
+    ) -> Result<u64, sqlx::Error> {
+        if from_artist_id == into_artist_id {
+            return Ok(0);
+        }
+        let mut tx = self.pool.begin().await?;
+        let songs = Self::fetch_songs_where(&mut tx, "s.artist_id = ?", from_artist_id).await?;
+        // albums both artists have: the songs move to the target's copy and the duplicate goes away
+        let duplicate_albums: Vec<(u32, u32)> = sqlx::query_as(
+            "SELECT a.id, b.id FROM Albums a JOIN Albums b ON LOWER(a.title) = LOWER(b.title) \
+            WHERE a.artist_id = ? AND b.artist_id = ?",
+        )
+        .bind(from_artist_id)
+        .bind(into_artist_id)
+        .fetch_all(&mut *tx)
+        .await?;
+        for (from_album_id, into_album_id) in duplicate_albums {
+            sqlx::query("UPDATE Songs SET album_id = ? WHERE album_id = ?")
+                .bind(into_album_id)
+                .bind(from_album_id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM Albums WHERE id = ?")
+                .bind(from_album_id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        // every other album and song just changes hands
+        sqlx::query("UPDATE Albums SET artist_id = ? WHERE artist_id = ?")
+            .bind(into_artist_id)
+            .bind(from_artist_id)
+            .execute(&mut *tx)
+            .await?;
+        let moved = sqlx::query("UPDATE Songs SET artist_id = ? WHERE artist_id = ?")
+            .bind(into_artist_id)
+            .bind(from_artist_id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query("DELETE FROM Artists WHERE id = ?")
+            .bind(from_artist_id)
+            .execute(&mut *tx)
+            .await?;
+        Self::audit_songs(&mut tx, &self.user, songs).await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&moved))
+    }
+
+    async fn add_playlist(&self, new_playlist: Playlist) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("INSERT INTO Playlists (name, description) VALUES (?, ?)")
+            .bind(new_playlist.name.trim())
+            .bind(new_playlist.description.trim())
+            .execute(&self.pool)
+            .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn get_all_playlists(&self) -> Result<Vec<Playlist>, sqlx::Error> {
+        let playlists = sqlx::query_as(SELECT_PLAYLISTS)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(playlists)
+    }
+
+    async fn update_playlist(
+        &self,
+        playlist_id: u32,
+        playlist_fields: Playlist,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE Playlists SET name = ?, description = ? WHERE id = ?")
+            .bind(playlist_fields.name.trim())
+            .bind(playlist_fields.description.trim())
+            .bind(playlist_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn delete_playlist(&self, playlist_id: u32) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        // entries point at the playlist, so they go first
+        sqlx::query("DELETE FROM PlaylistSongs WHERE playlist_id = ?")
+            .bind(playlist_id)
+            .execute(&mut *tx)
+            .await?;
+        let result = sqlx::query("DELETE FROM Playlists WHERE id = ?")
+            .bind(playlist_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn get_playlist_entries(
+        &self,
+        playlist_id: u32,
+    ) -> Result<Vec<PlaylistEntry>, sqlx::Error> {
+        let entries = sqlx::query_as(SELECT_PLAYLIST_ENTRIES)
+            .bind(playlist_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(entries)
+    }
+
+    async fn add_song_to_playlist(
+        &self,
+        playlist_id: u32,
+        song_id: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let last_position: Option<i32> =
+            sqlx::query_scalar("SELECT MAX(position) FROM PlaylistSongs WHERE playlist_id = ?")
+                .bind(playlist_id)
+                .fetch_one(&mut *tx)
+                .await?;
+        let result = sqlx::query(
+            "INSERT INTO PlaylistSongs (playlist_id, song_id, position) VALUES (?, ?, ?)",
+        )
+        .bind(playlist_id)
+        .bind(song_id)
+        .bind(last_position.map_or(0, |position| position + 1))
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn swap_playlist_entries(
+        &self,
+        first_entry_id: u32,
+        second_entry_id: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let position = "SELECT position FROM PlaylistSongs WHERE id = ?";
+        let first: i32 = sqlx::query_scalar(position)
+            .bind(first_entry_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let second: i32 = sqlx::query_scalar(position)
+            .bind(second_entry_id)
+            .fetch_one(&mut *tx)
+            .await?;
+        let mut rows_affected = 0;
+        for (entry_id, new_position) in [(first_entry_id, second), (second_entry_id, first)] {
+            let result = sqlx::query("UPDATE PlaylistSongs SET position = ? WHERE id = ?")
+                .bind(new_position)
+                .bind(entry_id)
+                .execute(&mut *tx)
+                .await?;
+            rows_affected += DB::rows_affected(&result);
+        }
+        tx.commit().await?;
+        Ok(rows_affected)
+    }
+
+    async fn remove_playlist_entry(&self, entry_id: u32) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM PlaylistSongs WHERE id = ?")
+            .bind(entry_id)
+            .execute(&self.pool)
+            .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn get_scanned_files(&self) -> Result<Vec<ScannedFile>, sqlx::Error> {
+        let files = sqlx::query_as("SELECT path, song_id, modified, size FROM ScannedFiles")
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(files)
+    }
+
+    async fn save_scanned_files(
+        &self,
+        files: Vec<(ScannedFile, Song)>,
+    ) -> Result<u64, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut saved = 0;
+        let mut updated = Vec::new();
+        for (file, song) in files {
+            let song = Self::resolve_media_type_of(&mut tx, song).await?;
+            let artist_id = Self::resolve_artist(&mut tx, &song.artist).await?;
+            let album_id = Self::resolve_album(&mut tx, &song, artist_id).await?;
+            let song_id = match file.song_id {
+                Some(song_id) => {
+                    updated.extend(Self::fetch_song(&mut tx, song_id).await?);
+                    sqlx::query(
+                        "UPDATE Songs SET title = ?, artist_id = ?, album_id = ?, release_year = ?, media_type = ? WHERE id = ?",
+                    )
+                    .bind(&song.title)
+                    .bind(artist_id)
+                    .bind(album_id)
+                    .bind(song.release_year)
+                    .bind(&song.media_type)
+                    .bind(song_id)
+                    .execute(&mut *tx)
+                    .await?;
+                    song_id
+                }
+                None => {
+                    let id = Self::insert_song(&mut tx, &song, None).await?;
+                    let change = Change::Added(Song { id, ..song });
+                    Self::record_change(&mut tx, &change).await?;
+                    Self::audit(&mut tx, &self.user, &change).await?;
+                    id
+                }
+            };
+            sqlx::query(DB::UPSERT_SCANNED_FILE)
+                .bind(&file.path)
+                .bind(song_id)
+                .bind(file.modified)
+                .bind(file.size)
+                .execute(&mut *tx)
+                .await?;
+            saved += 1;
+        }
+        Self::journal_songs(&mut tx, &self.user, updated).await?;
+        tx.commit().await?;
+        Ok(saved)
+    }
+}
+
+// the steps the LibraryStore methods are made of, run inside their transactions.
+// The bounds are the same as LibraryStore's above.
+impl<DB> SqlStore<DB>
+where
+    DB: Backend,
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> <DB as HasArguments<'q>>::Arguments: IntoArguments<'q, DB>,
+    usize: ColumnIndex<DB::Row>,
+    for<'q> bool: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> u32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<i32>: Encode<'q, DB>,
+    for<'q> Option<u32>: Encode<'q, DB>,
+    for<'q> Option<String>: Encode<'q, DB>,
+    for<'q> Option<&'q str>: Encode<'q, DB>,
+    for<'r> Song: FromRow<'r, DB::Row>,
+    for<'r> TrashedSong: FromRow<'r, DB::Row>,
+    for<'r> Album: FromRow<'r, DB::Row>,
+    for<'r> Artist: FromRow<'r, DB::Row>,
+    for<'r> MediaType: FromRow<'r, DB::Row>,
+    for<'r> Playlist: FromRow<'r, DB::Row>,
+    for<'r> PlaylistEntry: FromRow<'r, DB::Row>,
+    for<'r> JournalEntry: FromRow<'r, DB::Row>,
+    for<'r> AuditEntry: FromRow<'r, DB::Row>,
+    for<'r> ScannedFile: FromRow<'r, DB::Row>,
+{
+    // deletes an album if no song points at it, trash included. Returns the number of rows affected.
+    async fn delete_album_if_empty(
+        tx: &mut Transaction<'_, DB>,
+        album_id: u32,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "DELETE FROM Albums WHERE id = ? AND NOT EXISTS (SELECT 1 FROM Songs WHERE album_id = ?)",
+        )
+        .bind(album_id)
+        .bind(album_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    // deletes the albums of purged songs that have no songs left, like the ones delete_album kept
+    async fn delete_emptied_albums(
+        tx: &mut Transaction<'_, DB>,
+        purged: &[Song],
+    ) -> Result<(), sqlx::Error> {
+        let album_ids: BTreeSet<u32> = purged.iter().map(|song| song.album_id).collect();
+        for album_id in album_ids {
+            Self::delete_album_if_empty(tx, album_id).await?;
+        }
+        Ok(())
+    }
+
+    // adds a song, with the given id if it has one, and returns its id
+    async fn insert_song(
+        tx: &mut Transaction<'_, DB>,
+        song: &Song,
+        id: Option<u32>,
+    ) -> Result<u32, sqlx::Error> {
+        let artist_id = Self::resolve_artist(tx, &song.artist).await?;
+        let album_id = Self::resolve_album(tx, song, artist_id).await?;
+        let result = sqlx::query(
+            "INSERT INTO Songs (id, title, artist_id, album_id, release_year, media_type) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(id)
+        .bind(&song.title)
+        .bind(artist_id)
+        .bind(album_id)
+        .bind(song.release_year)
+        .bind(&song.media_type)
+        .execute(&mut **tx)
+        .await?;
+        Ok(DB::last_insert_id(&result))
+    }
+
+    // gives the song with the song's id the song's values, returns the number of rows affected
+    async fn write_song(tx: &mut Transaction<'_, DB>, song: &Song) -> Result<u64, sqlx::Error> {
+        let artist_id = Self::resolve_artist(tx, &song.artist).await?;
+        let album_id = Self::resolve_album(tx, song, artist_id).await?;
+        let result = sqlx::query(
+            "UPDATE Songs SET title = ?, artist_id = ?, album_id = ?, release_year = ?, media_type = ? WHERE id = ?",
+        )
+        .bind(&song.title)
+        .bind(artist_id)
+        .bind(album_id)
+        .bind(song.release_year)
+        .bind(&song.media_type)
+        .bind(song.id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    // takes a song out of the trash, returns the number of rows affected
+    async fn restore(tx: &mut Transaction<'_, DB>, song_id: u32) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            "UPDATE Songs SET deleted_at = NULL WHERE id = ? AND deleted_at IS NOT NULL",
+        )
+        .bind(song_id)
+        .execute(&mut **tx)
+        .await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    async fn fetch_song(
+        tx: &mut Transaction<'_, DB>,
+        song_id: u32,
+    ) -> Result<Option<Song>, sqlx::Error> {
+        sqlx::query_as(&format!("{SELECT_SONGS} AND s.id = ?"))
+            .bind(song_id)
+            .fetch_optional(&mut **tx)
+            .await
+    }
+
+    // moves a song to the trash, journaled and audited like any other deletion
+    async fn trash(
+        tx: &mut Transaction<'_, DB>,
+        user: &str,
+        song: Song,
+        deleted_at: &str,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE Songs SET deleted_at = ? WHERE id = ?")
+            .bind(deleted_at)
+            .bind(song.id)
+            .execute(&mut **tx)
+            .await?;
+        let change = Change::Deleted(song);
+        Self::record_change(tx, &change).await?;
+        Self::audit(tx, user, &change).await?;
+        Ok(DB::rows_affected(&result))
+    }
+
+    // writes a change to the journal. The changes undone before it can't be redone after it, so they go.
+    async fn record_change(
+        tx: &mut Transaction<'_, DB>,
+        change: &Change,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM Journal WHERE undone = ?")
+            .bind(true)
+            .execute(&mut **tx)
+            .await?;
+        let (song_id, old, new) = change.columns();
+        sqlx::query(INSERT_JOURNAL)
+            .bind(song_id)
+            .bind(old.map(|song| song.title.as_str()))
+            .bind(old.map(|song| song.artist.as_str()))
+            .bind(old.map(|song| song.album.as_str()))
+            .bind(old.map(|song| song.release_year))
+            .bind(old.map(|song| song.media_type.as_str()))
+            .bind(new.map(|song| song.title.as_str()))
+            .bind(new.map(|song| song.artist.as_str()))
+            .bind(new.map(|song| song.album.as_str()))
+            .bind(new.map(|song| song.release_year))
+            .bind(new.map(|song| song.media_type.as_str()))
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    // writes a change to the audit log, under the user's name and the time now
+    async fn audit(
+        tx: &mut Transaction<'_, DB>,
+        user: &str,
+        change: &Change,
+    ) -> Result<(), sqlx::Error> {
+        let (song_id, old, new) = change.columns();
+        sqlx::query(INSERT_AUDIT)
+            .bind(song_id)
+            .bind(audit::timestamp_now())
+            .bind(user)
+            .bind(old.map(|song| song.title.as_str()))
+            .bind(old.map(|song| song.artist.as_str()))
+            .bind(old.map(|song| song.album.as_str()))
+            .bind(old.map(|song| song.release_year))
+            .bind(old.map(|song| song.media_type.as_str()))
+            .bind(new.map(|song| song.title.as_str()))
+            .bind(new.map(|song| song.artist.as_str()))
+            .bind(new.map(|song| song.album.as_str()))
+            .bind(new.map(|song| song.release_year))
+            .bind(new.map(|song| song.media_type.as_str()))
+            .execute(&mut **tx)
+            .await?;
+        Ok(())
+    }
+
+    // the songs an album or artist change is about to touch, so audit_songs can tell what it did to them
+    async fn fetch_songs_where(
+        tx: &mut Transaction<'_, DB>,
+        condition: &str,
+        id: u32,
+    ) -> Result<Vec<Song>, sqlx::Error> {
+        sqlx::query_as(&format!("{SELECT_SONGS} AND ({condition})"))
+            .bind(id)
+            .fetch_all(&mut **tx)
+            .await
+    }
+
+    // what happened to each song since it was fetched: edited if it looks different now,
+    // or deleted if it's gone
+    async fn changes_since(
+        tx: &mut Transaction<'_, DB>,
+        songs: Vec<Song>,
+    ) -> Result<Vec<Change>, sqlx::Error> {
+        let mut changes = Vec::new();
+        for before in songs {
+            changes.push(match Self::fetch_song(tx, before.id).await? {
+                // moving a song to another artist or album with the same name changes nothing a user sees
+                Some(after) if !before.differs_from(&after) => continue,
+                Some(after) => Change::Edited { before, after },
+                None => Change::Deleted(before),
+            });
+        }
+        Ok(changes)
+    }
+
+    // audits what happened to each song since it was fetched
+    async fn audit_songs(
+        tx: &mut Transaction<'_, DB>,
+        user: &str,
+        songs: Vec<Song>,
+    ) -> Result<(), sqlx::Error> {
+        for change in Self::changes_since(tx, songs).await? {
+            Self::audit(tx, user, &change).await?;
+        }
+        Ok(())
+    }
+
+    // journals and audits what happened to each song since it was fetched, so undo can take the
+    // songs back one at a time like an update_songs
+    async fn journal_songs(
+        tx: &mut Transaction<'_, DB>,
+        user: &str,
+        songs: Vec<Song>,
+    ) -> Result<(), sqlx::Error> {
+        for change in Self::changes_since(tx, songs).await? {
+            Self::record_change(tx, &change).await?;
+            Self::audit(tx, user, &change).await?;
+        }
+        Ok(())
+    }
+
+    // undoes the latest change that hasn't been undone, or redoes the one undone last.
+    // The change is made again without journaling it, and its row is marked, in one transaction.
+    // It's audited like any other change, if there was still something to change.
+    async fn replay(&self, undo: bool) -> Result<Option<Change>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let order = if undo { "DESC" } else { "ASC" };
+        let entry: Option<JournalEntry> = sqlx::query_as(&format!(
+            "{SELECT_JOURNAL} WHERE undone = ? ORDER BY id {order} LIMIT 1"
+        ))
+        .bind(!undo)
+        .fetch_optional(&mut *tx)
+        .await?;
+        let Some((entry_id, change)) = entry.and_then(|entry| Some((entry.id, entry.change()?)))
+        else {
+            return Ok(None);
+        };
+        let replayed = if undo {
+            change.clone().inverse()
+        } else {
+            change.clone()
+        };
+        // the song goes back under the media type its old one is filed under now
+        let replayed = match replayed {
+            Change::Added(song) => Change::Added(Self::resolve_media_type_of(&mut tx, song).await?),
+            Change::Edited { before, after } => Change::Edited {
+                before,
+                after: Self::resolve_media_type_of(&mut tx, after).await?,
+            },
+            deleted => deleted,
+        };
+        // a song deleted some other way since can't be edited or deleted again, which is fine
+        let applied = match &replayed {
+            // a deleted song is taken out of the trash, or added again with its id if it was purged since
+            Change::Added(song) => {
+                if Self::restore(&mut tx, song.id).await? == 0 {
+                    Self::insert_song(&mut tx, song, Some(song.id)).await?;
+                }
+                1
+            }
+            Change::Edited { after, .. } => Self::write_song(&mut tx, after).await?,
+            Change::Deleted(song) => {
+                let result = sqlx::query(
+                    "UPDATE Songs SET deleted_at = ? WHERE id = ? AND deleted_at IS NULL",
+                )
+                .bind(audit::timestamp_now())
+                .bind(song.id)
+                .execute(&mut *tx)
+                .await?;
+                DB::rows_affected(&result)
+            }
+        };
+        if applied > 0 {
+            Self::audit(&mut tx, &self.user, &replayed).await?;
+        }
+        sqlx::query("UPDATE Journal SET undone = ? WHERE id = ?")
+            .bind(undo)
+            .bind(entry_id)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(Some(change))
+    }
+
+    // returns the id of the artist with this name (ignoring case), creating the artist if there isn't one
+    async fn resolve_artist(tx: &mut Transaction<'_, DB>, name: &str) -> Result<u32, sqlx::Error> {
+        let name = name.trim();
+        let existing: Option<u32> =
+            sqlx::query_scalar("SELECT id FROM Artists WHERE LOWER(name) = LOWER(?)")
+                .bind(name)
+                .fetch_optional(&mut **tx)
+                .await?;
+        match existing {
+            Some(id) => Ok(id),
+            None => {
+                let result = sqlx::query("INSERT INTO Artists (name) VALUES (?)")
+                    .bind(name)
+                    .execute(&mut **tx)
+                    .await?;
+                Ok(DB::last_insert_id(&result))
+            }
+        }
+    }
+
+    // returns the name of the media type with this name or alias (ignoring case). A name that isn't
+    // on the list is an error, types are only added from the media types view.
+    async fn resolve_media_type(
+        tx: &mut Transaction<'_, DB>,
+        name: &str,
+    ) -> Result<String, sqlx::Error> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Ok(String::new());
+        }
+        let existing: Option<String> = sqlx::query_scalar(
+            "SELECT name FROM MediaTypes WHERE LOWER(name) = LOWER(?) \
+             UNION ALL SELECT mt.name FROM MediaTypeAliases a JOIN MediaTypes mt ON mt.id = a.media_type_id \
+             WHERE LOWER(a.alias) = LOWER(?) LIMIT 1",
+        )
+        .bind(name)
+        .bind(name)
+        .fetch_optional(&mut **tx)
+        .await?;
+        existing
+            .ok_or_else(|| sqlx::Error::Configuration(Box::new(validate::unknown_media_type(name))))
+    }
+
+    // the song with the name of its media type from the list
+    async fn resolve_media_type_of(
+        tx: &mut Transaction<'_, DB>,
+        song: Song,
+    ) -> Result<Song, sqlx::Error> {
+        let media_type = Self::resolve_media_type(tx, &song.media_type).await?;
+        Ok(Song { media_type, ..song })
+    }
+
+    // returns the id of the song's album by this artist, creating it from the song's year and media type if needed
+    async fn resolve_album(
+        tx: &mut Transaction<'_, DB>,
+        song: &Song,
+        artist_id: u32,
+    ) -> Result<u32, sqlx::Error> {
+        let title = song.album.trim();
+        let existing: Option<u32> = sqlx::query_scalar(
+            "SELECT id FROM Albums WHERE artist_id = ? AND LOWER(title) = LOWER(?)",
+        )
+        .bind(artist_id)
+        .bind(title)
+        .fetch_optional(&mut **tx)
+        .await?;
+        match existing {
+            Some(id) => Ok(id),
+            None => {
+                let result = sqlx::query(
+                    "INSERT INTO Albums (title, artist_id, release_year, media_type) VALUES (?, ?, ?, ?)",
+                )
+                .bind(title)
+                .bind(artist_id)
+                .bind(song.release_year)
+                .bind(&song.media_type)
+                .execute(&mut **tx)
+                .await?;
+                Ok(DB::last_insert_id(&result))
+            }
+        }
+    }
+}
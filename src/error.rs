@@ -1,18 +1,326 @@
-// Custom Error types live here
+// Custom Error types live here. AppError gathers up the others, so everything the app can run
+// into travels up with ? to where it's shown: main for a command, the status bar in the TUI.
 
-use std::{error::Error, fmt};
+use crate::{
+    config::{redact_password, Config, UrlSource, DATABASE_URL_VAR},
+    validate::FieldError,
+};
+use std::{error::Error, fmt, io, path::PathBuf};
+
+// Every error the app can stop with or show in the TUI
+#[derive(Debug)]
+pub enum AppError {
+    Argument(ArgumentError),
+    Config(ConfigError),
+    Command(CommandError),
+    Database {
+        doing: &'static str,
+        error: sqlx::Error,
+    }, // doing is what failed, like "add the song"
+    File {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Terminal(io::Error),
+    Invalid(Vec<FieldError>), // every problem with the values typed into a form
+}
+impl Error for AppError {}
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Argument(error) => write!(f, "{}", error),
+            Self::Config(error) => write!(f, "{}", error),
+            Self::Command(error) => write!(f, "{}", error),
+            Self::Database { doing, error } => write!(f, "Error: Could not {}: {}", doing, error),
+            Self::File { path, error } => {
+                write!(f, "Error: Could not write {}: {}", path.display(), error)
+            }
+            Self::Terminal(error) => write!(f, "Error: The terminal failed: {}", error),
+            Self::Invalid(errors) => {
+                let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+                write!(f, "Error: The {}", errors.join(", "))
+            }
+        }
+    }
+}
+impl From<ArgumentError> for AppError {
+    fn from(error: ArgumentError) -> Self {
+        Self::Argument(error)
+    }
+}
+impl From<ConfigError> for AppError {
+    fn from(error: ConfigError) -> Self {
+        Self::Config(error)
+    }
+}
+impl From<CommandError> for AppError {
+    fn from(error: CommandError) -> Self {
+        Self::Command(error)
+    }
+}
+
+// says what was being done when a database call failed, for the message:
+// result.context("add the song")?
+pub trait Context<T> {
+    fn context(self, doing: &'static str) -> Result<T, AppError>;
+}
+impl<T> Context<T> for Result<T, sqlx::Error> {
+    fn context(self, doing: &'static str) -> Result<T, AppError> {
+        self.map_err(|error| AppError::Database { doing, error })
+    }
+}
 
 #[derive(Debug)]
 pub enum ArgumentError {
     InvalidArgument,
     InvalidNumberOfArguments,
+    InvalidValue { arg: String, value: String },
+    InvalidField(FieldError), // a song value the library can't hold, like a year of 19700
+    MissingField(&'static str),
 }
 impl Error for ArgumentError {}
 impl fmt::Display for ArgumentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const HELP: &str = "Use \"-h\" or \"--help\" for usage information.";
+        match self {
+            Self::InvalidArgument => write!(f, "Error: Invalid argument. {}", HELP),
+            Self::InvalidNumberOfArguments => {
+                write!(f, "Error: Invalid number of arguments. {}", HELP)
+            }
+            Self::InvalidValue { arg, value } => {
+                write!(
+                    f,
+                    "Error: Invalid value \"{}\" for {}. {}",
+                    value, arg, HELP
+                )
+            }
+            Self::InvalidField(error) => write!(f, "Error: The {}.", error),
+            Self::MissingField(field) => write!(f, "Error: Missing {}. {}", field, HELP),
+        }
+    }
+}
+
+// Problems with the config file or the database it points at
+#[derive(Debug)]
+pub enum ConfigError {
+    Unreadable {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Malformed {
+        path: PathBuf,
+        error: toml::de::Error,
+    },
+    InvalidUrl {
+        url: String,
+        source: UrlSource,
+        reason: String,
+    },
+    ConnectionFailed {
+        url: String,
+        source: UrlSource,
+        error: sqlx::Error,
+    },
+}
+impl Error for ConfigError {}
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable { path, error } => write!(
+                f,
+                "Error: Could not read the config file {}: {}",
+                path.display(),
+                error
+            ),
+            Self::Malformed { path, error } => write!(
+                f,
+                "Error: The config file {} is malformed: {}",
+                path.display(),
+                error
+            ),
+            Self::InvalidUrl {
+                url,
+                source,
+                reason,
+            } => write!(
+                f,
+                "Error: Invalid database URL \"{}\" ({}): {}",
+                redact_password(url),
+                source,
+                reason
+            ),
+            Self::ConnectionFailed { url, source, error } => {
+                let config_path = Config::default_path()
+                    .map(|path| format!(" at {}", path.display()))
+                    .unwrap_or_default();
+                write!(
+                    f,
+                    "Error: Could not open the library at {} ({}): {}\n\
+                    Set the database URL with --database-url, {} or the config file{}.",
+                    redact_password(url),
+                    source,
+                    error,
+                    DATABASE_URL_VAR,
+                    config_path
+                )
+            }
+        }
+    }
+}
+
+// Problems running a CLI subcommand
+#[derive(Debug)]
+pub enum CommandError {
+    NotFound(u32),
+    UnknownMediaType(String), // a media type that isn't on the list
+    Database(sqlx::Error),
+    Output(io::Error),
+    Import(ImportError),
+    Scan(ScanError),
+    Query(QueryError),
+}
+impl Error for CommandError {}
+impl fmt::Display for CommandError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(id) => write!(f, "Error: There is no song with id {}", id),
+            Self::UnknownMediaType(name) => write!(
+                f,
+                "Error: There is no media type named {}, see `music-library media-types`",
+                name
+            ),
+            Self::Database(error) => write!(f, "Error: Database error: {}", error),
+            Self::Output(error) => write!(f, "Error: Could not write the output: {}", error),
+            Self::Import(error) => write!(f, "{}", error),
+            Self::Scan(error) => write!(f, "{}", error),
+            Self::Query(error) => write!(f, "{}", error),
+        }
+    }
+}
+impl From<sqlx::Error> for CommandError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error)
+    }
+}
+impl From<io::Error> for CommandError {
+    fn from(error: io::Error) -> Self {
+        Self::Output(error)
+    }
+}
+impl From<ImportError> for CommandError {
+    fn from(error: ImportError) -> Self {
+        Self::Import(error)
+    }
+}
+impl From<ScanError> for CommandError {
+    fn from(error: ScanError) -> Self {
+        Self::Scan(error)
+    }
+}
+
+impl From<QueryError> for CommandError {
+    fn from(error: QueryError) -> Self {
+        Self::Query(error)
+    }
+}
+
+// Problems with a CSV file being imported
+#[derive(Debug)]
+pub enum ImportError {
+    Unreadable {
+        path: PathBuf,
+        error: io::Error,
+    },
+    Csv(csv::Error),
+    UnknownColumn(String),
+    MissingColumn {
+        field: &'static str,
+        headers: Vec<String>,
+    },
+    InvalidRows(usize),
+}
+impl Error for ImportError {}
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable { path, error } => {
+                write!(f, "Error: Could not read {}: {}", path.display(), error)
+            }
+            Self::Csv(error) => write!(f, "Error: Could not read the CSV: {}", error),
+            Self::UnknownColumn(header) => {
+                write!(f, "Error: There is no column called \"{}\" to map", header)
+            }
+            Self::MissingColumn { field, headers } => write!(
+                f,
+                "Error: No column holds the {}. Name one with --map \"<HEADER>={}\" (the columns are: {})",
+                field,
+                field,
+                headers.join(", ")
+            ),
+            Self::InvalidRows(count) => {
+                write!(f, "Error: {} problems found, nothing was imported", count)
+            }
+        }
+    }
+}
+impl From<csv::Error> for ImportError {
+    fn from(error: csv::Error) -> Self {
+        Self::Csv(error)
+    }
+}
+
+// Problems scanning a music folder. Files that can't be read are reported with the scan instead.
+#[derive(Debug)]
+pub enum ScanError {
+    Unreadable { path: PathBuf, error: io::Error },
+    Database(sqlx::Error),
+}
+impl Error for ScanError {}
+impl fmt::Display for ScanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unreadable { path, error } => write!(
+                f,
+                "Error: Could not read the folder {}: {}",
+                path.display(),
+                error
+            ),
+            Self::Database(error) => write!(f, "Error: Database error: {}", error),
+        }
+    }
+}
+impl From<sqlx::Error> for ScanError {
+    fn from(error: sqlx::Error) -> Self {
+        Self::Database(error)
+    }
+}
+
+// Syntax errors in a search query
+#[derive(Debug, PartialEq)]
+pub enum QueryError {
+    UnknownField(String),
+    MissingValue(String),
+    InvalidYear(String),
+    UnclosedQuote,
+}
+impl Error for QueryError {}
+impl fmt::Display for QueryError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            Self::InvalidArgument => write!(f, "Error: Invalid argument. Use \"-h\" or \"--help\" for usage information."),
-            Self::InvalidNumberOfArguments => write!(f, "Error: Invalid number of arguments. Use \"-h\" or \"--help\" for usage information."),
+            Self::UnknownField(field) => write!(
+                f,
+                "Error: Unknown field \"{}\", search by title, artist, album, year or media",
+                field
+            ),
+            Self::MissingValue(field) => {
+                write!(f, "Error: Nothing to search for after \"{}\"", field)
+            }
+            Self::InvalidYear(years) => write!(
+                f,
+                "Error: Invalid year \"{}\", use a year like 1995 or a range like 1990..1999",
+                years
+            ),
+            Self::UnclosedQuote => write!(f, "Error: A quote is missing its closing \""),
         }
     }
 }
@@ -0,0 +1,310 @@
+// Typo-tolerant search. Every word of the query is matched against the words of each song's title,
+// artist and album, allowing a typo or two, and the songs are ranked by how well the words matched.
+
+use crate::song::Song;
+use std::{cmp::Reverse, collections::BinaryHeap, ops::Range};
+
+// the fields fuzzy search looks in, in the order of Highlights
+const FIELDS: usize = 3;
+
+// the characters of the title, artist and album a query matched, as char ranges
+pub type Highlights = [Vec<Range<usize>>; FIELDS];
+
+// a song that matched, with its score and where it matched
+#[derive(Debug, Clone, PartialEq)]
+pub struct FuzzyMatch {
+    pub song: Song,
+    pub score: u32,
+    pub highlights: Highlights,
+}
+
+// scores for the ways a query word can match, typos score lower for each edit
+const EXACT: u32 = 100;
+const PREFIX: u32 = 80;
+const SUBSTRING: u32 = 60;
+const TYPO: u32 = 50;
+// a title match is worth a bit more than the same match in the artist or album
+const FIELD_BONUS: [u32; FIELDS] = [10, 5, 0];
+
+// the best matches for a query among songs scored a batch at a time, so a whole library never has
+// to be held at once. Only the best limit matches are kept, best first, ties in the order they came in.
+#[derive(Debug)]
+pub struct BestMatches {
+    query_words: Vec<String>,
+    limit: usize,
+    seen: usize,              // how many songs have been scored, to keep ties in order
+    kept: BinaryHeap<Ranked>, // the worst match kept on top, to go first
+}
+
+// a match ordered worst last: by score, then by the order it came in
+#[derive(Debug)]
+struct Ranked {
+    score: Reverse<u32>,
+    seen: usize,
+    found: FuzzyMatch,
+}
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        (self.score, self.seen) == (other.score, other.seen)
+    }
+}
+impl Eq for Ranked {}
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.score, self.seen).cmp(&(other.score, other.seen))
+    }
+}
+
+impl BestMatches {
+    pub fn new(query: &str, limit: usize) -> Self {
+        Self {
+            query_words: words(query).into_iter().map(|(_, word)| word).collect(),
+            limit,
+            seen: 0,
+            kept: BinaryHeap::new(),
+        }
+    }
+
+    // scores the next songs, letting go of the ones that don't match or aren't among the best
+    pub fn add(&mut self, songs: Vec<Song>) {
+        for song in songs {
+            self.seen += 1;
+            let Some(found) = self.score(song) else {
+                continue;
+            };
+            self.kept.push(Ranked {
+                score: Reverse(found.score),
+                seen: self.seen,
+                found,
+            });
+            if self.kept.len() > self.limit {
+                self.kept.pop();
+            }
+        }
+    }
+
+    // the matches kept, best first
+    pub fn into_matches(self) -> Vec<FuzzyMatch> {
+        self.kept
+            .into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.found)
+            .collect()
+    }
+
+    // how well a song matches every word of the query, None if a word doesn't match at all
+    fn score(&self, song: Song) -> Option<FuzzyMatch> {
+        let fields = [&song.title, &song.artist, &song.album].map(|field| words(field));
+        let mut score = 0;
+        let mut highlights = Highlights::default();
+        for query_word in &self.query_words {
+            let (word_score, field, range) = best_match(query_word, &fields)?;
+            score += word_score;
+            highlights[field].push(range);
+        }
+        Some(FuzzyMatch {
+            song,
+            score,
+            highlights,
+        })
+    }
+}
+
+// the words of a text, lowercase, with the char position each one starts at
+// whether a query has no words to match, so every song would match it equally
+pub fn is_blank(query: &str) -> bool {
+    words(query).is_empty()
+}
+
+fn words(text: &str) -> Vec<(usize, String)> {
+    let mut words = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (index, c) in text.chars().enumerate() {
+        if c.is_alphanumeric() {
+            current
+                .get_or_insert_with(|| (index, String::new()))
+                .1
+                .extend(c.to_lowercase());
+        } else if let Some(word) = current.take() {
+            words.push(word);
+        }
+    }
+    words.extend(current);
+    words
+}
+
+// the best way a query word matches any of the fields: its score, the field and the chars it covers
+fn best_match(
+    query_word: &str,
+    fields: &[Vec<(usize, String)>; FIELDS],
+) -> Option<(u32, usize, Range<usize>)> {
+    let query_length = query_word.chars().count();
+    let mut best: Option<(u32, usize, Range<usize>)> = None;
+    for (field, words) in fields.iter().enumerate() {
+        for (start, word) in words {
+            let word_length = word.chars().count();
+            let found = if word == query_word {
+                Some((EXACT, 0..word_length))
+            } else if word.starts_with(query_word) {
+                Some((PREFIX, 0..query_length))
+            } else if let Some(offset) = word.find(query_word) {
+                let offset = word[..offset].chars().count();
+                Some((SUBSTRING, offset..offset + query_length))
+            } else {
+                let edits = edit_distance(query_word, word);
+                (edits <= allowed_typos(query_length))
+                    .then(|| (TYPO - 10 * (edits as u32 - 1), 0..word_length))
+            };
+            if let Some((score, range)) = found {
+                let score = score + FIELD_BONUS[field];
+                if best
+                    .as_ref()
+                    .is_none_or(|(best_score, ..)| score > *best_score)
+                {
+                    best = Some((score, field, start + range.start..start + range.end));
+                }
+            }
+        }
+    }
+    best
+}
+
+// short words have to be spelled right, longer ones can have a typo or two
+pub fn allowed_typos(length: usize) -> usize {
+    match length {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+// the number of insertions, deletions, substitutions and swaps of neighbouring characters
+// needed to turn one word into the other, so "radiohaed" is one edit from "radiohead"
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1)
+                .min(distances[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+    distances[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn library() -> Vec<Song> {
+        vec![
+            Song::new(0, "Walrus", "Blur", "Leisure", 1991, "CD"),
+            Song::new(
+                0,
+                "I Am the Walrus",
+                "The Beatles",
+                "Magical Mystery Tour",
+                1967,
+                "Vinyl",
+            ),
+            Song::new(
+                0,
+                "Paranoid Android",
+                "Radiohead",
+                "OK Computer",
+                1997,
+                "CD",
+            ),
+            Song::new(0, "Help!", "The Beatles", "Help!", 1965, "Vinyl"),
+        ]
+    }
+
+    // every song that matches every word of the query, best match first
+    fn search(songs: Vec<Song>, query: &str) -> Vec<FuzzyMatch> {
+        let mut best = BestMatches::new(query, usize::MAX);
+        best.add(songs);
+        best.into_matches()
+    }
+
+    fn titles(matches: &[FuzzyMatch]) -> Vec<&str> {
+        matches.iter().map(|m| m.song.title.as_str()).collect()
+    }
+
+    fn spans(ranges: &[Range<usize>]) -> Vec<(usize, usize)> {
+        ranges
+            .iter()
+            .map(|range| (range.start, range.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("radiohaed", "radiohead"), 1);
+        assert_eq!(edit_distance("beatles", "beetles"), 1);
+        assert_eq!(edit_distance("walrus", "wallrus"), 1);
+        assert_eq!(edit_distance("blur", "help"), 4);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn test_typos_and_ranking() {
+        let matches = search(library(), "radiohaed");
+        assert_eq!(titles(&matches), vec!["Paranoid Android"]);
+        assert_eq!(spans(&matches[0].highlights[1]), vec![(0, 9)]);
+
+        // every word has to match somewhere, the walrus by the beatles beats the one by blur
+        let matches = search(library(), "beatles walrus");
+        assert_eq!(titles(&matches), vec!["I Am the Walrus"]);
+        assert_eq!(spans(&matches[0].highlights[0]), vec![(9, 15)]);
+        assert_eq!(spans(&matches[0].highlights[1]), vec![(4, 11)]);
+        assert!(matches[0].highlights[2].is_empty());
+        assert_eq!(
+            titles(&search(library(), "walrs")),
+            vec!["Walrus", "I Am the Walrus"]
+        );
+
+        // short words have to be spelled right
+        let matches = search(library(), "help");
+        assert_eq!(titles(&matches), vec!["Help!"]);
+        assert!(search(library(), "hlp").is_empty());
+        assert_eq!(search(library(), "").len(), 4);
+    }
+
+    #[test]
+    fn test_only_the_best_are_kept() {
+        let mut library = library();
+        let rest = library.split_off(2);
+        // scored in batches, a tie goes to the song that came first
+        let mut best = BestMatches::new("walrus", 1);
+        best.add(library.clone());
+        best.add(rest.clone());
+        assert_eq!(titles(&best.into_matches()), vec!["Walrus"]);
+
+        // every song matches an empty query equally well, so the first ones are kept
+        let mut best = BestMatches::new("", 2);
+        best.add(rest);
+        best.add(library);
+        assert_eq!(
+            titles(&best.into_matches()),
+            vec!["Paranoid Android", "Help!"]
+        );
+    }
+}
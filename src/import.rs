@@ -0,0 +1,229 @@
+// CSV import of songs. Columns are matched to song fields by their header, every row is
+// checked, media types against the list, before anything is written, and then all of them are added in one transaction.
+
+use crate::{error::ImportError, media_type::MediaType, song::Song, validate};
+use std::io;
+
+// the song fields a column can hold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Field {
+    Title,
+    Artist,
+    Album,
+    ReleaseYear,
+    MediaType,
+}
+impl Field {
+    pub const ALL: [Field; 5] = [
+        Field::Title,
+        Field::Artist,
+        Field::Album,
+        Field::ReleaseYear,
+        Field::MediaType,
+    ];
+
+    // the field a header or --map name means, ignoring case, spaces and punctuation
+    pub fn from_name(name: &str) -> Option<Self> {
+        match normalize(name).as_str() {
+            "title" | "song" | "songtitle" | "track" | "name" => Some(Self::Title),
+            "artist" | "artistname" | "band" => Some(Self::Artist),
+            "album" | "albumtitle" | "record" => Some(Self::Album),
+            "year" | "releaseyear" | "released" => Some(Self::ReleaseYear),
+            "mediatype" | "media" | "format" => Some(Self::MediaType),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Title => "title",
+            Self::Artist => "artist",
+            Self::Album => "album",
+            Self::ReleaseYear => "year",
+            Self::MediaType => "media_type",
+        }
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// a problem with one value, reported with the line it's on so it can be fixed in the spreadsheet
+#[derive(Debug, PartialEq)]
+pub struct RowError {
+    pub line: u64,
+    pub message: String,
+}
+
+// the rows that can be imported and the problems with the ones that can't
+#[derive(Debug, Default)]
+pub struct Import {
+    pub songs: Vec<Song>,
+    pub errors: Vec<RowError>,
+}
+
+// reads songs from CSV with a header row.
+// mapping pairs a header with the field its column holds, for headers that aren't recognized by name.
+// Columns that aren't mapped to a field, like the id column of an export, are ignored.
+pub fn read_songs(
+    reader: impl io::Read,
+    mapping: &[(String, Field)],
+    media_types: &[MediaType],
+) -> Result<Import, ImportError> {
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+    let headers = reader.headers()?.clone();
+    let columns = map_columns(&headers, mapping)?;
+
+    let mut import = Import::default();
+    for record in reader.records() {
+        let record = record?;
+        let line = record.position().map_or(0, |position| position.line());
+        // blank lines at the end of a spreadsheet export aren't songs
+        if record.iter().all(|value| value.trim().is_empty()) {
+            continue;
+        }
+        let value = |field: Field| record.get(columns[field as usize]).unwrap_or("");
+        let song = validate::parse_song(Field::ALL.map(value)).and_then(|song| {
+            validate::media_type(media_types, &song.media_type).map_err(|error| vec![error])?;
+            Ok(song)
+        });
+        match song {
+            Ok(song) => import.songs.push(song),
+            Err(errors) => import
+                .errors
+                .extend(errors.into_iter().map(|error| RowError {
+                    line,
+                    message: error.to_string(),
+                })),
+        }
+    }
+    Ok(import)
+}
+
+// the index of the column holding each field, in the order of Field::ALL
+fn map_columns(
+    headers: &csv::StringRecord,
+    mapping: &[(String, Field)],
+) -> Result<[usize; 5], ImportError> {
+    let mut columns = [None; 5];
+    // explicit mappings win over headers recognized by name
+    for (header, field) in mapping {
+        let index = headers
+            .iter()
+            .position(|name| normalize(name) == normalize(header))
+            .ok_or_else(|| ImportError::UnknownColumn(header.clone()))?;
+        columns[*field as usize] = Some(index);
+    }
+    for (index, name) in headers.iter().enumerate() {
+        if let Some(field) = Field::from_name(name) {
+            let column = &mut columns[field as usize];
+            if column.is_none() {
+                *column = Some(index);
+            }
+        }
+    }
+
+    let mut mapped = [0; 5];
+    for field in Field::ALL {
+        mapped[field as usize] =
+            columns[field as usize].ok_or_else(|| ImportError::MissingColumn {
+                field: field.name(),
+                headers: headers.iter().map(str::to_owned).collect(),
+            })?;
+    }
+    Ok(mapped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media_type::Format;
+
+    fn media_types() -> Vec<MediaType> {
+        let mut vinyl = MediaType::new("Vinyl", Format::Vinyl);
+        vinyl.aliases = vec!["LP".to_owned()];
+        vec![vinyl, MediaType::new("Digital Download", Format::Digital)]
+    }
+
+    #[test]
+    fn test_headers_are_recognized() {
+        let csv = "ID,Song Title,Artist,Album,Release Year,Format\n\
+                   7,War Pigs,Black Sabbath,Paranoid,1970,Vinyl\n\
+                   ,,,,,\n";
+        let import = read_songs(csv.as_bytes(), &[], &media_types()).unwrap();
+        assert!(import.errors.is_empty());
+        assert_eq!(
+            import.songs,
+            vec![Song::new(
+                0,
+                "War Pigs",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "Vinyl"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_mapping_overrides_headers() {
+        let csv = "Name,Band,Record,Year,Shelf,Format\n\
+                   Solo,Future,HNDRXX,2017,lp,Digital Download\n";
+        let mapping = [
+            ("record".to_owned(), Field::Album),
+            ("Shelf".to_owned(), Field::MediaType),
+        ];
+        let import = read_songs(csv.as_bytes(), &mapping, &media_types()).unwrap();
+        assert_eq!(import.songs[0].album, "HNDRXX");
+        assert_eq!(import.songs[0].media_type, "lp");
+
+        let mapping = [("Location".to_owned(), Field::MediaType)];
+        assert!(matches!(
+            read_songs(csv.as_bytes(), &mapping, &media_types()),
+            Err(ImportError::UnknownColumn(_))
+        ));
+        assert!(matches!(
+            read_songs("title,artist,album,year\n".as_bytes(), &[], &[]),
+            Err(ImportError::MissingColumn {
+                field: "media_type",
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_every_bad_value_is_reported() {
+        let long_title = "x".repeat(61);
+        let csv = format!(
+            "title,artist,album,year,media_type\n\
+             Caldonia,B.B. King,B.B. King in London,1971,Vinyl\n\
+             {long_title},,Paranoid,1970,Vinyl\n\
+             Solo,Future,HNDRXX,soon,Digital Download\n\
+             Iron Man,Black Sabbath,Paranoid,1970,lp\n\
+             Planet Caravan,Black Sabbath,Paranoid,1970,Shellac\n"
+        );
+        let import = read_songs(csv.as_bytes(), &[], &media_types()).unwrap();
+        assert_eq!(import.songs.len(), 2);
+        let messages: Vec<(u64, &str)> = import
+            .errors
+            .iter()
+            .map(|error| (error.line, error.message.as_str()))
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                (3, "title is longer than 60 characters"),
+                (3, "artist is empty"),
+                (4, "year \"soon\" is not a number"),
+                (
+                    6,
+                    "media_type \"Shellac\" is not on the list of media types"
+                ),
+            ]
+        );
+    }
+}
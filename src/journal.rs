@@ -0,0 +1,107 @@
+// The undo journal. Every song added, edited or deleted is written to the Journal table in the
+// same transaction as the change itself, so it can be undone and redone, even after a restart.
+// Songs are kept by name rather than by artist and album id, since those can be gone by the time
+// a change is undone; they're resolved again like a new song's are.
+
+use crate::song::Song;
+
+// a change to one song
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added(Song),
+    Edited { before: Song, after: Song },
+    Deleted(Song),
+}
+impl Change {
+    // the change that puts things back the way they were
+    pub fn inverse(self) -> Self {
+        match self {
+            Self::Added(song) => Self::Deleted(song),
+            Self::Edited { before, after } => Self::Edited {
+                before: after,
+                after: before,
+            },
+            Self::Deleted(song) => Self::Added(song),
+        }
+    }
+
+    // the song's id, and its values before and after the change, for the Journal's columns
+    pub fn columns(&self) -> (u32, Option<&Song>, Option<&Song>) {
+        match self {
+            Self::Added(song) => (song.id, None, Some(song)),
+            Self::Edited { before, after } => (before.id, Some(before), Some(after)),
+            Self::Deleted(song) => (song.id, Some(song), None),
+        }
+    }
+
+    // what the change did, for telling the user what was undone
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Added(song) => format!("adding {}", song.title),
+            Self::Edited { before, .. } => format!("editing {}", before.title),
+            Self::Deleted(song) => format!("deleting {}", song.title),
+        }
+    }
+}
+
+// a row of the Journal table. The old_ columns are NULL for an added song, the new_ ones for a deleted one.
+#[derive(Debug, sqlx::FromRow)]
+pub struct JournalEntry {
+    pub id: u32,
+    song_id: u32,
+    old_title: Option<String>,
+    old_artist: Option<String>,
+    old_album: Option<String>,
+    old_release_year: Option<i32>,
+    old_media_type: Option<String>,
+    new_title: Option<String>,
+    new_artist: Option<String>,
+    new_album: Option<String>,
+    new_release_year: Option<i32>,
+    new_media_type: Option<String>,
+}
+impl JournalEntry {
+    pub fn change(&self) -> Option<Change> {
+        let old = song(
+            self.song_id,
+            &self.old_title,
+            &self.old_artist,
+            &self.old_album,
+            self.old_release_year,
+            &self.old_media_type,
+        );
+        let new = song(
+            self.song_id,
+            &self.new_title,
+            &self.new_artist,
+            &self.new_album,
+            self.new_release_year,
+            &self.new_media_type,
+        );
+        match (old, new) {
+            (None, Some(song)) => Some(Change::Added(song)),
+            (Some(before), Some(after)) => Some(Change::Edited { before, after }),
+            (Some(song), None) => Some(Change::Deleted(song)),
+            (None, None) => None,
+        }
+    }
+}
+
+// one side of a journal row as a song, None if its columns are NULL
+fn song(
+    id: u32,
+    title: &Option<String>,
+    artist: &Option<String>,
+    album: &Option<String>,
+    release_year: Option<i32>,
+    media_type: &Option<String>,
+) -> Option<Song> {
+    Some(Song::new(
+        id,
+        title.as_deref()?,
+        artist.as_deref()?,
+        album.as_deref()?,
+        release_year?,
+        media_type.as_deref()?,
+    ))
+}
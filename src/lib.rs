@@ -1,51 +1,158 @@
-use std::io;
+use std::{env, sync::Arc};
 
-use sqlx::MySqlPool;
-
-use crate::database::connect_to_database;
+use crate::database::{connect_to_database, LibraryStore};
 use app::App;
-use error::ArgumentError;
+use cli::{Command, OutputFormat, COMMANDS};
+use config::{Config, UiConfig, DATABASE_URL_VAR};
+pub use error::AppError;
+use error::{ArgumentError, CommandError, ConfigError, Context};
 
+mod album;
+mod album_popup;
 mod app;
+mod artist;
+mod audit;
+mod cli;
+mod complete;
+mod config;
 mod database;
 mod error;
+mod fuzzy;
+mod import;
+mod journal;
+mod media_type;
+mod media_type_popup;
+mod pager;
+mod picker;
+mod playlist;
+mod playlist_popup;
 mod popup;
+mod query;
+mod scan;
 mod song;
+mod sort;
+mod status;
 mod text_box;
 mod tui;
+mod validate;
+
+// Options for running the app, from the command line
+#[derive(Debug, Default)]
+pub struct Options {
+    database_url: Option<String>, // overrides the environment and the config file
+    command: Option<Command>,     // run this instead of the TUI
+    format: OutputFormat,         // how the command prints songs
+}
 
-pub fn parse_args(args: Vec<String>) -> Result<(), ArgumentError> {
-    if args.len() == 2 {
-        match args[1].as_str() {
+// Returns the options to run with, or None if the arguments were handled here (help, version)
+pub fn parse_args(args: Vec<String>) -> Result<Option<Options>, ArgumentError> {
+    let mut options = Options::default();
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
             "-h" | "--help" => {
                 print_help();
-                Ok(())
+                return Ok(None);
             }
             "-v" | "--version" => {
                 print_version();
-                Ok(())
+                return Ok(None);
+            }
+            "-d" | "--database-url" => {
+                let url = args.next().ok_or(ArgumentError::InvalidNumberOfArguments)?;
+                options.database_url = Some(url);
             }
-            _ => Err(ArgumentError::InvalidArgument),
+            "-s" | "--sqlite" => {
+                let file = args.next().ok_or(ArgumentError::InvalidNumberOfArguments)?;
+                options.database_url = Some(format!("sqlite://{}", file));
+            }
+            "-f" | "--format" => {
+                let format = args.next().ok_or(ArgumentError::InvalidNumberOfArguments)?;
+                options.format = OutputFormat::parse(&format)?;
+            }
+            // the rest of the arguments belong to the subcommand
+            name if COMMANDS.contains(&name) => {
+                let command = Command::parse(name, args.by_ref(), &mut options.format)?;
+                options.command = Some(command);
+            }
+            _ => return Err(ArgumentError::InvalidArgument),
         }
-    } else {
-        Err(ArgumentError::InvalidNumberOfArguments)
     }
+    Ok(Some(options))
 }
 
-pub async fn initialize() -> Result<(), sqlx::Error> {
-    const URL: &str = "mysql://root:@localhost:3306/music";
-    let pool = connect_to_database(URL).await?;
-    run_tui(pool).await?;
+// read the config, connect to the library it points at and run the TUI or the subcommand
+pub async fn initialize(options: Options) -> Result<(), AppError> {
+    let config = Config::load()?;
+    let (url, source) =
+        config.database_url(options.database_url, env::var(DATABASE_URL_VAR).ok())?;
+    let store = match connect_to_database(&url, &config.audit.user()).await {
+        Ok(store) => store,
+        Err(error) => return Err(ConfigError::ConnectionFailed { url, source, error }.into()),
+    };
+    // songs that have been in the trash longer than the retention period go for good
+    if config.trash.retention_days > 0 {
+        let cutoff = audit::timestamp_days_ago(config.trash.retention_days);
+        store
+            .purge_trash(&cutoff)
+            .await
+            .context("empty the trash")?;
+    }
+    match options.command {
+        Some(command) => cli::run(store.as_ref(), command, options.format).await?,
+        None => run_tui(store, config.ui, config.media_types.allow_adding).await?,
+    }
     Ok(())
 }
 
+// the process exit code for an error: 2 for bad usage, 3 when the song or media type doesn't exist,
+// 4 when an import file can't be imported, 1 otherwise
+pub fn exit_code(error: &AppError) -> u8 {
+    match error {
+        AppError::Argument(_) => 2,
+        AppError::Command(CommandError::Query(_)) => 2,
+        AppError::Command(CommandError::NotFound(_) | CommandError::UnknownMediaType(_)) => 3,
+        AppError::Command(CommandError::Import(_)) => 4,
+        _ => 1,
+    }
+}
+
 fn print_help() {
     println!(
-        "\nUsage: music-library [OPTIONS]\n
+        "\nUsage: music-library [OPTIONS] [COMMAND]\n
+    \nCommands (without one, the TUI opens):
+    \n  list \t\t\tPrint every song
+    \n  search <QUERY> \tPrint the songs matching a query, like: artist:radiohead year:1990..1999 -title:live
+    \n  add --title <TITLE> --artist <ARTIST> --album <ALBUM> --year <YEAR> --media-type <TYPE>
+    \n  \t\t\tAdd a song and print it
+    \n  edit <ID> [--title <TITLE>] [--artist <ARTIST>] [--album <ALBUM>] [--year <YEAR>] [--media-type <TYPE>]
+    \n  \t\t\tChange the given fields of a song and print it
+    \n  delete <ID> \t\tMove a song to the trash and print it
+    \n  import <FILE> [--map <HEADER>=<FIELD>]... [--dry-run]
+    \n  \t\t\tAdd every song in a CSV file, or none if any row is invalid
+    \n  scan <FOLDER> [--dry-run] [-y, --yes]
+    \n  \t\t\tRead the tags of the MP3, FLAC, OGG and M4A files in a folder and import them
+    \n  export [QUERY] [-o, --output <FILE>]
+    \n  \t\t\tWrite every song, or the ones matching a query, as CSV
+    \n  history [ID] [--from <YYYY-MM-DD>] [--to <YYYY-MM-DD>]
+    \n  \t\t\tPrint the changes made to songs, or one song, between two days (in UTC)
+    \n  media-types \t\tPrint the media types songs and albums can have, with their other names
+    \n  media-types map <VALUE> <TYPE>
+    \n  \t\t\tFile the songs and albums with a media type under another one, and keep it as another name
+    \n
     \nOptions: 
-    \n  <NONE> \t\tRun music library
+    \n  <NONE> \t\tRun music library on the database from the config file
+    \n  -d, --database-url <URL> \tRun music library on this database instead (mysql://, mariadb:// or sqlite://)
+    \n  -s, --sqlite <FILE> \tRun music library on a SQLite file (created if missing)
+    \n  -f, --format <FORMAT> \tPrint songs as a table (default), json or csv
     \n  -v, --version \tPrint version information
-    \n  -h, --help \t\tPrint help (you are here)\n"
+    \n  -h, --help \t\tPrint help (you are here)\n
+    \nThe config file is read from {}
+    \n{} overrides the database URL in the config file
+    \nCommands exit with 0 on success, 1 on a database error, 2 on bad arguments or search syntax, 3 if the song doesn't exist
+    \nand 4 if the file to import has problems\n",
+        Config::default_path().map_or("<unknown>".to_owned(), |path| path.display().to_string()),
+        DATABASE_URL_VAR
     );
 }
 
@@ -58,15 +165,21 @@ fn print_version() {
     );
 }
 
-async fn run_tui(pool: MySqlPool) -> io::Result<()> {
-    let mut terminal = match tui::init() {
-        Ok(tui) => tui,
-        Err(error) => panic!("TUI initialization failed: {}", error),
+// the terminal is restored however the TUI ends, even if setting it up failed halfway
+// allow_adding is whether the TUI offers adding media types
+async fn run_tui(
+    store: Arc<dyn LibraryStore>,
+    ui_config: UiConfig,
+    allow_adding: bool,
+) -> Result<(), AppError> {
+    let app_result = match tui::init() {
+        Ok(mut terminal) => {
+            App::new(store, ui_config, allow_adding)
+                .run(&mut terminal)
+                .await
+        }
+        Err(error) => Err(error),
     };
-    let app_result = App::new(pool).run(&mut terminal).await;
-    match tui::restore() {
-        Ok(restored_terminal) => restored_terminal,
-        Err(error) => panic!("Terminal restoration failed: {}", error),
-    }
-    app_result
+    let restore_result = tui::restore();
+    app_result.and(restore_result).map_err(AppError::Terminal)
 }
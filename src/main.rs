@@ -1,14 +1,21 @@
-use std::{env, error::Error};
+use std::{env, process::ExitCode};
 
-use music_library::{initialize, parse_args};
+use music_library::{exit_code, initialize, parse_args};
 
 #[tokio::main]
-async fn main() -> Result<(), Box<dyn Error>> {
+async fn main() -> ExitCode {
     let args: Vec<String> = env::args().collect();
-    if args.len() > 1 {
-        parse_args(args)?;
-    } else {
-        initialize().await?;
+    let result = match parse_args(args) {
+        Ok(Some(options)) => initialize(options).await,
+        Ok(None) => Ok(()),
+        Err(error) => Err(error.into()),
+    };
+    // print errors with their message rather than their Debug form
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("{}", error);
+            ExitCode::from(exit_code(&error))
+        }
     }
-    Ok(())
 }
@@ -0,0 +1,151 @@
+// This is the program's model of a media type, one entry in the managed list songs and albums
+// take their media type from. Songs store the type's name, so grouping by media type works.
+// The details are optional and depend on the format: speed and size for vinyl, bitrate and codec
+// for digital. The counts and aliases are only filled in when media types are read from the database.
+
+use crate::picker::Choice;
+
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, serde::Serialize)]
+pub struct MediaType {
+    #[serde(skip)]
+    pub id: u32,
+    pub name: String,
+    pub format: String,
+    pub speed: Option<String>,
+    pub size: Option<String>,
+    pub bitrate: Option<String>,
+    pub codec: Option<String>,
+    // other spellings that are filed under this type, like "LP" for "Vinyl"
+    #[sqlx(skip)]
+    pub aliases: Vec<String>,
+    pub song_count: i64,
+    pub album_count: i64,
+}
+impl MediaType {
+    pub fn new(name: &str, format: Format) -> Self {
+        Self {
+            id: 0,
+            name: name.to_owned(),
+            format: format.name().to_owned(),
+            speed: None,
+            size: None,
+            bitrate: None,
+            codec: None,
+            aliases: Vec::new(),
+            song_count: 0,
+            album_count: 0,
+        }
+    }
+
+    // the format with the details that are filled in, like "Vinyl, 45 rpm, 7\""
+    pub fn details(&self) -> String {
+        let format = Format::parse(&self.format).label().to_owned();
+        [&self.speed, &self.size, &self.bitrate, &self.codec]
+            .into_iter()
+            .flatten()
+            .fold(format, |details, detail| format!("{}, {}", details, detail))
+    }
+
+    // the type as an option of the media type pickers, found by its aliases as well
+    pub fn choice(&self) -> Choice {
+        Choice {
+            value: self.name.clone(),
+            detail: self.details(),
+            aliases: self.aliases.clone(),
+        }
+    }
+}
+
+// what kind of media a type is, stored in MediaTypes.format by name
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Vinyl,
+    Cd,
+    Cassette,
+    Digital,
+    Other,
+}
+impl Format {
+    pub const ALL: [Self; 5] = [
+        Self::Vinyl,
+        Self::Cd,
+        Self::Cassette,
+        Self::Digital,
+        Self::Other,
+    ];
+
+    // formats the app doesn't know, say from a newer version, are shown as other
+    pub fn parse(name: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|format| format.name() == name)
+            .unwrap_or(Self::Other)
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Vinyl => "vinyl",
+            Self::Cd => "cd",
+            Self::Cassette => "cassette",
+            Self::Digital => "digital",
+            Self::Other => "other",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Vinyl => "Vinyl",
+            Self::Cd => "CD",
+            Self::Cassette => "Cassette",
+            Self::Digital => "Digital",
+            Self::Other => "Other",
+        }
+    }
+
+    // the details a type of this format can have
+    pub fn details(self) -> &'static [Detail] {
+        match self {
+            Self::Vinyl => &[Detail::Speed, Detail::Size],
+            Self::Digital => &[Detail::Bitrate, Detail::Codec],
+            Self::Cd | Self::Cassette | Self::Other => &[],
+        }
+    }
+}
+
+// the optional details of a media type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Detail {
+    Speed,
+    Size,
+    Bitrate,
+    Codec,
+}
+impl Detail {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Speed => "Speed",
+            Self::Size => "Size",
+            Self::Bitrate => "Bitrate",
+            Self::Codec => "Codec",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_details_follow_the_format() {
+        let mut single = MediaType::new("7\" Single", Format::Vinyl);
+        single.speed = Some("45 rpm".to_owned());
+        single.size = Some("7\"".to_owned());
+        assert_eq!(single.details(), "Vinyl, 45 rpm, 7\"");
+        assert_eq!(MediaType::new("CD", Format::Cd).details(), "CD");
+        assert_eq!(
+            Format::parse("digital").details(),
+            [Detail::Bitrate, Detail::Codec]
+        );
+        assert_eq!(Format::parse("minidisc"), Format::Other);
+    }
+}
@@ -0,0 +1,290 @@
+// this file contains the popup for adding a media type to the managed list, the media type
+// version of playlist_popup.rs. Only the details that go with the picked format are shown.
+
+use crate::{
+    database::LibraryStore,
+    error::{AppError, Context},
+    import::Field,
+    media_type::{Detail, Format, MediaType},
+    picker::{Choice, Picker},
+    text_box::{InputMode, TextBox},
+    validate::{self, FieldError, MAX_MEDIA_TYPE_LENGTH},
+};
+use crossterm::event::KeyEvent;
+
+// the fields of the popup in Tab order, the details depending on the format
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MediaTypeField {
+    Name,
+    Format,
+    Detail(Detail),
+}
+
+// MediaTypePopup stores all state info
+#[derive(Debug, Clone)]
+pub struct MediaTypePopup {
+    focus: MediaTypeField,
+    taken: Vec<String>, // the names and aliases already on the list
+    pub name_box: TextBox,
+    pub format_picker: Picker,
+    pub speed_box: TextBox,
+    pub size_box: TextBox,
+    pub bitrate_box: TextBox,
+    pub codec_box: TextBox,
+}
+
+impl Default for MediaTypePopup {
+    fn default() -> Self {
+        let mut format_picker = Picker::new("Format".to_owned());
+        format_picker.set_choices(
+            Format::ALL
+                .into_iter()
+                .map(|format| Choice {
+                    value: format.label().to_owned(),
+                    detail: format
+                        .details()
+                        .iter()
+                        .map(|detail| detail.label().to_lowercase())
+                        .collect::<Vec<String>>()
+                        .join(" and "),
+                    aliases: Vec::new(),
+                })
+                .collect(),
+        );
+        Self {
+            focus: MediaTypeField::Name,
+            taken: Vec::new(),
+            name_box: TextBox::new("Name".to_owned()),
+            format_picker,
+            speed_box: TextBox::new("Speed".to_owned()),
+            size_box: TextBox::new("Size".to_owned()),
+            bitrate_box: TextBox::new("Bitrate".to_owned()),
+            codec_box: TextBox::new("Codec".to_owned()),
+        }
+    }
+}
+
+impl MediaTypePopup {
+    // adds the media type, returns the number of rows affected
+    pub async fn submit(&mut self, store: &dyn LibraryStore) -> Result<u64, AppError> {
+        let new_media_type = self.get_media_type_from_input()?;
+        store
+            .add_media_type(new_media_type)
+            .await
+            .context("add the media type")
+    }
+
+    // returns a checked media type from the input, or everything wrong with it
+    fn get_media_type_from_input(&self) -> Result<MediaType, AppError> {
+        let mut errors = Vec::new();
+        let name = validate::text(Field::MediaType, self.name_box.get_input())
+            .and_then(|name| match self.is_taken(&name) {
+                true => Err(FieldError {
+                    field: Field::MediaType,
+                    message: format!("{} is already on the list", name),
+                }),
+                false => Ok(name),
+            })
+            .map_err(|error| errors.push(error))
+            .unwrap_or_default();
+        let format = self.get_format();
+        if format.is_none() {
+            errors.push(FieldError {
+                field: Field::MediaType,
+                message: "format isn't picked".to_owned(),
+            });
+        }
+        let format = format.unwrap_or(Format::Other);
+        let mut media_type = MediaType::new(&name, format);
+        for &detail in format.details() {
+            let value = self.detail_box(detail).get_input().trim();
+            if value.chars().count() > MAX_MEDIA_TYPE_LENGTH {
+                errors.push(FieldError {
+                    field: Field::MediaType,
+                    message: format!(
+                        "{} is longer than {} characters",
+                        detail.label().to_lowercase(),
+                        MAX_MEDIA_TYPE_LENGTH
+                    ),
+                });
+            }
+            let value = Some(value.to_owned()).filter(|value| !value.is_empty());
+            match detail {
+                Detail::Speed => media_type.speed = value,
+                Detail::Size => media_type.size = value,
+                Detail::Bitrate => media_type.bitrate = value,
+                Detail::Codec => media_type.codec = value,
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(media_type),
+            false => Err(AppError::Invalid(errors)),
+        }
+    }
+
+    fn is_taken(&self, name: &str) -> bool {
+        self.taken
+            .iter()
+            .any(|taken| taken.to_lowercase() == name.to_lowercase())
+    }
+
+    // the names and aliases of the media types on the list, which a new one can't have
+    pub fn set_taken(&mut self, media_types: &[MediaType]) {
+        self.taken = media_types
+            .iter()
+            .flat_map(|media_type| std::iter::once(&media_type.name).chain(&media_type.aliases))
+            .cloned()
+            .collect();
+    }
+
+    // the picked format, None until one is
+    pub fn get_format(&self) -> Option<Format> {
+        Format::ALL
+            .into_iter()
+            .find(|format| format.label() == self.format_picker.get_input())
+    }
+
+    pub fn detail_box(&self, detail: Detail) -> &TextBox {
+        match detail {
+            Detail::Speed => &self.speed_box,
+            Detail::Size => &self.size_box,
+            Detail::Bitrate => &self.bitrate_box,
+            Detail::Codec => &self.codec_box,
+        }
+    }
+
+    fn detail_box_mut(&mut self, detail: Detail) -> &mut TextBox {
+        match detail {
+            Detail::Speed => &mut self.speed_box,
+            Detail::Size => &mut self.size_box,
+            Detail::Bitrate => &mut self.bitrate_box,
+            Detail::Codec => &mut self.codec_box,
+        }
+    }
+
+    // clears all input fields, the format going back to none picked
+    pub fn clear_all_boxes(&mut self) {
+        self.name_box.clear_input();
+        self.format_picker.clear_input();
+        for detail in [Detail::Speed, Detail::Size, Detail::Bitrate, Detail::Codec] {
+            self.detail_box_mut(detail).clear_input();
+        }
+    }
+
+    // sets all input modes to the passed mode
+    pub fn set_all_input_modes(&mut self, new_mode: InputMode) {
+        self.name_box.set_input_mode(new_mode.clone());
+        self.format_picker.set_input_mode(new_mode.clone());
+        for detail in [Detail::Speed, Detail::Size, Detail::Bitrate, Detail::Codec] {
+            self.detail_box_mut(detail).set_input_mode(new_mode.clone());
+        }
+    }
+
+    // puts the focus on the name box, like opening the popup fresh
+    pub fn focus_first_field(&mut self) {
+        self.set_all_input_modes(InputMode::Normal);
+        self.focus = MediaTypeField::Name;
+        self.name_box.set_input_mode(InputMode::Editing);
+    }
+
+    // "Tab" moves to the next field: the name, the format, then the format's details
+    pub fn focus_next_field(&mut self) {
+        let details = self.get_format().map_or(&[][..], Format::details);
+        self.focus = match self.focus {
+            MediaTypeField::Name => MediaTypeField::Format,
+            MediaTypeField::Format => details.first().map_or(MediaTypeField::Name, |&detail| {
+                MediaTypeField::Detail(detail)
+            }),
+            MediaTypeField::Detail(detail) => details
+                .iter()
+                .skip_while(|&&other| other != detail)
+                .nth(1)
+                .map_or(MediaTypeField::Name, |&next| MediaTypeField::Detail(next)),
+        };
+        self.set_all_input_modes(InputMode::Normal);
+        match self.focus {
+            MediaTypeField::Name => self.name_box.set_input_mode(InputMode::Editing),
+            MediaTypeField::Format => self.format_picker.set_input_mode(InputMode::Editing),
+            MediaTypeField::Detail(detail) => self
+                .detail_box_mut(detail)
+                .set_input_mode(InputMode::Editing),
+        }
+    }
+
+    // edits the focused box with a key, or picks a format
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        match self.focus {
+            MediaTypeField::Name => {
+                self.name_box.handle_key(key_event);
+            }
+            MediaTypeField::Format => {
+                self.format_picker.handle_key(key_event);
+            }
+            MediaTypeField::Detail(detail) => {
+                self.detail_box_mut(detail).handle_key(key_event);
+            }
+        }
+    }
+
+    // pasted text goes into the focused box, or narrows the formats down
+    pub fn paste(&mut self, text: &str) {
+        match self.focus {
+            MediaTypeField::Name => self.name_box.insert_str(text),
+            MediaTypeField::Format => self.format_picker.insert_str(text),
+            MediaTypeField::Detail(detail) => self.detail_box_mut(detail).insert_str(text),
+        }
+    }
+
+    pub fn has_name(&self) -> bool {
+        !self.name_box.get_input().trim().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyModifiers};
+
+    fn type_text(popup: &mut MediaTypePopup, text: &str) {
+        for c in text.chars() {
+            popup.handle_key(KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE));
+        }
+    }
+
+    #[test]
+    fn test_details_follow_the_picked_format() {
+        let mut popup = MediaTypePopup::default();
+        popup.set_taken(&[MediaType {
+            aliases: vec!["LP".to_owned()],
+            ..MediaType::new("Vinyl", Format::Vinyl)
+        }]);
+        popup.focus_first_field();
+        type_text(&mut popup, "lp");
+        popup.focus_next_field();
+        type_text(&mut popup, "vin");
+        assert_eq!(popup.get_format(), Some(Format::Vinyl));
+        popup.focus_next_field();
+        type_text(&mut popup, "78 rpm");
+        popup.focus_next_field();
+        assert_eq!(popup.focus, MediaTypeField::Detail(Detail::Size));
+        popup.focus_next_field();
+        assert_eq!(popup.focus, MediaTypeField::Name);
+
+        // names and aliases on the list are taken, ignoring case
+        let Err(AppError::Invalid(errors)) = popup.get_media_type_from_input() else {
+            panic!("a taken name was accepted");
+        };
+        assert_eq!(
+            errors[0].to_string(),
+            "media_type lp is already on the list"
+        );
+
+        popup.name_box.set_input("Shellac".to_owned());
+        let shellac = popup.get_media_type_from_input().unwrap();
+        assert_eq!(shellac.details(), "Vinyl, 78 rpm");
+        // details of another format aren't kept
+        popup.format_picker.set_input("CD".to_owned());
+        let shellac = popup.get_media_type_from_input().unwrap();
+        assert_eq!(shellac.speed, None);
+    }
+}
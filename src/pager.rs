@@ -0,0 +1,352 @@
+// A window onto a list of songs too long to load at once. Only the rows around the ones on screen
+// are kept, and pages are fetched from the database as the table scrolls to them.
+
+use crate::{
+    database::{LibraryStore, PageCursor},
+    fuzzy::{self, BestMatches, Highlights},
+    query::Query,
+    song::Song,
+    sort::Sort,
+};
+use std::{collections::VecDeque, ops::Range};
+
+// how many songs one trip to the database fetches
+const PAGE_SIZE: usize = 200;
+// how many songs are kept, the ones furthest from the screen are dropped first
+const MAX_CACHED: usize = 1000;
+// how many of the best matches a fuzzy search lists
+pub const FUZZY_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Default)]
+pub struct SongPager {
+    query: Option<Query>, // the songs listed, None if they were all loaded up front
+    sort: Sort,           // the order the query's songs are listed in
+    total: usize,         // how many songs there are in the whole list
+    offset: usize,        // the index of rows[0] in the whole list
+    rows: VecDeque<Song>, // the cached songs, offset..offset + rows.len()
+    highlights: Vec<Highlights>, // for a loaded list, what fuzzy search matched in each song
+}
+
+impl SongPager {
+    // the songs matching a query in the sort order, with the first page loaded
+    pub async fn open(
+        store: &dyn LibraryStore,
+        query: Query,
+        sort: Sort,
+    ) -> Result<Self, sqlx::Error> {
+        let total = store.count_songs(&query).await? as usize;
+        let rows = store
+            .get_songs_page(&query, &sort, PageCursor::First, PAGE_SIZE as u32)
+            .await?;
+        Ok(Self {
+            query: Some(query),
+            sort,
+            total,
+            offset: 0,
+            rows: rows.into(),
+            highlights: Vec::new(),
+        })
+    }
+
+    // every song ranked against a fuzzy query, best match first. The library is scored a page at a
+    // time and only the best FUZZY_LIMIT matches are kept, so a huge library isn't held in memory.
+    // A query with no words matches every song, so they're all listed in the sort order instead.
+    pub async fn fuzzy(
+        store: &dyn LibraryStore,
+        query: &str,
+        sort: Sort,
+    ) -> Result<Self, sqlx::Error> {
+        let everything = Query::default();
+        if fuzzy::is_blank(query) {
+            return Self::open(store, everything, sort).await;
+        }
+        let sort = Sort::default();
+        let mut best = BestMatches::new(query, FUZZY_LIMIT);
+        let mut cursor = PageCursor::First;
+        loop {
+            let page = store
+                .get_songs_page(&everything, &sort, cursor, PAGE_SIZE as u32)
+                .await?;
+            let Some(last) = page.last() else {
+                break;
+            };
+            cursor = PageCursor::After(last.id);
+            best.add(page);
+        }
+        let (songs, highlights) = best
+            .into_matches()
+            .into_iter()
+            .map(|found| (found.song, found.highlights))
+            .unzip();
+        Ok(Self::loaded(songs, highlights))
+    }
+
+    // a list that's already in memory, like an album's songs or fuzzy search results
+    pub fn loaded(songs: Vec<Song>, highlights: Vec<Highlights>) -> Self {
+        Self {
+            query: None,
+            sort: Sort::default(),
+            total: songs.len(),
+            offset: 0,
+            rows: songs.into(),
+            highlights,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.total
+    }
+
+    // the song at an index of the whole list, if it's cached
+    pub fn get(&self, index: usize) -> Option<&Song> {
+        index
+            .checked_sub(self.offset)
+            .and_then(|index| self.rows.get(index))
+    }
+
+    pub fn highlights(&self, index: usize) -> Option<&Highlights> {
+        self.highlights.get(index)
+    }
+
+    // the order the songs are listed in, None if they came in an order of their own
+    pub fn sort(&self) -> Option<&Sort> {
+        self.query.as_ref().map(|_| &self.sort)
+    }
+
+    // every song in the list in the same order, for exporting it
+    pub async fn all(&self, store: &dyn LibraryStore) -> Result<Vec<Song>, sqlx::Error> {
+        let Some(query) = &self.query else {
+            return Ok(self.rows.iter().cloned().collect());
+        };
+        let mut songs: Vec<Song> = Vec::new();
+        loop {
+            let cursor = match songs.last() {
+                Some(song) => PageCursor::After(song.id),
+                None => PageCursor::First,
+            };
+            let page = store
+                .get_songs_page(query, &self.sort, cursor, PAGE_SIZE as u32)
+                .await?;
+            if page.is_empty() {
+                return Ok(songs);
+            }
+            songs.extend(page);
+        }
+    }
+
+    // the ids of the songs in a range of rows, like the ones a visual selection spans.
+    // Rows that aren't cached are fetched a page at a time.
+    pub async fn ids(
+        &mut self,
+        store: &dyn LibraryStore,
+        rows: Range<usize>,
+    ) -> Result<Vec<u32>, sqlx::Error> {
+        let mut ids = Vec::new();
+        for start in rows.clone().step_by(PAGE_SIZE) {
+            let end = (start + PAGE_SIZE).min(rows.end);
+            self.load(store, start, end - start).await?;
+            ids.extend((start..end).filter_map(|index| self.get(index).map(|song| song.id)));
+        }
+        Ok(ids)
+    }
+
+    // whether rows start..start + count are all cached, so load has nothing to fetch
+    pub fn is_cached(&self, start: usize, count: usize) -> bool {
+        if self.query.is_none() {
+            return true;
+        }
+        let end = (start + count).min(self.total);
+        start.min(end) >= self.offset && end <= self.offset + self.rows.len()
+    }
+
+    // makes sure rows start..start + count are cached, fetching pages next to the cached ones.
+    // A jump to the start or the end of the list starts the cache over from there.
+    pub async fn load(
+        &mut self,
+        store: &dyn LibraryStore,
+        start: usize,
+        count: usize,
+    ) -> Result<(), sqlx::Error> {
+        if self.is_cached(start, count) {
+            return Ok(());
+        }
+        let Some(query) = self.query.clone() else {
+            return Ok(());
+        };
+        let end = (start + count).min(self.total);
+        let start = start.min(end);
+        let cached_end = self.offset + self.rows.len();
+
+        let far_away = end + PAGE_SIZE < self.offset || start > cached_end + PAGE_SIZE;
+        if far_away && start < PAGE_SIZE {
+            self.reset(store, PageCursor::First).await?;
+        } else if far_away && end + PAGE_SIZE > self.total {
+            self.reset(store, PageCursor::Last).await?;
+        }
+
+        // pages after the cache, dropping rows from the front to stay under MAX_CACHED
+        while self.offset + self.rows.len() < end {
+            let cursor = match self.rows.back() {
+                Some(song) => PageCursor::After(song.id),
+                None => PageCursor::First,
+            };
+            let page = store
+                .get_songs_page(&query, &self.sort, cursor, PAGE_SIZE as u32)
+                .await?;
+            if page.is_empty() {
+                // songs were deleted since the list was counted
+                self.total = self.offset + self.rows.len();
+                break;
+            }
+            self.rows.extend(page);
+            while self.rows.len() > MAX_CACHED && self.offset + PAGE_SIZE <= start {
+                self.rows.pop_front();
+                self.offset += 1;
+            }
+        }
+        // pages before the cache, dropping rows from the back
+        while self.offset > start {
+            let Some(first) = self.rows.front() else {
+                break;
+            };
+            let page = store
+                .get_songs_page(
+                    &query,
+                    &self.sort,
+                    PageCursor::Before(first.id),
+                    PAGE_SIZE as u32,
+                )
+                .await?;
+            if page.is_empty() {
+                // songs were deleted before the cache, so it starts the list now
+                self.total -= self.offset;
+                self.offset = 0;
+                break;
+            }
+            self.offset = self.offset.saturating_sub(page.len());
+            for song in page.into_iter().rev() {
+                self.rows.push_front(song);
+            }
+            while self.rows.len() > MAX_CACHED && self.offset + self.rows.len() > end + PAGE_SIZE {
+                self.rows.pop_back();
+            }
+        }
+        Ok(())
+    }
+
+    // drops the cache and loads the first or last page
+    async fn reset(
+        &mut self,
+        store: &dyn LibraryStore,
+        cursor: PageCursor,
+    ) -> Result<(), sqlx::Error> {
+        let Some(query) = &self.query else {
+            return Ok(());
+        };
+        let page = store
+            .get_songs_page(query, &self.sort, cursor, PAGE_SIZE as u32)
+            .await?;
+        self.offset = match cursor {
+            PageCursor::Last => self.total.saturating_sub(page.len()),
+            _ => 0,
+        };
+        self.rows = page.into();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteStore;
+
+    // a library of 2500 songs titled "Track 1" to "Track 2500", in id order
+    async fn store_with_tracks() -> SqliteStore {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let songs = (1..=2500)
+            .map(|number| Song::new(0, &format!("Track {number}"), "Artist", "Album", 2000, "CD"))
+            .collect();
+        store.add_songs(songs).await.unwrap();
+        store
+    }
+
+    #[tokio::test]
+    async fn test_pages_load_around_the_window() {
+        let store = store_with_tracks().await;
+        let mut pager = SongPager::open(&store, Query::default(), Sort::default())
+            .await
+            .unwrap();
+        assert_eq!(pager.len(), 2500);
+        assert_eq!(pager.get(0).unwrap().title, "Track 1");
+        assert!(pager.get(PAGE_SIZE).is_none());
+
+        // scrolling down fetches the next pages and lets go of the first ones
+        assert!(!pager.is_cached(1150, 40));
+        pager.load(&store, 1150, 40).await.unwrap();
+        assert!(pager.is_cached(1150, 40));
+        assert_eq!(pager.get(1189).unwrap().title, "Track 1190");
+        assert!(pager.get(0).is_none());
+        assert!(pager.rows.len() <= MAX_CACHED + PAGE_SIZE);
+
+        // End jumps straight to the last page, then scrolling up walks back
+        pager.load(&store, 2460, 40).await.unwrap();
+        assert_eq!(pager.get(2499).unwrap().title, "Track 2500");
+        pager.load(&store, 2000, 40).await.unwrap();
+        assert_eq!(pager.get(2000).unwrap().title, "Track 2001");
+    }
+
+    #[tokio::test]
+    async fn test_ids_of_a_range_past_the_cache() {
+        let store = store_with_tracks().await;
+        let mut pager = SongPager::open(&store, Query::default(), Sort::default())
+            .await
+            .unwrap();
+        // a range reaching past the cache is fetched as it goes
+        let ids = pager.ids(&store, 100..1400).await.unwrap();
+        assert_eq!(ids.len(), 1300);
+        assert_eq!(pager.get(1399).unwrap().id, ids[1299]);
+    }
+
+    #[tokio::test]
+    async fn test_a_search_counts_only_its_songs() {
+        let store = store_with_tracks().await;
+        let filtered = SongPager::open(
+            &store,
+            Query::parse("\"track 25\"").unwrap(),
+            Sort::default(),
+        )
+        .await
+        .unwrap();
+        // Track 25, 250-259 and 2500
+        assert_eq!(filtered.len(), 12);
+        assert_eq!(filtered.all(&store).await.unwrap().len(), 12);
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_keeps_only_the_best_matches() {
+        let store = store_with_tracks().await;
+        // every track matches equally well, so the first ones added are kept
+        let pager = SongPager::fuzzy(&store, "trakc", Sort::default())
+            .await
+            .unwrap();
+        assert_eq!(pager.len(), FUZZY_LIMIT);
+        assert_eq!(pager.get(0).unwrap().title, "Track 1");
+        assert_eq!(pager.get(FUZZY_LIMIT - 1).unwrap().title, "Track 1000");
+
+        let pager = SongPager::fuzzy(&store, "track 2500", Sort::default())
+            .await
+            .unwrap();
+        assert_eq!(pager.get(0).unwrap().title, "Track 2500");
+    }
+
+    #[tokio::test]
+    async fn test_an_empty_fuzzy_query_lists_every_song() {
+        let store = store_with_tracks().await;
+        let mut pager = SongPager::fuzzy(&store, " ", Sort::default())
+            .await
+            .unwrap();
+        assert_eq!(pager.len(), 2500);
+        pager.load(&store, 2460, 40).await.unwrap();
+        assert_eq!(pager.get(2499).unwrap().title, "Track 2500");
+    }
+}
@@ -0,0 +1,336 @@
+// A list to pick a value from instead of typing it, for the media types: songs and albums can only
+// get one of the types on the managed list. It looks like a text box until it's focused, then opens
+// the list under itself. Typing narrows the list down to the choices that match by value or alias,
+// ranked the same way as the suggestions in the song popups.
+
+use crate::{complete, text_box::InputMode};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{
+        block::{Position, Title},
+        Block, Borders, Clear, List, ListItem, ListState, Paragraph,
+    },
+};
+use unicode_width::UnicodeWidthStr;
+
+// one of the values that can be picked, with what to show next to it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choice {
+    pub value: String,
+    pub detail: String,
+    pub aliases: Vec<String>, // other spellings typing finds it by
+}
+
+#[derive(Debug, Clone)]
+pub struct Picker {
+    title: String,
+    choices: Vec<Choice>,
+    selected: Option<usize>, // the picked choice, None until one is
+    filter: String,          // what's been typed to narrow the list down
+    input_mode: InputMode,
+}
+
+impl Picker {
+    pub const fn new(title: String) -> Self {
+        Self {
+            title,
+            choices: Vec::new(),
+            selected: None,
+            filter: String::new(),
+            input_mode: InputMode::Normal,
+        }
+    }
+
+    // replaces the choices, keeping the picked value if it's still one of them
+    pub fn set_choices(&mut self, choices: Vec<Choice>) {
+        let value = self.get_input().to_owned();
+        self.choices = choices;
+        self.set_input(value);
+    }
+
+    // the picked value, empty if there isn't one
+    pub fn get_input(&self) -> &str {
+        self.selected
+            .map_or("", |index| self.choices[index].value.as_str())
+    }
+
+    // picks the choice with this value or alias, ignoring case. A value that isn't a choice, like
+    // a song's that isn't on the list the popup was given, is added so editing the song keeps it.
+    pub fn set_input(&mut self, value: String) {
+        self.filter.clear();
+        let value = value.trim();
+        if value.is_empty() {
+            self.selected = None;
+            return;
+        }
+        let found = self
+            .choices
+            .iter()
+            .position(|choice| choice.value.eq_ignore_ascii_case(value))
+            .or_else(|| {
+                self.choices.iter().position(|choice| {
+                    choice
+                        .aliases
+                        .iter()
+                        .any(|alias| alias.eq_ignore_ascii_case(value))
+                })
+            });
+        self.selected = found.or_else(|| {
+            self.choices.push(Choice {
+                value: value.to_owned(),
+                detail: "not on the list".to_owned(),
+                aliases: Vec::new(),
+            });
+            Some(self.choices.len() - 1)
+        });
+    }
+
+    pub fn clear_input(&mut self) {
+        self.selected = None;
+        self.filter.clear();
+    }
+
+    // what's been typed is forgotten when the picker loses focus, the picked value stays
+    pub fn set_input_mode(&mut self, input_mode: InputMode) {
+        if input_mode == InputMode::Normal {
+            self.filter.clear();
+        }
+        self.input_mode = input_mode;
+    }
+
+    pub fn get_input_mode(&self) -> InputMode {
+        self.input_mode.clone()
+    }
+
+    // the indexes of the choices matching what's been typed, best first, or of every choice
+    fn matches(&self) -> Vec<usize> {
+        if self.filter.trim().is_empty() {
+            return (0..self.choices.len()).collect();
+        }
+        let mut ranked: Vec<(usize, usize)> = self
+            .choices
+            .iter()
+            .enumerate()
+            .filter_map(|(index, choice)| {
+                let rank = std::iter::once(&choice.value)
+                    .chain(&choice.aliases)
+                    .filter_map(|value| complete::rank(self.filter.trim(), value))
+                    .min()?;
+                Some((rank, index))
+            })
+            .collect();
+        ranked.sort();
+        ranked.into_iter().map(|(_, index)| index).collect()
+    }
+
+    // picks a choice with a key: Up and Down go through the list (the matching part of it once
+    // something's been typed), Home and End jump to its ends, typing narrows it down and picks the
+    // best match, and Backspace or Delete with nothing typed clears the pick.
+    // Returns false if the key isn't one for picking, so the caller can use it for something else.
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> bool {
+        let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        let matches = self.matches();
+        let position = self
+            .selected
+            .and_then(|selected| matches.iter().position(|&index| index == selected));
+        let count = matches.len();
+        match key_event.code {
+            KeyCode::Up | KeyCode::Down | KeyCode::Home | KeyCode::End if count == 0 => {}
+            KeyCode::Down => {
+                let next = position.map_or(0, |position| (position + 1) % count);
+                self.selected = Some(matches[next]);
+            }
+            KeyCode::Up => {
+                let previous =
+                    position.map_or(count - 1, |position| (position + count - 1) % count);
+                self.selected = Some(matches[previous]);
+            }
+            KeyCode::Home => self.selected = matches.first().copied(),
+            KeyCode::End => self.selected = matches.last().copied(),
+            KeyCode::Char('u' | 'w') if control => self.filter.clear(),
+            KeyCode::Char(_) if control => return false,
+            KeyCode::Char(new_char) => self.narrow(&new_char.to_string()),
+            KeyCode::Backspace if !self.filter.is_empty() => {
+                self.filter.pop();
+                if !self.filter.is_empty() {
+                    self.selected = self.matches().first().copied();
+                }
+            }
+            KeyCode::Backspace | KeyCode::Delete => self.clear_input(),
+            _ => return false,
+        }
+        true
+    }
+
+    // pasted text narrows the list down like typing it would
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        self.narrow(&text);
+    }
+
+    fn narrow(&mut self, text: &str) {
+        self.filter.push_str(text);
+        self.selected = self.matches().first().copied();
+    }
+
+    /// Draws the picked value in a block like a TextBox, with what's been typed instead while
+    /// narrowing the list down. Turns yellow in Editing mode.
+    pub fn render(&self, frame: &mut Frame, block: Block, area: Rect) {
+        let inner = block.inner(area);
+        let label = format!(" {}: ", self.title);
+        let style = match self.input_mode {
+            InputMode::Normal => Style::new(),
+            InputMode::Editing => Style::new().yellow().bold(),
+        };
+        let selected = self.selected.map(|index| &self.choices[index]);
+        let line = match (self.filter.is_empty(), selected) {
+            (true, Some(choice)) if choice.detail != choice.value => Line::from(vec![
+                Span::styled(label.clone() + &choice.value, style),
+                Span::styled(format!("  {}", choice.detail), Style::new().dark_gray()),
+            ]),
+            (true, _) => Line::from(Span::styled(label.clone() + self.get_input(), style)),
+            (false, Some(choice)) => Line::from(vec![
+                Span::styled(label.clone() + &self.filter, style),
+                Span::styled(format!(" → {}", choice.value), Style::new().dark_gray()),
+            ]),
+            (false, None) => Line::from(vec![
+                Span::styled(label.clone() + &self.filter, style),
+                Span::styled(" (no match)", Style::new().dark_gray()),
+            ]),
+        };
+        frame.render_widget(Paragraph::new(line).left_aligned().block(block), area);
+        if self.input_mode == InputMode::Editing && inner.width > 0 && inner.height > 0 {
+            let column = label.width() + self.filter.width();
+            let x = inner.x + column.min(usize::from(inner.width) - 1) as u16;
+            frame.set_cursor(x, inner.y);
+        }
+    }
+
+    /// Draws the list under the picker's box while it's being edited, or over it if there's more
+    /// room there. Drawn after the boxes around it, since it covers them.
+    pub fn render_list(&self, frame: &mut Frame, box_area: Rect) {
+        if self.input_mode != InputMode::Editing {
+            return;
+        }
+        let matches = self.matches();
+        let items: Vec<ListItem> = match matches.is_empty() {
+            true if self.filter.is_empty() => vec![ListItem::new(" Nothing to pick from".italic())],
+            true => vec![ListItem::new(
+                format!(" Nothing matches \"{}\"", self.filter).italic(),
+            )],
+            false => matches
+                .iter()
+                .map(|&index| {
+                    let choice = &self.choices[index];
+                    ListItem::new(Line::from(vec![
+                        format!(" {} ", choice.value).into(),
+                        choice.detail.clone().dark_gray(),
+                    ]))
+                })
+                .collect(),
+        };
+        let screen = frame.size();
+        let below = screen.bottom().saturating_sub(box_area.bottom());
+        let above = box_area.top().saturating_sub(screen.top());
+        let wanted = items.len() as u16 + 2;
+        let area = if below >= wanted.min(6) || below >= above {
+            let height = wanted.min(below);
+            Rect::new(box_area.x, box_area.bottom(), box_area.width, height)
+        } else {
+            let height = wanted.min(above);
+            Rect::new(box_area.x, box_area.top() - height, box_area.width, height)
+        };
+        if area.height < 3 {
+            return;
+        }
+        let instructions = Title::from(Line::from(vec![
+            " Pick ".into(),
+            "<Up/Down>".yellow().bold(),
+            " Clear ".into(),
+            "<Del> ".yellow().bold(),
+        ]));
+        let block = Block::default().borders(Borders::ALL).title(
+            instructions
+                .alignment(Alignment::Right)
+                .position(Position::Bottom),
+        );
+        let list = List::new(items)
+            .highlight_style(Style::new().reversed())
+            .block(block);
+        let selected = self
+            .selected
+            .and_then(|selected| matches.iter().position(|&index| index == selected));
+        let mut state = ListState::default().with_selected(selected);
+        frame.render_widget(Clear, area);
+        frame.render_stateful_widget(list, area, &mut state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(picker: &mut Picker, code: KeyCode) {
+        picker.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+    }
+
+    fn picker() -> Picker {
+        let choice = |value: &str, aliases: &[&str]| Choice {
+            value: value.to_owned(),
+            detail: String::new(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+        };
+        let mut picker = Picker::new("Media Type".to_owned());
+        picker.set_choices(vec![
+            choice("7\" Single", &["45"]),
+            choice("CD", &[]),
+            choice("Vinyl", &["LP", "12in"]),
+        ]);
+        picker
+    }
+
+    #[test]
+    fn test_picking_with_keys() {
+        let mut picker = picker();
+        assert_eq!(picker.get_input(), "");
+        press(&mut picker, KeyCode::Down);
+        assert_eq!(picker.get_input(), "7\" Single");
+        press(&mut picker, KeyCode::Up);
+        assert_eq!(picker.get_input(), "Vinyl");
+        press(&mut picker, KeyCode::Down);
+        assert_eq!(picker.get_input(), "7\" Single");
+        press(&mut picker, KeyCode::End);
+        assert_eq!(picker.get_input(), "Vinyl");
+        press(&mut picker, KeyCode::Delete);
+        assert_eq!(picker.get_input(), "");
+        assert!(!picker.handle_key(KeyEvent::new(KeyCode::Tab, KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn test_typing_narrows_the_list() {
+        let mut picker = picker();
+        // aliases find their type
+        picker.insert_str("lp");
+        assert_eq!(picker.get_input(), "Vinyl");
+        press(&mut picker, KeyCode::Backspace);
+        press(&mut picker, KeyCode::Backspace);
+        press(&mut picker, KeyCode::Char('c'));
+        assert_eq!(picker.get_input(), "CD");
+        assert_eq!(picker.matches().len(), 1);
+        press(&mut picker, KeyCode::Char('x'));
+        press(&mut picker, KeyCode::Char('y'));
+        assert_eq!(picker.get_input(), "");
+        // losing focus forgets what was typed
+        picker.set_input_mode(InputMode::Normal);
+        assert_eq!(picker.matches().len(), 3);
+
+        picker.set_input("12IN".to_owned());
+        assert_eq!(picker.get_input(), "Vinyl");
+        // a value that isn't on the list is kept, to not lose a song's
+        picker.set_input("Minidisc".to_owned());
+        assert_eq!(picker.get_input(), "Minidisc");
+        picker.set_choices(picker.choices[..3].to_vec());
+        assert_eq!(picker.get_input(), "Minidisc");
+    }
+}
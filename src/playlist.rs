@@ -0,0 +1,30 @@
+// This is the program's model of a Playlist, a named and ordered list of songs.
+// song_count is only filled in when playlists are read from the database.
+
+use crate::song::Song;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Playlist {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub song_count: i64,
+}
+impl Playlist {
+    pub fn new(id: u32, name: &str, description: &str) -> Self {
+        Self {
+            id,
+            name: name.to_owned(),
+            description: description.to_owned(),
+            song_count: 0,
+        }
+    }
+}
+
+// one song on a playlist, in playlist order. entry_id tells apart the same song added twice.
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct PlaylistEntry {
+    pub entry_id: u32,
+    #[sqlx(flatten)]
+    pub song: Song,
+}
@@ -0,0 +1,112 @@
+// this file contains the playlist popup widget logic, the playlist version of album_popup.rs
+
+use crate::{
+    database::LibraryStore,
+    error::{AppError, Context},
+    playlist::Playlist,
+    popup::PopupMode,
+    text_box::{InputMode, TextBox},
+};
+use crossterm::event::KeyEvent;
+
+// PlaylistPopup stores all state info
+#[derive(Debug, Clone)]
+pub struct PlaylistPopup {
+    mode: PopupMode,  // mode
+    playlist_id: u32, // id for playlist to edit or create
+    // text boxes for each input field
+    pub name_box: TextBox,
+    pub description_box: TextBox,
+}
+
+impl PlaylistPopup {
+    pub fn new(mode: PopupMode, playlist_id: u32) -> Self {
+        Self {
+            mode,
+            playlist_id,
+            name_box: TextBox::new("Name".to_owned()),
+            description_box: TextBox::new("Description".to_owned()),
+        }
+    }
+
+    // adds or updates the playlist, returns the number of rows affected
+    pub async fn submit(&mut self, store: &dyn LibraryStore) -> Result<u64, AppError> {
+        // store a playlist from the input
+        let new_playlist = Playlist::new(
+            0,
+            self.name_box.get_input(),
+            self.description_box.get_input(),
+        );
+        match self.mode {
+            PopupMode::New => store
+                .add_playlist(new_playlist)
+                .await
+                .context("add the playlist"),
+            PopupMode::Edit => store
+                .update_playlist(self.playlist_id, new_playlist)
+                .await
+                .context("update the playlist"),
+        }
+    }
+
+    // clears all input fields in text boxes
+    pub fn clear_all_boxes(&mut self) {
+        self.name_box.clear_input();
+        self.description_box.clear_input();
+    }
+
+    // only the name is required, a playlist doesn't need a description
+    pub fn has_name(&self) -> bool {
+        !self.name_box.get_input().trim().is_empty()
+    }
+
+    // sets all textbox input modes to the passed mode
+    pub fn set_all_input_modes(&mut self, new_mode: InputMode) {
+        self.name_box.set_input_mode(new_mode.clone());
+        self.description_box.set_input_mode(new_mode);
+    }
+
+    // puts the focus on the name box, like opening the popup fresh
+    pub fn focus_first_field(&mut self) {
+        self.set_all_input_modes(InputMode::Normal);
+        self.name_box.set_input_mode(InputMode::Editing);
+    }
+
+    // "Tab" switches between the two boxes
+    pub fn focus_next_field(&mut self) {
+        if self.name_box.get_input_mode() == InputMode::Editing {
+            self.name_box.set_input_mode(InputMode::Normal);
+            self.description_box.set_input_mode(InputMode::Editing);
+        } else {
+            self.focus_first_field();
+        }
+    }
+
+    // the text box with focus
+    pub fn focused_box(&mut self) -> &mut TextBox {
+        if self.description_box.get_input_mode() == InputMode::Editing {
+            &mut self.description_box
+        } else {
+            &mut self.name_box
+        }
+    }
+
+    // edits the focused box with a key
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        self.focused_box().handle_key(key_event);
+    }
+
+    // pushes the data from the playlist's fields to each text box
+    pub fn populate_textboxes_with_playlist(&mut self, playlist: &Playlist) {
+        self.name_box.set_input(playlist.name.clone());
+        self.description_box.set_input(playlist.description.clone());
+    }
+
+    pub fn get_popup_mode(&self) -> PopupMode {
+        self.mode.clone()
+    }
+
+    pub fn set_playlist_id(&mut self, new_id: u32) {
+        self.playlist_id = new_id;
+    }
+}
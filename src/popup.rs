@@ -1,11 +1,18 @@
 // this file contains the popup menu widget logic
 
 use crate::{
-    database::{add_song, update_song},
-    song::Song,
+    album::Album,
+    complete::{Suggestion, Vocabulary},
+    database::LibraryStore,
+    error::{AppError, Context},
+    import::Field,
+    picker::Picker,
+    song::{Song, SongFields},
     text_box::{InputMode, TextBox},
+    validate::{self, FieldError},
 };
-use sqlx::MySqlPool;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::sync::Arc;
 
 // popup modes related to App mode
 #[derive(Debug, Clone)]
@@ -17,14 +24,19 @@ pub enum PopupMode {
 // Popup struct stores all state info
 #[derive(Debug, Clone)]
 pub struct Popup {
-    mode: PopupMode, // mode
-    song_id: u32,    // id for song to edit or create
+    mode: PopupMode,              // mode
+    song_id: u32,                 // id for song to edit or create
+    song_ids: Vec<u32>,           // the marked songs when editing many at once, empty for one song
+    show_errors: bool,            // set when submitting failed, so empty boxes are flagged too
+    vocabulary: Arc<Vocabulary>,  // what's in the library already, to suggest
+    suggestions: Vec<Suggestion>, // for the box being typed into
+    suggestion: usize,            // the selected one, that Tab accepts
     // text boxes for each input field
     pub title_box: TextBox,
     pub artist_box: TextBox,
     pub album_box: TextBox,
     pub release_year_box: TextBox,
-    pub media_type_box: TextBox,
+    pub media_type_picker: Picker, // media types come from their list
 }
 
 impl Popup {
@@ -33,32 +45,38 @@ impl Popup {
         Self {
             mode,
             song_id,
+            song_ids: Vec::new(),
+            show_errors: false,
+            vocabulary: Arc::default(),
+            suggestions: Vec::new(),
+            suggestion: 0,
             title_box: TextBox::new("Title".to_owned()),
             artist_box: TextBox::new("Artist".to_owned()),
             album_box: TextBox::new("Album".to_owned()),
-            release_year_box: TextBox::new("Year".to_owned()),
-            media_type_box: TextBox::new("Media Type".to_owned()),
+            release_year_box: TextBox::new("Year".to_owned()).numeric(),
+            media_type_picker: Picker::new("Media Type".to_owned()),
         }
     }
-    pub async fn submit(&mut self, pool: &MySqlPool) {
-        // submit all boxes and store a song from the input
+    // adds or updates the song, returns the number of songs changed
+    pub async fn submit(&mut self, store: &dyn LibraryStore) -> Result<u64, AppError> {
+        // editing many songs only changes the fields that were filled in
+        if self.is_bulk() {
+            let fields = self.get_fields_from_input()?;
+            self.submit_all_boxes();
+            return store
+                .update_songs(&self.song_ids, &fields)
+                .await
+                .context("update the songs");
+        }
+        // store a song from the input and submit all boxes
+        let new_song = self.get_song_from_input()?;
         self.submit_all_boxes();
-        let new_song = self.get_song_from_input();
         match self.mode {
-            // if mode is New, add new song and print any errors
-            PopupMode::New => {
-                match add_song(pool, new_song).await {
-                    Ok(_) => (),
-                    Err(error) => eprintln!("Error adding song: {error}"),
-                };
-            }
-            // if Edit mode, update the song and print any errors
-            PopupMode::Edit => {
-                match update_song(pool, self.song_id, new_song).await {
-                    Ok(_) => (),
-                    Err(error) => eprintln!("Error updating song: {error}"),
-                };
-            }
+            PopupMode::New => store.add_song(new_song).await.context("add the song"),
+            PopupMode::Edit => store
+                .update_song(self.song_id, new_song)
+                .await
+                .context("update the song"),
         }
     }
 
@@ -68,7 +86,8 @@ impl Popup {
         self.artist_box.submit_message();
         self.album_box.submit_message();
         self.release_year_box.submit_message();
-        self.media_type_box.submit_message();
+        self.media_type_picker.set_input_mode(InputMode::Normal);
+        self.show_errors = false;
     }
 
     // clears all input fields in text boxes
@@ -77,56 +96,260 @@ impl Popup {
         self.artist_box.clear_input();
         self.album_box.clear_input();
         self.release_year_box.clear_input();
-        self.media_type_box.clear_input();
+        self.media_type_picker.clear_input();
+        self.show_errors = false;
+        self.suggestions.clear();
+    }
+
+    // the text typed into each box, in the order of Field::ALL
+    fn get_inputs(&self) -> [&str; 5] {
+        [
+            self.title_box.get_input(),
+            self.artist_box.get_input(),
+            self.album_box.get_input(),
+            self.release_year_box.get_input(),
+            self.media_type_picker.get_input(),
+        ]
+    }
+
+    // returns a checked song from the text boxes, or everything wrong with them
+    fn get_song_from_input(&self) -> Result<Song, AppError> {
+        validate::parse_song(self.get_inputs()).map_err(AppError::Invalid)
+    }
+
+    // the fields typed into the boxes, leaving out the empty ones
+    fn get_fields_from_input(&self) -> Result<SongFields, AppError> {
+        validate::parse_fields(self.get_inputs()).map_err(AppError::Invalid)
+    }
+
+    // every problem with the input: empty boxes only count when editing one song
+    fn get_field_errors(&self) -> Vec<FieldError> {
+        let result = match self.is_bulk() {
+            true => validate::parse_fields(self.get_inputs()).map(|_| ()),
+            false => validate::parse_song(self.get_inputs()).map(|_| ()),
+        };
+        result.err().unwrap_or_default()
+    }
+
+    // the problem to show under a box. Boxes nothing was typed into yet are left alone until
+    // submitting fails, so a new song doesn't open covered in errors.
+    pub fn get_error(&self, field: Field) -> Option<FieldError> {
+        let typed = !self.get_inputs()[field as usize].is_empty();
+        self.get_field_errors()
+            .into_iter()
+            .find(|error| error.field == field)
+            .filter(|_| typed || self.show_errors)
+    }
+
+    // flags every problem with the input, after submitting it failed
+    pub fn show_errors(&mut self) {
+        self.show_errors = true;
+    }
+
+    // the text box of a field, None for the media type's picker
+    pub fn text_box(&self, field: Field) -> Option<&TextBox> {
+        match field {
+            Field::Title => Some(&self.title_box),
+            Field::Artist => Some(&self.artist_box),
+            Field::Album => Some(&self.album_box),
+            Field::ReleaseYear => Some(&self.release_year_box),
+            Field::MediaType => None,
+        }
+    }
+
+    fn text_box_mut(&mut self, field: Field) -> Option<&mut TextBox> {
+        match field {
+            Field::Title => Some(&mut self.title_box),
+            Field::Artist => Some(&mut self.artist_box),
+            Field::Album => Some(&mut self.album_box),
+            Field::ReleaseYear => Some(&mut self.release_year_box),
+            Field::MediaType => None,
+        }
+    }
+
+    fn get_input_mode(&self, field: Field) -> InputMode {
+        match self.text_box(field) {
+            Some(text_box) => text_box.get_input_mode(),
+            None => self.media_type_picker.get_input_mode(),
+        }
+    }
+
+    fn set_input_mode(&mut self, field: Field, new_mode: InputMode) {
+        match self.text_box_mut(field) {
+            Some(text_box) => text_box.set_input_mode(new_mode),
+            None => self.media_type_picker.set_input_mode(new_mode),
+        }
+    }
+
+    // the text box being typed into, if any
+    pub fn editing_box(&mut self) -> Option<&mut TextBox> {
+        let field = self.editing_field()?;
+        self.text_box_mut(field)
+    }
+
+    // "Tab" moves from the box being typed into to the next one, wrapping around to the title
+    pub fn focus_next_box(&mut self) {
+        if let Some(field) = self.editing_field() {
+            let next = Field::ALL[(field as usize + 1) % Field::ALL.len()];
+            self.set_input_mode(field, InputMode::Normal);
+            self.set_input_mode(next, InputMode::Editing);
+        }
+        self.update_suggestions();
+    }
+
+    // "Tab" takes the selected suggestion if there is one, and moves to the next box otherwise
+    pub fn complete_or_focus_next(&mut self) {
+        match self.suggestions.is_empty() {
+            true => self.focus_next_box(),
+            false => self.accept_suggestion(),
+        }
+    }
+
+    // edits the box being typed into with a key. Up and Down go through the suggestions,
+    // Right at the end of the box takes one, and Ctrl-Y fills the song in from a picked album.
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        let count = self.suggestions.len();
+        match key_event.code {
+            KeyCode::Down if count > 0 => self.suggestion = (self.suggestion + 1) % count,
+            KeyCode::Up if count > 0 => self.suggestion = (self.suggestion + count - 1) % count,
+            KeyCode::Right
+                if count > 0
+                    && self
+                        .editing_box()
+                        .is_some_and(|text_box| text_box.is_cursor_at_end()) =>
+            {
+                self.accept_suggestion()
+            }
+            KeyCode::Char('y') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.fill_from_album()
+            }
+            _ if self.editing_field() == Some(Field::MediaType) => {
+                self.media_type_picker.handle_key(key_event);
+            }
+            _ => {
+                if let Some(text_box) = self.editing_box() {
+                    if text_box.handle_key(key_event) {
+                        self.update_suggestions();
+                    }
+                }
+            }
+        }
+    }
+
+    // the artists and albums to suggest from, and the media types to pick from
+    pub fn set_vocabulary(&mut self, vocabulary: Arc<Vocabulary>) {
+        self.media_type_picker.set_choices(
+            vocabulary
+                .media_types
+                .iter()
+                .map(|media_type| media_type.choice())
+                .collect(),
+        );
+        self.vocabulary = vocabulary;
+        self.update_suggestions();
+    }
+
+    // the field of the box being typed into
+    pub fn editing_field(&self) -> Option<Field> {
+        Field::ALL
+            .into_iter()
+            .find(|&field| self.get_input_mode(field) == InputMode::Editing)
+    }
+
+    // pasted text goes into the box being typed into, or narrows the media types down
+    pub fn paste(&mut self, text: &str) {
+        match self.editing_field() {
+            Some(Field::MediaType) => self.media_type_picker.insert_str(text),
+            Some(_) => {
+                if let Some(text_box) = self.editing_box() {
+                    text_box.insert_str(text);
+                }
+            }
+            None => return,
+        }
+        self.update_suggestions();
+    }
+
+    fn update_suggestions(&mut self) {
+        self.suggestion = 0;
+        self.suggestions = match self.editing_field() {
+            Some(field) => self
+                .vocabulary
+                .suggest(field, self.get_inputs()[field as usize]),
+            None => Vec::new(),
+        };
+    }
+
+    fn accept_suggestion(&mut self) {
+        if let Some(suggestion) = self.suggestions.get(self.suggestion).cloned() {
+            if let Some(text_box) = self.editing_box() {
+                text_box.set_input(suggestion.value);
+            }
+            self.update_suggestions();
+        }
     }
 
-    // returns a song from textbox input
-    // this function is only called when all boxes have input
-    fn get_song_from_input(&self) -> Song {
-        Song::new(
-            0,
-            &self.title_box.get_mesages().pop().unwrap(),
-            &self.artist_box.get_mesages().pop().unwrap(),
-            &self.album_box.get_mesages().pop().unwrap(),
+    // the suggestions for the box being typed into, and which one is selected
+    pub fn get_suggestions(&self) -> (&[Suggestion], usize) {
+        (&self.suggestions, self.suggestion)
+    }
+
+    // the rest of the selected suggestion after what's been typed, to show faintly after it
+    pub fn get_completion(&self, field: Field) -> &str {
+        let input = self.get_inputs()[field as usize];
+        match self.suggestions.get(self.suggestion) {
+            Some(suggestion) if self.editing_field() == Some(field) => suggestion
+                .value
+                .get(input.len()..)
+                .filter(|_| {
+                    suggestion
+                        .value
+                        .to_lowercase()
+                        .starts_with(&input.to_lowercase())
+                })
+                .unwrap_or_default(),
+            _ => "",
+        }
+    }
+
+    // an existing album typed into the album box whose artist, year or media type differ from
+    // the other boxes', which Ctrl-Y copies over
+    pub fn get_album_offer(&self) -> Option<&Album> {
+        if self.editing_field() != Some(Field::Album) {
+            return None;
+        }
+        let [_, artist, album, year, media_type] = self.get_inputs().map(str::trim);
+        self.vocabulary.find_album(album, artist).filter(|found| {
+            (
+                found.artist.as_str(),
+                found.release_year.to_string().as_str(),
+                found.media_type.as_str(),
+            ) != (artist, year, media_type)
+        })
+    }
+
+    fn fill_from_album(&mut self) {
+        if let Some(album) = self.get_album_offer().cloned() {
+            self.artist_box.set_input(album.artist);
             self.release_year_box
-                .get_mesages()
-                .pop()
-                .unwrap()
-                .parse::<i32>()
-                .unwrap(),
-            &self.media_type_box.get_mesages().pop().unwrap(),
-        )
+                .set_input(album.release_year.to_string());
+            self.media_type_picker.set_input(album.media_type);
+        }
     }
 
     // returns true if any text boxes are in editing mode
     pub fn are_any_boxes_editing_mode(&self) -> bool {
-        let mut result = false;
-        if self.title_box.get_input_mode() == InputMode::Editing {
-            result = true;
-        } else if self.artist_box.get_input_mode() == InputMode::Editing {
-            result = true;
-        } else if self.album_box.get_input_mode() == InputMode::Editing {
-            result = true;
-        } else if self.release_year_box.get_input_mode() == InputMode::Editing {
-            result = true;
-        } else if self.media_type_box.get_input_mode() == InputMode::Editing {
-            result = true;
-        }
-        result
-    }
-
-    // returns true if all boxes have some text in them
-    pub fn do_all_boxes_have_text(&self) -> bool {
-        let mut result = false;
-        if (self.title_box.get_input().len() > 0)
-            && (self.artist_box.get_input().len() > 0)
-            && (self.album_box.get_input().len() > 0)
-            && (self.release_year_box.get_input().len() > 0)
-            && (self.media_type_box.get_input().len() > 0)
-        {
-            result = true;
-        }
-        result
+        self.editing_field().is_some()
+    }
+
+    // returns true if the input is valid: something to change for a bulk edit, a whole song otherwise
+    pub fn can_submit(&self) -> bool {
+        match self.is_bulk() {
+            true => self
+                .get_fields_from_input()
+                .is_ok_and(|fields| !fields.is_empty()),
+            false => self.get_song_from_input().is_ok(),
+        }
     }
 
     // sets all textbox input modes to the passed mode
@@ -135,7 +358,7 @@ impl Popup {
         self.artist_box.set_input_mode(new_mode.clone());
         self.album_box.set_input_mode(new_mode.clone());
         self.release_year_box.set_input_mode(new_mode.clone());
-        self.media_type_box.set_input_mode(new_mode);
+        self.media_type_picker.set_input_mode(new_mode);
     }
 
     // pushes the data from the song's fields to each text box
@@ -145,7 +368,7 @@ impl Popup {
         self.album_box.set_input(song.album.clone());
         self.release_year_box
             .set_input(song.release_year.to_string());
-        self.media_type_box.set_input(song.media_type.clone());
+        self.media_type_picker.set_input(song.media_type.clone());
     }
 
     pub fn get_popup_mode(&self) -> PopupMode {
@@ -155,4 +378,17 @@ impl Popup {
     pub fn set_song_id(&mut self, new_id: u32) {
         self.song_id = new_id;
     }
+
+    // the songs to edit at once, or none to go back to editing one song
+    pub fn set_song_ids(&mut self, new_ids: Vec<u32>) {
+        self.song_ids = new_ids;
+    }
+
+    pub fn is_bulk(&self) -> bool {
+        !self.song_ids.is_empty()
+    }
+
+    pub fn get_song_count(&self) -> usize {
+        self.song_ids.len()
+    }
 }
@@ -0,0 +1,384 @@
+// The search query language. A query like `artist:radiohead media:vinyl year:1990..1999 -title:live`
+// is parsed into terms that all have to match, then compiled to a WHERE clause with ? placeholders.
+// Plain words and "quoted phrases" match any field, like the searchbar always has.
+
+use crate::{error::QueryError, import::Field};
+
+// a query is the terms a song has to match, every one of them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Query {
+    pub terms: Vec<Term>,
+}
+
+// one word, phrase or qualified value. A leading - makes it exclude songs instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    // the text is in any field, year included
+    Anywhere(String),
+    // the text is in one field
+    Contains(Field, String),
+    // the release year is in the range, either end can be left open
+    Year { from: Option<i32>, to: Option<i32> },
+    // the song is on the album with this id, for listing one album's songs. It has no syntax.
+    OnAlbum(u32),
+}
+
+// a value to bind to a placeholder, in order
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Text(String),
+    Integer(i32),
+}
+
+// the columns of SELECT_SONGS a text condition can look in
+fn column(field: Field) -> &'static str {
+    match field {
+        Field::Title => "s.title",
+        Field::Artist => "ar.name",
+        Field::Album => "al.title",
+        Field::ReleaseYear => "s.release_year",
+        Field::MediaType => "s.media_type",
+    }
+}
+
+impl Query {
+    // the songs on one album
+    pub fn on_album(album_id: u32) -> Self {
+        Self {
+            terms: vec![Term {
+                negated: false,
+                condition: Condition::OnAlbum(album_id),
+            }],
+        }
+    }
+
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        let mut terms = Vec::new();
+        let mut chars = input.chars().peekable();
+        loop {
+            while chars.next_if(|c| c.is_whitespace()).is_some() {}
+            if chars.peek().is_none() {
+                break;
+            }
+            // a - on its own is just a word
+            let mut ahead = chars.clone();
+            let negated =
+                ahead.next() == Some('-') && ahead.next().is_some_and(|c| !c.is_whitespace());
+            if negated {
+                chars.next();
+            }
+
+            let mut word = String::new();
+            let mut quoted = false;
+            let mut field = None;
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+                match c {
+                    '"' => {
+                        quoted = true;
+                        loop {
+                            match chars.next() {
+                                Some('"') => break,
+                                Some(c) => word.push(c),
+                                None => return Err(QueryError::UnclosedQuote),
+                            }
+                        }
+                    }
+                    // the first : after a name makes it a field qualifier, titles with a colon need quotes
+                    ':' if field.is_none() && !quoted && !word.is_empty() => {
+                        let name = std::mem::take(&mut word);
+                        match Field::from_name(&name) {
+                            Some(qualifier) => field = Some((qualifier, name)),
+                            None => return Err(QueryError::UnknownField(name)),
+                        }
+                    }
+                    c => word.push(c),
+                }
+            }
+
+            let condition = match field {
+                None => Condition::Anywhere(word),
+                Some((Field::ReleaseYear, _)) => parse_years(&word)?,
+                Some((_, name)) if word.is_empty() && !quoted => {
+                    return Err(QueryError::MissingValue(format!("{}:", name)))
+                }
+                Some((field, _)) => Condition::Contains(field, word),
+            };
+            terms.push(Term { negated, condition });
+        }
+        Ok(Self { terms })
+    }
+
+    // the WHERE condition for SELECT_SONGS and the values for its placeholders, in order
+    pub fn to_sql(&self) -> (String, Vec<SqlValue>) {
+        self.compile(false)
+    }
+
+    // the same for MariaDB, where s.search_text has a FULLTEXT index. Plain words the index holds
+    // are looked up in it, anything else is matched the same as to_sql. The text to rank songs by
+    // relevance with is None if no word used the index.
+    pub fn to_fulltext_sql(&self) -> (String, Vec<SqlValue>, Option<String>) {
+        let (condition, values) = self.compile(true);
+        let relevance: Vec<&str> = self
+            .terms
+            .iter()
+            .filter_map(|term| match &term.condition {
+                Condition::Anywhere(text) if !term.negated && indexed(text) => Some(text.as_str()),
+                _ => None,
+            })
+            .collect();
+        (
+            condition,
+            values,
+            (!relevance.is_empty()).then(|| relevance.join(" ")),
+        )
+    }
+
+    fn compile(&self, fulltext: bool) -> (String, Vec<SqlValue>) {
+        if self.terms.is_empty() {
+            return ("1 = 1".to_owned(), Vec::new());
+        }
+        let mut values = Vec::new();
+        let conditions: Vec<String> = self
+            .terms
+            .iter()
+            .map(|term| {
+                let condition = match &term.condition {
+                    Condition::Anywhere(text) if fulltext && indexed(text) => {
+                        values.push(SqlValue::Text(boolean_query(text)));
+                        "MATCH(s.search_text) AGAINST(? IN BOOLEAN MODE)".to_owned()
+                    }
+                    Condition::Anywhere(text) => {
+                        let fields = Field::ALL.map(|field| {
+                            values.push(SqlValue::Text(like_pattern(text)));
+                            format!("{} LIKE ? ESCAPE '!'", column(field))
+                        });
+                        format!("({})", fields.join(" OR "))
+                    }
+                    Condition::Contains(field, text) => {
+                        values.push(SqlValue::Text(like_pattern(text)));
+                        format!("{} LIKE ? ESCAPE '!'", column(*field))
+                    }
+                    Condition::Year { from, to } => {
+                        let mut bounds = Vec::new();
+                        if let Some(from) = from {
+                            values.push(SqlValue::Integer(*from));
+                            bounds.push("s.release_year >= ?");
+                        }
+                        if let Some(to) = to {
+                            values.push(SqlValue::Integer(*to));
+                            bounds.push("s.release_year <= ?");
+                        }
+                        format!("({})", bounds.join(" AND "))
+                    }
+                    Condition::OnAlbum(album_id) => {
+                        values.push(SqlValue::Integer(*album_id as i32));
+                        "s.album_id = ?".to_owned()
+                    }
+                };
+                if term.negated {
+                    format!("NOT {}", condition)
+                } else {
+                    condition
+                }
+            })
+            .collect();
+        (conditions.join(" AND "), values)
+    }
+}
+
+// InnoDB leaves words shorter than innodb_ft_min_token_size (3 by default) and these stopwords out
+// of a FULLTEXT index, so searching the index for them finds nothing
+const MIN_INDEXED_LENGTH: usize = 3;
+const STOPWORDS: [&str; 35] = [
+    "a", "about", "an", "are", "as", "at", "be", "by", "com", "de", "en", "for", "from", "how",
+    "i", "in", "is", "it", "la", "of", "on", "or", "that", "the", "this", "to", "was", "what",
+    "when", "where", "who", "will", "with", "und", "www",
+];
+
+// true if the index can answer a search for the text. Punctuation isn't indexed either,
+// so "ac/dc" or "100%" are left to LIKE, which matches them as typed.
+fn indexed(text: &str) -> bool {
+    let mut words = text.split_whitespace().peekable();
+    words.peek().is_some()
+        && words.all(|word| {
+            word.chars().all(char::is_alphanumeric)
+                && word.chars().count() >= MIN_INDEXED_LENGTH
+                && !STOPWORDS.contains(&word.to_lowercase().as_str())
+        })
+}
+
+// a boolean mode search that requires the word, or a word starting with it so results show up
+// while it's still being typed. A phrase has to appear as is.
+fn boolean_query(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    match words.as_slice() {
+        [word] => format!("+{}*", word),
+        words => format!("+\"{}\"", words.join(" ")),
+    }
+}
+
+// year:1995, year:1990..1999, year:1990.. or year:..1999
+fn parse_years(value: &str) -> Result<Condition, QueryError> {
+    let year = |year: &str| {
+        year.parse::<i32>()
+            .map_err(|_| QueryError::InvalidYear(value.to_owned()))
+    };
+    match value.split_once("..") {
+        None => {
+            let year = year(value)?;
+            Ok(Condition::Year {
+                from: Some(year),
+                to: Some(year),
+            })
+        }
+        Some(("", "")) => Err(QueryError::InvalidYear(value.to_owned())),
+        Some((from, to)) => Ok(Condition::Year {
+            from: (!from.is_empty()).then(|| year(from)).transpose()?,
+            to: (!to.is_empty()).then(|| year(to)).transpose()?,
+        }),
+    }
+}
+
+// %text% with the LIKE wildcards in the text escaped, so "100%" means the characters 100%
+fn like_pattern(text: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in text.chars() {
+        if matches!(c, '!' | '%' | '_') {
+            pattern.push('!');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(negated: bool, condition: Condition) -> Term {
+        Term { negated, condition }
+    }
+
+    #[test]
+    fn test_parse() {
+        let query = Query::parse(
+            r#"artist:radiohead media:vinyl year:1990..1999 -title:live "exact phrase" lung"#,
+        )
+        .unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                term(
+                    false,
+                    Condition::Contains(Field::Artist, "radiohead".to_owned())
+                ),
+                term(
+                    false,
+                    Condition::Contains(Field::MediaType, "vinyl".to_owned())
+                ),
+                term(
+                    false,
+                    Condition::Year {
+                        from: Some(1990),
+                        to: Some(1999)
+                    }
+                ),
+                term(true, Condition::Contains(Field::Title, "live".to_owned())),
+                term(false, Condition::Anywhere("exact phrase".to_owned())),
+                term(false, Condition::Anywhere("lung".to_owned())),
+            ]
+        );
+
+        let query =
+            Query::parse(r#"  ARTIST:"pink floyd" -"live at" year:..1975 - "re:stacks" "#).unwrap();
+        assert_eq!(
+            query.terms,
+            vec![
+                term(
+                    false,
+                    Condition::Contains(Field::Artist, "pink floyd".to_owned())
+                ),
+                term(true, Condition::Anywhere("live at".to_owned())),
+                term(
+                    false,
+                    Condition::Year {
+                        from: None,
+                        to: Some(1975)
+                    }
+                ),
+                term(false, Condition::Anywhere("-".to_owned())),
+                term(false, Condition::Anywhere("re:stacks".to_owned())),
+            ]
+        );
+        assert_eq!(Query::parse("   ").unwrap(), Query::default());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            Query::parse("colour:blue"),
+            Err(QueryError::UnknownField("colour".to_owned()))
+        );
+        assert_eq!(
+            Query::parse("artist:\"pink floyd"),
+            Err(QueryError::UnclosedQuote)
+        );
+        assert_eq!(
+            Query::parse("war artist:"),
+            Err(QueryError::MissingValue("artist:".to_owned()))
+        );
+        for years in ["year:soon", "year:..", "year:1990..later", "year:"] {
+            assert!(matches!(
+                Query::parse(years),
+                Err(QueryError::InvalidYear(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let (sql, values) = Query::parse("-artist:50% year:1990..").unwrap().to_sql();
+        assert_eq!(
+            sql,
+            "NOT ar.name LIKE ? ESCAPE '!' AND (s.release_year >= ?)"
+        );
+        assert_eq!(
+            values,
+            vec![SqlValue::Text("%50!%%".to_owned()), SqlValue::Integer(1990)]
+        );
+
+        let (sql, values) = Query::parse("1995").unwrap().to_sql();
+        assert_eq!(sql.matches(" OR ").count(), 4);
+        assert_eq!(values.len(), 5);
+        assert_eq!(Query::default().to_sql(), ("1 = 1".to_owned(), Vec::new()));
+    }
+
+    #[test]
+    fn test_to_fulltext_sql() {
+        let (sql, values, relevance) =
+            Query::parse(r#"radiohead "planet telex" ok ac/dc -live artist:the"#)
+                .unwrap()
+                .to_fulltext_sql();
+        // short words, punctuation and qualified fields fall back to LIKE
+        assert_eq!(
+            sql.matches("MATCH(s.search_text) AGAINST(? IN BOOLEAN MODE)")
+                .count(),
+            3
+        );
+        assert_eq!(sql.matches(" LIKE ").count(), 11);
+        assert_eq!(values[0], SqlValue::Text("+radiohead*".to_owned()));
+        assert_eq!(values[1], SqlValue::Text("+\"planet telex\"".to_owned()));
+        // negated words narrow the results but don't make a song more relevant
+        assert_eq!(relevance, Some("radiohead planet telex".to_owned()));
+
+        let (_, _, relevance) = Query::parse("the year:1995").unwrap().to_fulltext_sql();
+        assert_eq!(relevance, None);
+    }
+}
@@ -0,0 +1,353 @@
+// Folder scanning. Audio files under a folder have their tags read into songs, and every file's size
+// and modification time are remembered so a rescan only reads the files that changed since.
+
+use crate::{
+    database::LibraryStore,
+    error::ScanError,
+    song::Song,
+    validate::{self, MAX_NAME_LENGTH, MAX_TITLE_LENGTH, UNKNOWN_YEAR},
+};
+use lofty::{file::FileType, prelude::*};
+use std::{
+    collections::HashMap,
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+// files with these extensions are read, anything else in the folder is ignored
+const EXTENSIONS: [&str; 4] = ["mp3", "flac", "ogg", "m4a"];
+
+// a file as the last scan saw it, modified is in nanoseconds since 1970.
+// song_id is None if its song has been deleted since.
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct ScannedFile {
+    pub path: String,
+    pub song_id: Option<u32>,
+    pub modified: i64,
+    pub size: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrackStatus {
+    New,
+    Changed,
+    Unchanged,
+}
+impl fmt::Display for TrackStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::New => write!(f, "new"),
+            Self::Changed => write!(f, "changed"),
+            Self::Unchanged => write!(f, "unchanged"),
+        }
+    }
+}
+
+// an audio file and the song it holds
+#[derive(Debug)]
+pub struct Track {
+    pub status: TrackStatus,
+    pub file: ScannedFile,
+    pub song: Song,
+    // the file was read this scan, so its size and modification time need saving
+    read: bool,
+}
+
+// everything a scan found, for review before it's saved
+#[derive(Debug, Default)]
+pub struct Scan {
+    pub tracks: Vec<Track>,
+    pub failures: Vec<(PathBuf, String)>,
+}
+impl Scan {
+    pub fn count(&self, status: TrackStatus) -> usize {
+        self.tracks
+            .iter()
+            .filter(|track| track.status == status)
+            .count()
+    }
+
+    // adds the new songs and updates the changed ones, returns the number of files saved
+    pub async fn save(self, store: &dyn LibraryStore) -> Result<u64, sqlx::Error> {
+        let files = self
+            .tracks
+            .into_iter()
+            .filter(|track| track.read)
+            .map(|track| (track.file, track.song))
+            .collect();
+        store.save_scanned_files(files).await
+    }
+}
+
+// finds every audio file under the folder and compares it with the last scan.
+// Files whose size and modification time haven't changed aren't read again.
+pub async fn scan_folder(store: &dyn LibraryStore, folder: &Path) -> Result<Scan, ScanError> {
+    let unreadable = |error| ScanError::Unreadable {
+        path: folder.to_owned(),
+        error,
+    };
+    let folder = folder.canonicalize().map_err(unreadable)?;
+    let mut paths = Vec::new();
+    find_audio_files(&folder, &mut paths).map_err(unreadable)?;
+    paths.sort();
+
+    let known: HashMap<String, ScannedFile> = store
+        .get_scanned_files()
+        .await?
+        .into_iter()
+        .map(|file| (file.path.clone(), file))
+        .collect();
+    let songs: HashMap<u32, Song> = store
+        .get_all_songs()
+        .await?
+        .into_iter()
+        .map(|song| (song.id, song))
+        .collect();
+    let media_types = store.get_media_types().await?;
+
+    let mut scan = Scan::default();
+    for path in paths {
+        let metadata = match fs::metadata(&path) {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                scan.failures.push((path, error.to_string()));
+                continue;
+            }
+        };
+        let file = ScannedFile {
+            path: path.to_string_lossy().into_owned(),
+            song_id: None,
+            modified: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_nanos() as i64),
+            size: metadata.len() as i64,
+        };
+        let previous = known.get(&file.path);
+        let existing_song = previous
+            .and_then(|previous| previous.song_id)
+            .and_then(|song_id| songs.get(&song_id));
+        let untouched = previous.is_some_and(|previous| {
+            (previous.modified, previous.size) == (file.modified, file.size)
+        });
+
+        match existing_song {
+            Some(song) if untouched => scan.tracks.push(Track {
+                status: TrackStatus::Unchanged,
+                file: ScannedFile {
+                    song_id: Some(song.id),
+                    ..file
+                },
+                song: song.clone(),
+                read: false,
+            }),
+            // the song was deleted from the library, leave it deleted until the file changes
+            None if untouched => {}
+            _ => match read_tags(&path).and_then(|song| {
+                validate::media_type(&media_types, &song.media_type)
+                    .map_err(|error| error.to_string())?;
+                Ok(song)
+            }) {
+                Ok(song) => {
+                    let status = match existing_song {
+                        Some(existing) if same_fields(existing, &song) => TrackStatus::Unchanged,
+                        Some(_) => TrackStatus::Changed,
+                        None => TrackStatus::New,
+                    };
+                    scan.tracks.push(Track {
+                        status,
+                        file: ScannedFile {
+                            song_id: existing_song.map(|existing| existing.id),
+                            ..file
+                        },
+                        song,
+                        read: true,
+                    });
+                }
+                Err(error) => scan.failures.push((path, error)),
+            },
+        }
+    }
+    Ok(scan)
+}
+
+// collects the audio files in the folder and every folder below it
+fn find_audio_files(folder: &Path, paths: &mut Vec<PathBuf>) -> std::io::Result<()> {
+    for entry in fs::read_dir(folder)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            find_audio_files(&path, paths)?;
+        } else if path.extension().is_some_and(|extension| {
+            EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str())
+        }) {
+            paths.push(path);
+        }
+    }
+    Ok(())
+}
+
+// reads a song from the file's tags. Missing tags get placeholders instead of failing the file.
+pub fn read_tags(path: &Path) -> Result<Song, String> {
+    let tagged_file = lofty::read_from_path(path).map_err(|error| error.to_string())?;
+    let tag = tagged_file
+        .primary_tag()
+        .or_else(|| tagged_file.first_tag());
+    let text = |value: Option<std::borrow::Cow<'_, str>>, max_length: usize| {
+        value
+            .map(|value| value.trim().chars().take(max_length).collect::<String>())
+            .filter(|value| !value.is_empty())
+    };
+
+    let file_name = path
+        .file_stem()
+        .map_or(String::new(), |stem| stem.to_string_lossy().into_owned());
+    let title = tag
+        .and_then(|tag| text(tag.title(), MAX_TITLE_LENGTH))
+        .unwrap_or_else(|| file_name.chars().take(MAX_TITLE_LENGTH).collect());
+    let artist = tag
+        .and_then(|tag| text(tag.artist(), MAX_NAME_LENGTH))
+        .unwrap_or_else(|| "Unknown Artist".to_owned());
+    let album = tag
+        .and_then(|tag| text(tag.album(), MAX_NAME_LENGTH))
+        .unwrap_or_else(|| "Unknown Album".to_owned());
+    // a date tag with an implausible year, like 0001 or 9999, is as good as none
+    let release_year = tag
+        .and_then(|tag| tag.date())
+        .and_then(|date| validate::release_year(i32::from(date.year)).ok())
+        .unwrap_or(UNKNOWN_YEAR);
+    let media_type = media_type(tagged_file.file_type());
+
+    Ok(Song::new(
+        0,
+        &title,
+        &artist,
+        &album,
+        release_year,
+        media_type,
+    ))
+}
+
+// the media type a container is filed under: lossless files by their format, lossy ones as downloads
+pub fn media_type(file_type: FileType) -> &'static str {
+    match file_type {
+        FileType::Flac => "FLAC",
+        _ => "Digital Download",
+    }
+}
+
+// true if the tags say the same thing as the song already in the library
+fn same_fields(existing: &Song, scanned: &Song) -> bool {
+    existing.title == scanned.title
+        && existing.artist.eq_ignore_ascii_case(&scanned.artist)
+        && existing.album.eq_ignore_ascii_case(&scanned.album)
+        && existing.release_year == scanned.release_year
+        && existing.media_type == scanned.media_type
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::SqliteStore;
+    use lofty::{
+        config::WriteOptions,
+        tag::{Tag, TagType},
+    };
+
+    // a FLAC file with no audio, just the STREAMINFO block, tagged with the given values
+    fn write_flac(path: &Path, title: &str, artist: &str, album: &str, year: u16) {
+        let mut bytes = b"fLaC".to_vec();
+        bytes.extend([0x80, 0, 0, 34]); // last metadata block, STREAMINFO, 34 bytes
+        bytes.extend([0x10, 0, 0x10, 0]); // block sizes
+        bytes.extend([0; 6]); // frame sizes
+        bytes.extend([0x0a, 0xc4, 0x42, 0xf0]); // 44.1kHz, stereo, 16 bits
+        bytes.extend([0; 4]); // no samples
+        bytes.extend([0; 16]); // MD5
+        fs::write(path, bytes).unwrap();
+
+        let mut tag = Tag::new(TagType::VorbisComments);
+        tag.set_title(title.to_owned());
+        tag.set_artist(artist.to_owned());
+        tag.set_album(album.to_owned());
+        tag.insert_text(ItemKey::Year, year.to_string());
+        tag.save_to_path(path, WriteOptions::default()).unwrap();
+    }
+
+    fn temp_folder(name: &str) -> PathBuf {
+        let folder =
+            std::env::temp_dir().join(format!("music-library-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&folder);
+        fs::create_dir_all(folder.join("Paranoid")).unwrap();
+        folder
+    }
+
+    #[test]
+    fn test_read_tags() {
+        let folder = temp_folder("tags");
+        let path = folder.join("Paranoid").join("01.flac");
+        write_flac(&path, "War Pigs", "Black Sabbath", "Paranoid", 1970);
+        assert_eq!(
+            read_tags(&path).unwrap(),
+            Song::new(0, "War Pigs", "Black Sabbath", "Paranoid", 1970, "FLAC")
+        );
+        assert_eq!(media_type(FileType::Mpeg), "Digital Download");
+        fs::remove_dir_all(folder).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rescans_only_read_changed_files() {
+        let store = SqliteStore::connect("sqlite::memory:").await.unwrap();
+        let folder = temp_folder("rescan");
+        let war_pigs = folder.join("Paranoid").join("01.flac");
+        write_flac(&war_pigs, "War Pigs", "Black Sabbath", "Paranoid", 1970);
+        write_flac(
+            &folder.join("02.FLAC"),
+            "Iron Man",
+            "Black Sabbath",
+            "Paranoid",
+            1970,
+        );
+        fs::write(folder.join("cover.jpg"), "not audio").unwrap();
+        fs::write(folder.join("broken.mp3"), "not audio either").unwrap();
+
+        let scan = scan_folder(&store, &folder).await.unwrap();
+        assert_eq!(scan.count(TrackStatus::New), 2);
+        assert_eq!(scan.failures.len(), 1);
+        assert_eq!(scan.save(&store).await.unwrap(), 2);
+        assert_eq!(store.get_all_songs().await.unwrap().len(), 2);
+
+        let scan = scan_folder(&store, &folder).await.unwrap();
+        assert_eq!(scan.count(TrackStatus::Unchanged), 2);
+        assert!(scan.tracks.iter().all(|track| !track.read));
+
+        // retagging changes the size, so the file is read again and its song updated
+        write_flac(
+            &war_pigs,
+            "War Pigs (Remastered)",
+            "Black Sabbath",
+            "Paranoid",
+            1970,
+        );
+        let scan = scan_folder(&store, &folder).await.unwrap();
+        assert_eq!(scan.count(TrackStatus::Changed), 1);
+        scan.save(&store).await.unwrap();
+        let titles: Vec<String> = store
+            .get_all_songs()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|song| song.title)
+            .collect();
+        assert!(titles.contains(&"War Pigs (Remastered)".to_owned()));
+        assert_eq!(titles.len(), 2);
+
+        // a deleted song stays deleted while its file is unchanged
+        let id = store.get_all_songs().await.unwrap()[0].id;
+        store.delete_song(id).await.unwrap();
+        let scan = scan_folder(&store, &folder).await.unwrap();
+        assert_eq!(scan.tracks.len(), 1);
+
+        fs::remove_dir_all(folder).unwrap();
+    }
+}
@@ -1,10 +1,17 @@
 // This is the program's model of a Song. Most of this is self explanatory.
+// Songs point at their artist and album by id; the names come along from a join so the UI can show them.
+// A song built from user input has ids of 0 until the database resolves its names.
+// Serialized (for CLI output) it's just the columns a user sees, without the artist and album ids.
 
-#[derive(Debug, Clone, sqlx::FromRow)]
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow, serde::Serialize)]
 pub struct Song {
     pub id: u32,
     pub title: String,
+    #[serde(skip)]
+    pub artist_id: u32,
     pub artist: String,
+    #[serde(skip)]
+    pub album_id: u32,
     pub album: String,
     pub release_year: i32,
     pub media_type: String,
@@ -19,23 +26,88 @@ impl Song {
         media_type: &str,
     ) -> Self {
         Self {
-            id: id,
+            id,
             title: title.to_owned(),
+            artist_id: 0,
             artist: artist.to_owned(),
+            album_id: 0,
             album: album.to_owned(),
-            release_year: release_year,
+            release_year,
             media_type: media_type.to_owned(),
         }
     }
 
+    // whether any value a user sees is different, whatever the artist and album ids
+    pub fn differs_from(&self, other: &Song) -> bool {
+        (
+            &self.title,
+            &self.artist,
+            &self.album,
+            self.release_year,
+            &self.media_type,
+        ) != (
+            &other.title,
+            &other.artist,
+            &other.album,
+            other.release_year,
+            &other.media_type,
+        )
+    }
+
+    // a song for tests where any will do
+    #[cfg(test)]
     pub fn default() -> Self {
         Self {
             id: 0,
             title: "Never Gonna Give You Up".to_owned(),
+            artist_id: 0,
             artist: "Rick Astley".to_owned(),
+            album_id: 0,
             album: "Whenever You Need Somebody".to_owned(),
             release_year: 1987,
             media_type: "Vinyl".to_owned(),
         }
     }
 }
+
+// some of a song's fields, like the ones given to the edit command or filled in for a bulk edit.
+// Anything left out is None and stays as it is.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SongFields {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub release_year: Option<i32>,
+    pub media_type: Option<String>,
+}
+impl SongFields {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // the song with the given fields replaced
+    pub fn apply_to(&self, mut song: Song) -> Song {
+        if let Some(title) = &self.title {
+            song.title = title.clone();
+        }
+        if let Some(artist) = &self.artist {
+            song.artist = artist.clone();
+        }
+        if let Some(album) = &self.album {
+            song.album = album.clone();
+        }
+        song.release_year = self.release_year.unwrap_or(song.release_year);
+        if let Some(media_type) = &self.media_type {
+            song.media_type = media_type.clone();
+        }
+        song
+    }
+}
+
+// a song in the trash, with when it was put there ("YYYY-MM-DD HH:MM:SS" in UTC)
+#[derive(Debug, Clone, PartialEq, sqlx::FromRow)]
+pub struct TrashedSong {
+    #[sqlx(flatten)]
+    pub song: Song,
+    pub deleted_at: String,
+}
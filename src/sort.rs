@@ -0,0 +1,138 @@
+// The order the songs table is sorted in. Sorting happens in the database's ORDER BY, so a table
+// that only loads the songs on screen still shows them in order. Songs that tie on the column
+// sorted by are ordered by the column sorted by before it, then by id. Until a column is picked,
+// a search's results are ranked by relevance on a backend that can tell.
+
+use crate::import::Field;
+
+// one column to sort by, and which way
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SortKey {
+    pub field: Field,
+    pub descending: bool,
+}
+
+// the columns to sort by, most important first. No columns means the order songs were added in,
+// or best match first for a search ranked by relevance.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Sort {
+    keys: Vec<SortKey>,
+}
+
+// an artist's leading "The", "A" or "An" is skipped, so The Beatles sort under B.
+// LIKE ignores case on both backends, and text is compared in lowercase so SQLite agrees with MariaDB.
+const ARTIST_SORT_NAME: &str = "LOWER(CASE \
+    WHEN ar.name LIKE 'The %' THEN SUBSTR(ar.name, 5) \
+    WHEN ar.name LIKE 'An %' THEN SUBSTR(ar.name, 4) \
+    WHEN ar.name LIKE 'A %' THEN SUBSTR(ar.name, 3) \
+    ELSE ar.name END)";
+
+// how well a song matches a search's words by MariaDB's FULLTEXT index, the ? being the words.
+// Higher is better, and songs that don't contain any of the words score 0.
+pub const RELEVANCE: &str = "MATCH(s.search_text) AGAINST(? IN NATURAL LANGUAGE MODE)";
+
+// the expression a field is sorted by, over the columns of SELECT_SONGS
+fn expression(field: Field) -> &'static str {
+    match field {
+        Field::Title => "LOWER(s.title)",
+        Field::Artist => ARTIST_SORT_NAME,
+        Field::Album => "LOWER(al.title)",
+        Field::ReleaseYear => "s.release_year",
+        Field::MediaType => "LOWER(s.media_type)",
+    }
+}
+
+impl Sort {
+    // the direction a field is sorted in, and whether it's the column sorted by first
+    pub fn direction(&self, field: Field) -> Option<(bool, bool)> {
+        self.keys
+            .iter()
+            .position(|key| key.field == field)
+            .map(|index| (self.keys[index].descending, index == 0))
+    }
+
+    // sorting by the column sorted by already turns it around. Another column is sorted by
+    // ascending, and the one sorted by until now breaks its ties.
+    pub fn toggle(&mut self, field: Field) {
+        match self.keys.first_mut() {
+            Some(first) if first.field == field => first.descending = !first.descending,
+            _ => {
+                self.keys.retain(|key| key.field != field);
+                self.keys.insert(
+                    0,
+                    SortKey {
+                        field,
+                        descending: false,
+                    },
+                );
+                self.keys.truncate(2);
+            }
+        }
+    }
+
+    // the expressions to order SELECT_SONGS by and whether each is descending, ending with the id
+    // so songs that tie on every column still have a fixed order to page through.
+    // ranked is whether the search has words to rank by RELEVANCE, which comes first without columns.
+    pub fn order_by(&self, ranked: bool) -> Vec<(&'static str, bool)> {
+        let relevance = (ranked && self.keys.is_empty()).then_some((RELEVANCE, true));
+        relevance
+            .into_iter()
+            .chain(
+                self.keys
+                    .iter()
+                    .map(|key| (expression(key.field), key.descending)),
+            )
+            .chain([("s.id", false)])
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toggle() {
+        let mut sort = Sort::default();
+        assert_eq!(sort.order_by(false), vec![("s.id", false)]);
+        // a search with words is best match first until a column is picked
+        assert_eq!(
+            sort.order_by(true),
+            vec![(RELEVANCE, true), ("s.id", false)]
+        );
+
+        sort.toggle(Field::Artist);
+        sort.toggle(Field::ReleaseYear);
+        sort.toggle(Field::ReleaseYear);
+        // newest first, and songs from the same year by artist
+        assert_eq!(
+            sort.order_by(false),
+            vec![
+                ("s.release_year", true),
+                (ARTIST_SORT_NAME, false),
+                ("s.id", false)
+            ]
+        );
+        assert_eq!(sort.order_by(true), sort.order_by(false));
+        assert_eq!(sort.direction(Field::ReleaseYear), Some((true, true)));
+        assert_eq!(sort.direction(Field::Artist), Some((false, false)));
+
+        // a third column pushes the oldest one out, and sorting by the second brings it to the front
+        sort.toggle(Field::Title);
+        assert_eq!(sort.direction(Field::Artist), None);
+        sort.toggle(Field::ReleaseYear);
+        assert_eq!(
+            sort.keys,
+            vec![
+                SortKey {
+                    field: Field::ReleaseYear,
+                    descending: false
+                },
+                SortKey {
+                    field: Field::Title,
+                    descending: false
+                }
+            ]
+        );
+    }
+}
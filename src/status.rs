@@ -0,0 +1,53 @@
+// The status bar under the table, which says what the last action did or why it failed.
+// A message goes away by itself after a while, errors last longest so there's time to read them.
+
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Success,
+    Info,
+    Warning,
+    Error,
+}
+impl Severity {
+    // how long a message of this severity stays up
+    fn duration(self) -> Duration {
+        match self {
+            Self::Success | Self::Info => Duration::from_secs(3),
+            Self::Warning => Duration::from_secs(5),
+            Self::Error => Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Status {
+    pub severity: Severity,
+    pub message: String,
+    pub expires_at: Instant, // when the message is taken down
+}
+impl Status {
+    pub fn new(severity: Severity, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            message: message.into(),
+            expires_at: Instant::now() + severity.duration(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_stay_up_longest() {
+        let now = Instant::now();
+        let success = Status::new(Severity::Success, "Added Paranoid");
+        let error = Status::new(Severity::Error, "Error: Could not add the song");
+        assert!(success.expires_at > now);
+        assert!(success.expires_at < error.expires_at);
+        assert!(success.expires_at <= Instant::now() + Duration::from_secs(3));
+    }
+}
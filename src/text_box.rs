@@ -1,7 +1,13 @@
 // This module is sourced from https://github.com/ratatui-org/ratatui/blob/main/examples/user_input.rs
 // Thank you to joshka from the Ratatui discord server for the recommendation
 
-use ratatui::{style::Stylize, text::Text, widgets::Paragraph};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    prelude::*,
+    widgets::{Block, Paragraph},
+};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 #[derive(Debug, PartialEq, PartialOrd, Clone)]
 pub enum InputMode {
@@ -16,12 +22,14 @@ pub struct TextBox {
     title: String,
     /// Current value of the input box
     input: String,
-    /// Position of cursor in the editor area.
+    /// Position of cursor in the editor area, in graphemes (what the user sees as one character)
     cursor_position: usize,
     /// Current input mode
     input_mode: InputMode,
     /// History of recorded messages
     messages: Vec<String>,
+    /// Only takes digits, for years
+    numeric: bool,
 }
 
 impl TextBox {
@@ -32,49 +40,118 @@ impl TextBox {
             input_mode: InputMode::Normal,
             messages: Vec::new(),
             cursor_position: 0,
+            numeric: false,
         }
     }
 
-    pub fn move_cursor_left(&mut self) {
-        let cursor_moved_left = self.cursor_position.saturating_sub(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_left);
+    /// A box that ignores anything typed or pasted into it but digits
+    pub const fn numeric(mut self) -> Self {
+        self.numeric = true;
+        self
     }
 
-    pub fn move_cursor_right(&mut self) {
-        let cursor_moved_right = self.cursor_position.saturating_add(1);
-        self.cursor_position = self.clamp_cursor(cursor_moved_right);
+    /// Edits the input with a key: typing, Backspace, Delete, Ctrl-W (the word before the cursor),
+    /// Ctrl-U (everything before the cursor), Left and Right (Ctrl for a word at a time), Home and End.
+    /// Returns false if the key isn't one for editing, so the caller can use it for something else.
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> bool {
+        let control = key_event.modifiers.contains(KeyModifiers::CONTROL);
+        match key_event.code {
+            KeyCode::Char('w') if control => self.delete_word(),
+            KeyCode::Char('u') if control => self.delete_to_start(),
+            KeyCode::Char(_) if control => return false,
+            KeyCode::Char(new_char) => self.enter_char(new_char),
+            KeyCode::Backspace => self.delete_char(),
+            KeyCode::Delete => self.delete_char_forward(),
+            KeyCode::Left if control => self.move_cursor_to(self.word_start()),
+            KeyCode::Right if control => self.move_cursor_to(self.word_end()),
+            KeyCode::Left => self.move_cursor_to(self.cursor_position.saturating_sub(1)),
+            KeyCode::Right => self.move_cursor_to(self.cursor_position + 1),
+            KeyCode::Home => self.move_cursor_to(0),
+            KeyCode::End => self.move_cursor_to(usize::MAX),
+            _ => return false,
+        }
+        true
     }
 
     pub fn enter_char(&mut self, new_char: char) {
-        self.input.insert(self.cursor_position, new_char);
+        self.insert_str(new_char.encode_utf8(&mut [0; 4]));
+    }
 
-        self.move_cursor_right();
+    /// Inserts pasted text at the cursor. Line breaks and tabs become spaces, since a box holds one line.
+    pub fn insert_str(&mut self, text: &str) {
+        let text: String = text
+            .trim_end_matches(['\r', '\n'])
+            .chars()
+            .map(|c| if c.is_control() { ' ' } else { c })
+            .filter(|c| !self.numeric || c.is_ascii_digit())
+            .collect();
+        let index = self.byte_index(self.cursor_position);
+        self.input.insert_str(index, &text);
+        // counted again rather than added, since a combining accent joins the character before it
+        self.cursor_position = self.input[..index + text.len()].graphemes(true).count();
     }
 
     pub fn delete_char(&mut self) {
-        let is_not_cursor_leftmost = self.cursor_position != 0;
-        if is_not_cursor_leftmost {
-            // Method "remove" is not used on the saved text for deleting the selected char.
-            // Reason: Using remove on String works on bytes instead of the chars.
-            // Using remove would require special care because of char boundaries.
-
-            let current_index = self.cursor_position;
-            let from_left_to_current_index = current_index - 1;
-
-            // Getting all characters before the selected character.
-            let before_char_to_delete = self.input.chars().take(from_left_to_current_index);
-            // Getting all characters after selected character.
-            let after_char_to_delete = self.input.chars().skip(current_index);
-
-            // Put all characters together except the selected one.
-            // By leaving the selected one out, it is forgotten and therefore deleted.
-            self.input = before_char_to_delete.chain(after_char_to_delete).collect();
-            self.move_cursor_left();
+        if self.cursor_position > 0 {
+            self.delete_range(self.cursor_position - 1, self.cursor_position);
+        }
+    }
+
+    fn delete_char_forward(&mut self) {
+        self.delete_range(self.cursor_position, self.cursor_position + 1);
+    }
+
+    fn delete_word(&mut self) {
+        self.delete_range(self.word_start(), self.cursor_position);
+    }
+
+    fn delete_to_start(&mut self) {
+        self.delete_range(0, self.cursor_position);
+    }
+
+    // removes the graphemes from start up to end and leaves the cursor where they were
+    fn delete_range(&mut self, start: usize, end: usize) {
+        let range = self.byte_index(start)..self.byte_index(end);
+        self.input.replace_range(range, "");
+        self.move_cursor_to(start);
+    }
+
+    fn move_cursor_to(&mut self, position: usize) {
+        self.cursor_position = position.min(self.input.graphemes(true).count());
+    }
+
+    // where the word before the cursor starts, after any spaces between them
+    fn word_start(&self) -> usize {
+        let graphemes: Vec<&str> = self.input.graphemes(true).collect();
+        let mut position = self.cursor_position.min(graphemes.len());
+        while position > 0 && is_space(graphemes[position - 1]) {
+            position -= 1;
+        }
+        while position > 0 && !is_space(graphemes[position - 1]) {
+            position -= 1;
         }
+        position
     }
 
-    fn clamp_cursor(&self, new_cursor_pos: usize) -> usize {
-        new_cursor_pos.clamp(0, self.input.len())
+    // where the word after the cursor ends, after any spaces before it
+    fn word_end(&self) -> usize {
+        let graphemes: Vec<&str> = self.input.graphemes(true).collect();
+        let mut position = self.cursor_position;
+        while position < graphemes.len() && is_space(graphemes[position]) {
+            position += 1;
+        }
+        while position < graphemes.len() && !is_space(graphemes[position]) {
+            position += 1;
+        }
+        position
+    }
+
+    // the byte the grapheme at a position starts at, or the end of the input
+    fn byte_index(&self, position: usize) -> usize {
+        self.input
+            .grapheme_indices(true)
+            .nth(position)
+            .map_or(self.input.len(), |(index, _)| index)
     }
 
     fn reset_cursor(&mut self) {
@@ -102,28 +179,144 @@ impl TextBox {
         self.messages.clone()
     }
 
+    /// Replaces the input, with the cursor at the end
     pub fn set_input(&mut self, input: String) {
-        self.input += &input;
-        self.cursor_position += input.len();
+        self.input = input;
+        self.move_cursor_to(usize::MAX);
+    }
+
+    pub fn is_cursor_at_end(&self) -> bool {
+        self.cursor_position == self.input.graphemes(true).count()
     }
 
     pub fn clear_input(&mut self) {
         self.input.clear();
+        self.reset_cursor();
+    }
+
+    // the grapheme the visible part of the input starts at and the column the cursor is in,
+    // for a box this many columns wide. It scrolls just far enough to keep the cursor in view.
+    fn scroll(&self, width: usize) -> (usize, usize) {
+        let graphemes: Vec<&str> = self.input.graphemes(true).collect();
+        let (mut start, mut column) = (0, 0);
+        for grapheme in &graphemes[..self.cursor_position.min(graphemes.len())] {
+            column += grapheme.width();
+        }
+        // leave a column after the last character for the cursor to sit in
+        while column >= width && start < self.cursor_position {
+            column -= graphemes[start].width();
+            start += 1;
+        }
+        (start, column)
+    }
+
+    /// Draws the box in a block, scrolled to the cursor, and puts the terminal's cursor in it
+    /// when it's being edited. Turns yellow in Editing mode.
+    pub fn render(&self, frame: &mut Frame, block: Block, area: Rect) {
+        self.render_with_completion(frame, block, area, "");
+    }
+
+    /// Like render, with the rest of a suggested value shown faintly after the input
+    pub fn render_with_completion(
+        &self,
+        frame: &mut Frame,
+        block: Block,
+        area: Rect,
+        completion: &str,
+    ) {
+        let inner = block.inner(area);
+        let label = format!(" {}: ", self.title);
+        let width = usize::from(inner.width).saturating_sub(label.width());
+        let (start, column) = self.scroll(width);
+        let visible: String = self.input.graphemes(true).skip(start).collect();
+        let style = match self.input_mode {
+            InputMode::Normal => Style::new(),
+            InputMode::Editing => Style::new().yellow().bold(),
+        };
+        let line = Line::from(vec![
+            Span::styled(label.clone() + &visible, style),
+            Span::styled(completion.to_owned(), Style::new().dark_gray()),
+        ]);
+        frame.render_widget(Paragraph::new(line).left_aligned().block(block), area);
+        if self.input_mode == InputMode::Editing && inner.width > 0 && inner.height > 0 {
+            let x = inner.x + (label.width() + column).min(usize::from(inner.width) - 1) as u16;
+            frame.set_cursor(x, inner.y);
+        }
+    }
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().all(char::is_whitespace)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(text_box: &mut TextBox, code: KeyCode, modifiers: KeyModifiers) {
+        text_box.handle_key(KeyEvent::new(code, modifiers));
     }
 
-    // turns the box yellow if in Editing mode
-    pub fn get_widget(&self) -> Paragraph {
-        match self.input_mode {
-            InputMode::Normal => {
-                Paragraph::new(Text::from(format!(" {}: {}", self.title, self.input)))
-                    .left_aligned()
-            }
-            InputMode::Editing => Paragraph::new(
-                Text::from(format!(" {}: {}", self.title, self.input))
-                    .yellow()
-                    .bold(),
-            )
-            .left_aligned(),
+    #[test]
+    fn test_editing_accented_names() {
+        let mut text_box = TextBox::new("Artist".to_owned());
+        for c in "Sigur Ros".chars() {
+            text_box.enter_char(c);
         }
+        // fix the o into an ó with a combining accent, two characters back from the end
+        press(&mut text_box, KeyCode::Left, KeyModifiers::NONE);
+        text_box.enter_char('\u{301}');
+        assert_eq!(text_box.get_input(), "Sigur Ro\u{301}s");
+        press(&mut text_box, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(text_box.get_input(), "Sigur Rs");
+        text_box.enter_char('ó');
+        press(&mut text_box, KeyCode::Home, KeyModifiers::NONE);
+        press(&mut text_box, KeyCode::Delete, KeyModifiers::NONE);
+        text_box.insert_str("Þ");
+        assert_eq!(text_box.get_input(), "Þigur Rós");
+
+        text_box.set_input("Björk".to_owned());
+        assert_eq!(text_box.get_input(), "Björk");
+        press(&mut text_box, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut text_box, KeyCode::Left, KeyModifiers::NONE);
+        press(&mut text_box, KeyCode::Backspace, KeyModifiers::NONE);
+        assert_eq!(text_box.get_input(), "Bjrk");
+        text_box.clear_input();
+        text_box.enter_char('x');
+        assert_eq!(text_box.get_input(), "x");
+    }
+
+    #[test]
+    fn test_words_and_paste() {
+        let mut text_box = TextBox::new("Title".to_owned());
+        text_box.insert_str("Hey Jude\r\n");
+        text_box.insert_str(" (Remastered\t2015)");
+        assert_eq!(text_box.get_input(), "Hey Jude (Remastered 2015)");
+        press(&mut text_box, KeyCode::Char('w'), KeyModifiers::CONTROL);
+        assert_eq!(text_box.get_input(), "Hey Jude (Remastered ");
+        press(&mut text_box, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut text_box, KeyCode::Left, KeyModifiers::CONTROL);
+        press(&mut text_box, KeyCode::Right, KeyModifiers::CONTROL);
+        press(&mut text_box, KeyCode::Char('u'), KeyModifiers::CONTROL);
+        assert_eq!(text_box.get_input(), " (Remastered ");
+        assert!(!text_box.handle_key(KeyEvent::new(KeyCode::Char('f'), KeyModifiers::CONTROL)));
+
+        let mut year_box = TextBox::new("Year".to_owned()).numeric();
+        year_box.insert_str("c. 1971");
+        assert_eq!(year_box.get_input(), "1971");
+    }
+
+    #[test]
+    fn test_scrolls_to_the_cursor() {
+        let mut text_box = TextBox::new("Title".to_owned());
+        text_box.set_input("0123456789".to_owned());
+        // the cursor after the last of 10 characters needs an 11th column
+        assert_eq!(text_box.scroll(11), (0, 10));
+        assert_eq!(text_box.scroll(8), (3, 7));
+        press(&mut text_box, KeyCode::Home, KeyModifiers::NONE);
+        assert_eq!(text_box.scroll(8), (0, 0));
+        // wide characters take two columns each
+        text_box.set_input("東京事変".to_owned());
+        assert_eq!(text_box.scroll(5), (2, 4));
     }
 }
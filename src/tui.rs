@@ -1,6 +1,13 @@
-use std::io::{self, stdout, Stdout};
+use std::{
+    io::{self, stdout, Stdout},
+    panic,
+};
 
-use crossterm::{execute, terminal::*};
+use crossterm::{
+    event::{DisableBracketedPaste, EnableBracketedPaste},
+    execute,
+    terminal::*,
+};
 use ratatui::prelude::*;
 
 /// A type alias for the terminal type used in this application
@@ -8,14 +15,21 @@ pub type Tui = Terminal<CrosstermBackend<Stdout>>;
 
 /// Initialize the terminal
 pub fn init() -> io::Result<Tui> {
-    execute!(stdout(), EnterAlternateScreen)?;
+    // a panic restores the terminal first, so its message isn't lost on the alternate screen
+    let panic_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        let _ = restore();
+        panic_hook(info);
+    }));
+    // pasted text arrives as one event instead of a key per character
+    execute!(stdout(), EnterAlternateScreen, EnableBracketedPaste)?;
     enable_raw_mode()?;
     Terminal::new(CrosstermBackend::new(stdout()))
 }
 
 /// Restore the terminal to its original state
 pub fn restore() -> io::Result<()> {
-    execute!(stdout(), LeaveAlternateScreen)?;
+    execute!(stdout(), DisableBracketedPaste, LeaveAlternateScreen)?;
     disable_raw_mode()?;
     Ok(())
 }
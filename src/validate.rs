@@ -0,0 +1,226 @@
+// Checks a song's values before they're written, the same way wherever they come from: the song
+// popups, the add and edit commands, CSV imports and folder scans. Values are trimmed first, and
+// the longest ones allowed are the ones the Songs, Artists and Albums columns can hold.
+
+use crate::{
+    audit,
+    import::Field,
+    media_type::MediaType,
+    song::{Song, SongFields},
+};
+use std::fmt;
+
+pub const MAX_TITLE_LENGTH: usize = 60;
+pub const MAX_NAME_LENGTH: usize = 200;
+pub const MAX_MEDIA_TYPE_LENGTH: usize = 60;
+// the earliest release year that isn't a typo, the latest is next year for announced releases
+pub const MIN_YEAR: i32 = 1800;
+// the year of a song nobody knows the year of, like a scanned file without a date tag
+pub const UNKNOWN_YEAR: i32 = 0;
+
+// what's wrong with the value of one field
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: Field,
+    pub message: String,
+}
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.field.name(), self.message)
+    }
+}
+impl std::error::Error for FieldError {}
+
+fn error(field: Field, message: String) -> FieldError {
+    FieldError { field, message }
+}
+
+// the longest a field's text can be
+pub fn max_length(field: Field) -> usize {
+    match field {
+        Field::Title => MAX_TITLE_LENGTH,
+        Field::Artist | Field::Album => MAX_NAME_LENGTH,
+        Field::ReleaseYear | Field::MediaType => MAX_MEDIA_TYPE_LENGTH,
+    }
+}
+
+// checks a text field, returns it trimmed
+pub fn text(field: Field, value: &str) -> Result<String, FieldError> {
+    let value = value.trim();
+    let length = value.chars().count();
+    if length == 0 {
+        return Err(error(field, "is empty".to_owned()));
+    }
+    if length > max_length(field) {
+        return Err(error(
+            field,
+            format!("is longer than {} characters", max_length(field)),
+        ));
+    }
+    Ok(value.to_owned())
+}
+
+// checks a release year is plausible
+pub fn release_year(year: i32) -> Result<i32, FieldError> {
+    let latest = audit::current_year() + 1;
+    if year != UNKNOWN_YEAR && !(MIN_YEAR..=latest).contains(&year) {
+        return Err(error(
+            Field::ReleaseYear,
+            format!(
+                "must be between {} and {}, or {} if unknown",
+                MIN_YEAR, latest, UNKNOWN_YEAR
+            ),
+        ));
+    }
+    Ok(year)
+}
+
+// checks a release year typed as text
+pub fn parse_year(value: &str) -> Result<i32, FieldError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(error(Field::ReleaseYear, "is empty".to_owned()));
+    }
+    let year = value
+        .parse()
+        .map_err(|_| error(Field::ReleaseYear, format!("\"{}\" is not a number", value)))?;
+    release_year(year)
+}
+
+// checks a media type is on the list, by its name or one of its aliases ignoring case
+pub fn media_type(media_types: &[MediaType], name: &str) -> Result<(), FieldError> {
+    let lowercase = name.trim().to_lowercase();
+    let matches = |value: &String| value.to_lowercase() == lowercase;
+    if media_types
+        .iter()
+        .any(|media_type| matches(&media_type.name) || media_type.aliases.iter().any(matches))
+    {
+        Ok(())
+    } else {
+        Err(unknown_media_type(name))
+    }
+}
+
+// the error for a media type that is neither a type on the list nor an alias of one
+pub fn unknown_media_type(name: &str) -> FieldError {
+    error(
+        Field::MediaType,
+        format!("\"{}\" is not on the list of media types", name.trim()),
+    )
+}
+
+// checks one field typed as text, as the text it will be saved as
+fn parse_field(field: Field, value: &str) -> Result<String, FieldError> {
+    match field {
+        Field::ReleaseYear => parse_year(value).map(|year| year.to_string()),
+        _ => text(field, value),
+    }
+}
+
+// a song from the text of every field, in the order of Field::ALL, or every problem with them
+pub fn parse_song(values: [&str; 5]) -> Result<Song, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut checked = Vec::new();
+    for (field, value) in Field::ALL.into_iter().zip(values) {
+        match parse_field(field, value) {
+            Ok(value) => checked.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(Song::new(
+        0,
+        &checked[0],
+        &checked[1],
+        &checked[2],
+        checked[3].parse().unwrap_or(UNKNOWN_YEAR),
+        &checked[4],
+    ))
+}
+
+// like parse_song, for an edit that changes some fields and leaves the empty ones as they are
+pub fn parse_fields(values: [&str; 5]) -> Result<SongFields, Vec<FieldError>> {
+    let mut errors = Vec::new();
+    let mut fields = SongFields::default();
+    for (field, value) in Field::ALL.into_iter().zip(values) {
+        if value.trim().is_empty() {
+            continue;
+        }
+        let checked = match parse_field(field, value) {
+            Ok(checked) => checked,
+            Err(error) => {
+                errors.push(error);
+                continue;
+            }
+        };
+        match field {
+            Field::Title => fields.title = Some(checked),
+            Field::Artist => fields.artist = Some(checked),
+            Field::Album => fields.album = Some(checked),
+            Field::ReleaseYear => fields.release_year = checked.parse().ok(),
+            Field::MediaType => fields.media_type = Some(checked),
+        }
+    }
+    match errors.is_empty() {
+        true => Ok(fields),
+        false => Err(errors),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_are_trimmed_and_checked() {
+        let song = parse_song([
+            "  War Pigs ",
+            "Black Sabbath",
+            "Paranoid",
+            " 1970",
+            "Vinyl ",
+        ]);
+        assert_eq!(
+            song,
+            Ok(Song::new(
+                0,
+                "War Pigs",
+                "Black Sabbath",
+                "Paranoid",
+                1970,
+                "Vinyl"
+            ))
+        );
+
+        let long_title = "x".repeat(61);
+        let errors = parse_song([&long_title, " ", "Paranoid", "19700", "Vinyl"]).unwrap_err();
+        let messages: Vec<String> = errors.iter().map(ToString::to_string).collect();
+        assert_eq!(messages[0], "title is longer than 60 characters");
+        assert_eq!(messages[1], "artist is empty");
+        assert!(messages[2].starts_with("year must be between 1800 and "));
+        assert_eq!(messages.len(), 3);
+        // a title of 60 characters fits, however many bytes they take
+        assert!(text(Field::Title, &"é".repeat(60)).is_ok());
+        assert_eq!(
+            parse_year("99999999999").unwrap_err().message,
+            "\"99999999999\" is not a number"
+        );
+        assert_eq!(release_year(UNKNOWN_YEAR), Ok(0));
+    }
+
+    #[test]
+    fn test_empty_fields_are_left_out_of_an_edit() {
+        let fields = parse_fields(["", "", " ", "", " LP "]).unwrap();
+        assert_eq!(
+            fields,
+            SongFields {
+                media_type: Some("LP".to_owned()),
+                ..Default::default()
+            }
+        );
+        let errors = parse_fields(["", "", "", "1492", ""]).unwrap_err();
+        assert_eq!(errors[0].field, Field::ReleaseYear);
+    }
+}